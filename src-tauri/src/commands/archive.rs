@@ -1,87 +1,2315 @@
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::{self, File};
-use std::io::{self, Cursor};
+use std::io::{self, BufReader, Cursor, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_8};
 use memchr::memmem::Finder;
-use memmap2::MmapOptions;
-use sevenz_rust2::{Password, decompress_with_extract_fn_and_password, default_entry_extract_fn};
+use memmap2::{Mmap, MmapOptions};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sevenz_rust2::{ArchiveEntry, ArchiveReader, Password};
+use tauri::Emitter;
 use walkdir::WalkDir;
 use zip::read::ZipArchive;
+use zip::result::ZipError;
+
+use super::download::CANCELLED;
+use super::lzh::{self, LzhEntry};
+use super::replace::{self, DeferredFile, LockedFilePolicy};
+use super::transaction;
+
+// 暗号化された書庫で UI がパスワードを尋ねられるよう、原因ごとに決まった文字列で失敗させる
+pub const PASSWORD_REQUIRED: &str = "PASSWORD_REQUIRED";
+pub const WRONG_PASSWORD: &str = "WRONG_PASSWORD";
+
+/// 展開先の外に出るエントリの扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UnsafeEntryPolicy {
+    // 1 つでもあれば何も書き込まずにエラーにする
+    #[default]
+    Reject,
+    // 飛ばして rejected に記録する
+    Skip,
+}
+
+/// 展開するエントリの絞り込み（インストール手順の extract / extractSfx と共通）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EntrySelection {
+    // このフォルダ以下のエントリだけを展開し、フォルダ自体は取り除く
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subpath: Option<String>,
+    // tar --strip-components と同様に先頭のフォルダを取り除く（subpath を取り除いた後に適用する）
+    #[serde(skip_serializing_if = "is_zero")]
+    pub strip_components: usize,
+    // "/" を含むパターンは subpath からの相対パス、含まないパターンはいずれかのフォルダ名・ファイル名と照合する
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ExtractOptions {
+    pub unsafe_entries: UnsafeEntryPolicy,
+    // zip のエントリ名の文字コード（省略時は自動判定）
+    pub encoding: Option<String>,
+    // 暗号化された zip / 7z のパスワード
+    pub password: Option<String>,
+    pub limits: ExtractLimits,
+    #[serde(flatten)]
+    pub selection: EntrySelection,
+    pub nested: Option<NestedExtract>,
+    // 上書きするファイルが起動中の AviUtl2 などに使われている場合の扱い
+    pub on_locked: LockedFilePolicy,
+}
+
+/// 展開したファイルのうち pattern に一致する書庫を、同じ場所のフォルダにさらに展開する（Plugin_x64.zip → Plugin_x64/）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NestedExtract {
+    // "/" を含むパターンは展開先からの相対パス、含まないパターンはファイル名と照合する
+    pub pattern: String,
+    // 1 なら最初の展開で出てきた書庫だけ、2 ならその中の書庫まで展開する
+    #[serde(default = "default_nested_depth")]
+    pub max_depth: usize,
+}
+
+fn default_nested_depth() -> usize {
+    1
+}
+
+// 書庫が自分自身を含む場合などに際限なく展開しないための上限
+const MAX_NESTED_DEPTH: usize = 8;
+
+/// 展開量の上限（展開前にヘッダーの値で確かめ、展開中も実際に書き込んだ量で確かめる）
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ExtractLimits {
+    // 展開後の合計サイズ（バイト）
+    pub max_total_bytes: u64,
+    pub max_entries: u64,
+    // 圧縮率（展開後 / 圧縮後）。エントリごとと書庫全体の両方に適用する
+    pub max_ratio: u64,
+    // 展開先から数えたパスの階層数
+    pub max_depth: usize,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        // deflate の圧縮率は最大でも約 1032 倍なので、通常の zip が圧縮率で止められることはない
+        Self { max_total_bytes: 8 << 30, max_entries: 100_000, max_ratio: 2_000, max_depth: 64 }
+    }
+}
+
+const LIMIT_EXCEEDED: &str = "extraction limit exceeded";
+
+// 小さなファイルは圧縮率が極端でも害がないので、この大きさを超えてから圧縮率を確かめる
+const RATIO_MIN_BYTES: u64 = 1 << 20;
+
+impl ExtractLimits {
+    fn check_total(&self, bytes: u64) -> Result<(), String> {
+        if bytes > self.max_total_bytes {
+            return Err(format!("{LIMIT_EXCEEDED}: total size {} bytes is over {} bytes", bytes, self.max_total_bytes));
+        }
+        Ok(())
+    }
+
+    fn check_entries(&self, entries: u64) -> Result<(), String> {
+        if entries > self.max_entries {
+            return Err(format!("{LIMIT_EXCEEDED}: {} entries is over {} entries", entries, self.max_entries));
+        }
+        Ok(())
+    }
+
+    fn check_ratio(&self, what: &str, size: u64, compressed: u64) -> Result<(), String> {
+        if size > RATIO_MIN_BYTES && size / compressed.max(1) > self.max_ratio {
+            return Err(format!("{LIMIT_EXCEEDED}: {} expands {} times (over {} times)", what, size / compressed.max(1), self.max_ratio));
+        }
+        Ok(())
+    }
+
+    fn check_depth(&self, name: &str, rel: &Path) -> Result<(), String> {
+        let depth = rel.components().count();
+        if depth > self.max_depth {
+            return Err(format!("{LIMIT_EXCEEDED}: {} is {} levels deep (over {} levels)", name, depth, self.max_depth));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UnsafeEntryReason {
+    AbsolutePath,
+    DriveLetter,
+    ParentTraversal,
+    InvalidName,
+    Symlink,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractedFile {
+    // アーカイブ内のエントリ名
+    pub entry: String,
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedEntry {
+    pub entry: String,
+    pub reason: UnsafeEntryReason,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractReport {
+    pub files: Vec<ExtractedFile>,
+    pub directories: Vec<String>,
+    pub rejected: Vec<RejectedEntry>,
+    // subpath や include / exclude で対象外になったエントリの数
+    pub filtered_out: usize,
+    // 入れ子になっていて続けて展開した書庫
+    pub nested: Vec<NestedArchive>,
+    // 使用中だったため古いファイルを別名に変えて置き換えたファイル
+    pub deferred: Vec<DeferredFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NestedArchive {
+    // 外側の書庫から見たエントリ名（"Release.zip" の中の "Plugin_x64.zip" なら "Plugin_x64.zip"）
+    pub entry: String,
+    pub format: ArchiveFormat,
+    // 展開したフォルダ
+    pub path: String,
+    pub depth: usize,
+}
+
+// Windows で予約されているデバイス名（拡張子付きも不可）
+fn is_reserved_name(part: &str) -> bool {
+    let stem = part.split('.').next().unwrap_or(part).trim_end().to_ascii_uppercase();
+    matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || ((stem.starts_with("COM") || stem.starts_with("LPT")) && stem.len() == 4 && stem.as_bytes()[3].is_ascii_digit() && stem.as_bytes()[3] != b'0')
+}
+
+/// エントリ名を展開先からの相対パスに正規化する（Ok(None) は展開先そのものを指す）
+pub(crate) fn normalize_entry_path(name: &str) -> Result<Option<PathBuf>, UnsafeEntryReason> {
+    let unified = name.replace('\\', "/");
+    if unified.starts_with('/') {
+        return Err(UnsafeEntryReason::AbsolutePath);
+    }
+    let bytes = unified.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        return Err(UnsafeEntryReason::DriveLetter);
+    }
+    let mut parts: Vec<&str> = Vec::new();
+    for part in unified.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.pop().is_none() {
+                    return Err(UnsafeEntryReason::ParentTraversal);
+                }
+            }
+            _ if part.contains(':') || part.chars().any(char::is_control) || is_reserved_name(part) => return Err(UnsafeEntryReason::InvalidName),
+            _ => parts.push(part),
+        }
+    }
+    Ok((!parts.is_empty()).then(|| parts.iter().collect()))
+}
+
+// 7z の属性からシンボリックリンク（Unix のモードまたはリパースポイント）を判定する
+fn is_7z_symlink(entry: &ArchiveEntry) -> bool {
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+    const UNIX_EXTENSION: u32 = 0x8000;
+    if !entry.has_windows_attributes {
+        return false;
+    }
+    let attributes = entry.windows_attributes;
+    attributes & FILE_ATTRIBUTE_REPARSE_POINT != 0 || (attributes & UNIX_EXTENSION != 0 && (attributes >> 16) & 0o170000 == 0o120000)
+}
+
+struct EntryFilter {
+    subpath: Vec<String>,
+    strip_components: usize,
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+}
+
+impl EntryFilter {
+    fn new(selection: &EntrySelection) -> Result<Self, String> {
+        let subpath = match selection.subpath.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            Some(raw) => match normalize_entry_path(raw) {
+                Ok(Some(rel)) => rel.iter().map(|c| c.to_string_lossy().into_owned()).collect(),
+                Ok(None) => Vec::new(),
+                Err(reason) => return Err(format!("invalid subpath {raw} ({reason:?})")),
+            },
+            None => Vec::new(),
+        };
+        let compile = |patterns: &[String]| -> Result<Vec<glob::Pattern>, String> {
+            patterns.iter().map(|p| glob::Pattern::new(&p.replace('\\', "/")).map_err(|e| format!("invalid glob pattern {p}: {e}"))).collect()
+        };
+        Ok(Self {
+            subpath,
+            strip_components: selection.strip_components,
+            include: compile(&selection.include)?,
+            exclude: compile(&selection.exclude)?,
+        })
+    }
+
+    fn matches(patterns: &[glob::Pattern], parts: &[&str]) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        let joined = parts.join("/");
+        patterns.iter().any(|p| if p.as_str().contains('/') { p.matches_with(&joined, options) } else { parts.iter().any(|part| p.matches_with(part, options)) })
+    }
+
+    fn is_under_subpath(&self, parts: &[&str]) -> bool {
+        parts.len() > self.subpath.len() && self.subpath.iter().zip(parts).all(|(a, b)| a.eq_ignore_ascii_case(b))
+    }
+
+    /// 正規化済みの相対パスを展開先からの相対パスに変換する（None は対象外）
+    fn apply(&self, parts: &[&str]) -> Option<PathBuf> {
+        if !self.is_under_subpath(parts) {
+            return None;
+        }
+        let rest = &parts[self.subpath.len()..];
+        if (!self.include.is_empty() && !Self::matches(&self.include, rest)) || Self::matches(&self.exclude, rest) || rest.len() <= self.strip_components {
+            return None;
+        }
+        Some(rest[self.strip_components..].iter().collect())
+    }
+}
+
+/// 全エントリの展開先を先に決め、危険なエントリを policy に従って扱う（戻り値は names と同じ順序で、None は展開しない）
+fn plan_entries(names: &[(String, bool)], options: &ExtractOptions, report: &mut ExtractReport) -> Result<Vec<Option<PathBuf>>, String> {
+    let filter = EntryFilter::new(&options.selection)?;
+    let mut planned = Vec::with_capacity(names.len());
+    let mut under_subpath = false;
+    for (name, symlink) in names {
+        let normalized = if *symlink { Err(UnsafeEntryReason::Symlink) } else { normalize_entry_path(name) };
+        match normalized {
+            Ok(Some(rel)) => {
+                let components: Vec<String> = rel.iter().map(|c| c.to_string_lossy().into_owned()).collect();
+                let parts: Vec<&str> = components.iter().map(String::as_str).collect();
+                under_subpath |= filter.is_under_subpath(&parts);
+                let target = filter.apply(&parts);
+                if target.is_none() {
+                    report.filtered_out += 1;
+                }
+                planned.push(target);
+            }
+            Ok(None) => planned.push(None),
+            Err(reason) => {
+                report.rejected.push(RejectedEntry { entry: name.clone(), reason });
+                planned.push(None);
+            }
+        }
+    }
+    if options.unsafe_entries == UnsafeEntryPolicy::Reject && !report.rejected.is_empty() {
+        let list: Vec<String> = report.rejected.iter().map(|r| format!("{} ({:?})", r.entry, r.reason)).collect();
+        return Err(format!("archive contains unsafe entries: {}", list.join(", ")));
+    }
+    if !filter.subpath.is_empty() && !under_subpath {
+        return Err(format!("subpath {} was not found in the archive", filter.subpath.join("/")));
+    }
+    for rejected in &report.rejected {
+        tracing::warn!("Skipped unsafe archive entry {} ({:?})", rejected.entry, rejected.reason);
+    }
+    Ok(planned)
+}
+
+/// 展開されるファイルのエントリ名と展開先からの相対パスを返す（インストール計画用）
+pub(crate) fn select_archive_entries(names: Vec<String>, selection: &EntrySelection) -> Result<Vec<(String, PathBuf)>, String> {
+    let options = ExtractOptions { selection: selection.clone(), ..Default::default() };
+    let entries: Vec<(String, bool)> = names.into_iter().map(|name| (name, false)).collect();
+    let planned = plan_entries(&entries, &options, &mut ExtractReport::default())?;
+    Ok(entries.into_iter().zip(planned).filter_map(|((name, _), rel)| Some((name, rel?))).collect())
+}
+
+/// 展開の進捗（extract:progress で通知する）
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractProgress {
+    pub entries: u64,
+    pub total_entries: u64,
+    pub bytes: u64,
+    pub total_bytes: u64,
+}
+
+// 大きなエントリを書き込んでいる間の通知間隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+// 実行中の展開（task_id → 中断フラグ）
+static EXTRACT_TASKS: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct EntryWriter<'a> {
+    dest: &'a Path,
+    report: ExtractReport,
+    progress: ExtractProgress,
+    cancel: Option<&'a AtomicBool>,
+    on_progress: &'a mut dyn FnMut(&ExtractProgress),
+    last_emit: Instant,
+    limits: ExtractLimits,
+    on_locked: LockedFilePolicy,
+    archive_size: u64,
+    limit_exceeded: bool,
+    // 中断時に片付けるため、この展開で新しく作ったものを記録する
+    created_files: Vec<PathBuf>,
+    created_dirs: Vec<PathBuf>,
+}
+
+impl<'a> EntryWriter<'a> {
+    fn new(dest: &'a Path, report: ExtractReport, options: &ExtractOptions, cancel: Option<&'a AtomicBool>, on_progress: &'a mut dyn FnMut(&ExtractProgress)) -> Self {
+        Self {
+            dest,
+            report,
+            progress: ExtractProgress::default(),
+            cancel,
+            on_progress,
+            last_emit: Instant::now(),
+            limits: options.limits,
+            on_locked: options.on_locked,
+            archive_size: 0,
+            limit_exceeded: false,
+            created_files: Vec::new(),
+            created_dirs: Vec::new(),
+        }
+    }
+
+    /// 展開するエントリ（名前・相対パス・展開後の大きさ・圧縮後の大きさ）を上限と照らし合わせ、進捗の総量にする
+    /// 圧縮後の大きさがエントリごとに分からない形式では None を渡し、書庫全体の圧縮率だけを確かめる
+    fn set_plan<'e>(&mut self, archive: &Path, entries: impl Iterator<Item = (&'e str, &'e Path, u64, Option<u64>)>) -> Result<(), String> {
+        self.archive_size = fs::metadata(archive).map(|m| m.len()).unwrap_or(0);
+        let (mut count, mut bytes) = (0u64, 0u64);
+        for (name, rel, size, compressed) in entries {
+            self.limits.check_depth(name, rel)?;
+            // 7z（ソリッド書庫）と tar は圧縮後の大きさが None なので、エントリごとの圧縮率の上限は確かめない
+            if let Some(compressed) = compressed {
+                self.limits.check_ratio(name, size, compressed)?;
+            }
+            count += 1;
+            bytes = bytes.saturating_add(size);
+        }
+        self.limits.check_entries(count)?;
+        self.limits.check_total(bytes)?;
+        self.limits.check_ratio("the archive", bytes, self.archive_size)?;
+        self.progress.total_entries = count;
+        self.progress.total_bytes = bytes;
+        self.emit();
+        Ok(())
+    }
+
+    // ヘッダーの値が偽られていても止められるよう、書き込んだ量でも確かめる
+    fn check_written(&mut self, name: &str, written: u64, compressed: Option<u64>) -> io::Result<()> {
+        let result = self
+            .limits
+            .check_total(self.progress.bytes)
+            .and_then(|_| compressed.map_or(Ok(()), |compressed| self.limits.check_ratio(name, written, compressed)))
+            .and_then(|_| self.limits.check_ratio("the archive", self.progress.bytes, self.archive_size));
+        result.map_err(|e| {
+            self.limit_exceeded = true;
+            io::Error::other(e)
+        })
+    }
+
+    fn emit(&mut self) {
+        (self.on_progress)(&self.progress);
+        self.last_emit = Instant::now();
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.is_some_and(|c| c.load(Ordering::Relaxed))
+    }
+
+    fn check_cancel(&self) -> io::Result<()> {
+        if self.is_cancelled() { Err(io::Error::other(CANCELLED)) } else { Ok(()) }
+    }
+
+    fn create_dirs(&mut self, dir: &Path) -> io::Result<()> {
+        let missing: Vec<PathBuf> = dir.ancestors().take_while(|p| !p.as_os_str().is_empty() && !p.exists()).map(Path::to_path_buf).collect();
+        transaction::create_dir_all(dir)?;
+        self.created_dirs.extend(missing);
+        Ok(())
+    }
+
+    fn write(&mut self, name: &str, rel: &Path, is_dir: bool, compressed: Option<u64>, reader: &mut dyn Read) -> io::Result<()> {
+        self.check_cancel()?;
+        if let Err(e) = self.limits.check_entries(self.progress.entries + 1) {
+            self.limit_exceeded = true;
+            return Err(io::Error::other(e));
+        }
+        let target = self.dest.join(rel);
+        if is_dir {
+            self.create_dirs(&target)?;
+            self.report.directories.push(target.to_string_lossy().into_owned());
+        } else {
+            if let Some(parent) = target.parent() {
+                self.create_dirs(parent)?;
+            }
+            let existed = target.exists();
+            transaction::prepare_write(&target)?;
+            // 書きかけのファイルが残らないよう、別名で書き終えてから置き換える
+            let mut partial = target.clone().into_os_string();
+            partial.push(".extracting");
+            let partial = PathBuf::from(partial);
+            // 途中で終了しても書きかけのファイルがロールバックで消えるよう記録しておく
+            transaction::prepare_write(&partial)?;
+            let size = match self.copy_to(name, compressed, reader, &partial).and_then(|size| self.place(&partial, &target).map(|_| size)) {
+                Ok(size) => size,
+                Err(e) => {
+                    let _ = fs::remove_file(&partial);
+                    return Err(e);
+                }
+            };
+            if !existed {
+                self.created_files.push(target.clone());
+            }
+            self.report.files.push(ExtractedFile { entry: name.to_string(), path: target.to_string_lossy().into_owned(), size });
+        }
+        self.progress.entries += 1;
+        self.emit();
+        Ok(())
+    }
+
+    // 読み込み中の .aux2 などは名前の変更で上書きできないので、使用中なら on_locked に従って置き換える
+    fn place(&mut self, partial: &Path, target: &Path) -> io::Result<()> {
+        let e = match fs::rename(partial, target) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        if !replace::is_locked(target) {
+            return Err(e);
+        }
+        let deferred = replace::replace_file(partial, target, self.on_locked)?;
+        fs::remove_file(partial)?;
+        if let Some(deferred) = deferred {
+            transaction::record_deferred(&deferred);
+            self.report.deferred.push(deferred);
+        }
+        Ok(())
+    }
+
+    fn copy_to(&mut self, name: &str, compressed: Option<u64>, reader: &mut dyn Read, path: &Path) -> io::Result<u64> {
+        let mut out = File::create(path)?;
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut written = 0u64;
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            out.write_all(&buf[..n])?;
+            written += n as u64;
+            self.progress.bytes += n as u64;
+            self.check_cancel()?;
+            self.check_written(name, written, compressed)?;
+            if self.last_emit.elapsed() >= PROGRESS_INTERVAL {
+                self.emit();
+            }
+        }
+        Ok(written)
+    }
+
+    fn finish(mut self, result: Result<(), String>) -> Result<ExtractReport, String> {
+        match result {
+            Ok(()) => Ok(self.report),
+            Err(_) if self.is_cancelled() => {
+                self.cleanup();
+                Err(CANCELLED.to_string())
+            }
+            Err(e) if self.limit_exceeded => {
+                self.cleanup();
+                Err(e)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // 中断時や上限を超えた時に、新しく作ったファイルとフォルダだけを消す（上書きしたファイルはトランザクションのロールバックで戻す）
+    fn cleanup(&mut self) {
+        for file in &self.created_files {
+            if let Err(e) = fs::remove_file(file) {
+                tracing::warn!("Failed to remove {} after cancelling extraction: {}", file.display(), e);
+            }
+        }
+        self.created_dirs.sort_by_key(|d| std::cmp::Reverse(d.components().count()));
+        self.created_dirs.dedup();
+        for dir in &self.created_dirs {
+            let _ = fs::remove_dir(dir);
+        }
+        tracing::info!("Extraction aborted: removed {} files and {} directories under {}", self.created_files.len(), self.created_dirs.len(), self.dest.display());
+    }
+}
+
+/// 中断フラグを登録し、extract:* イベントを送りながら展開する
+fn run_extract_task(
+    app: &tauri::AppHandle,
+    task_id: Option<String>,
+    run: impl FnOnce(&AtomicBool, &mut dyn FnMut(&ExtractProgress)) -> Result<ExtractReport, String>,
+) -> Result<ExtractReport, String> {
+    let task_id = task_id.unwrap_or_else(|| format!("extract-{}", chrono::Utc::now().timestamp_micros()));
+    let cancel = Arc::new(AtomicBool::new(false));
+    EXTRACT_TASKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(task_id.clone(), cancel.clone());
+    let mut on_progress = |progress: &ExtractProgress| {
+        let _ = app.emit(
+            "extract:progress",
+            serde_json::json!({
+                "taskId": task_id,
+                "entries": progress.entries,
+                "totalEntries": progress.total_entries,
+                "bytes": progress.bytes,
+                "totalBytes": progress.total_bytes,
+            }),
+        );
+    };
+    let result = run(&cancel, &mut on_progress);
+    EXTRACT_TASKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).remove(&task_id);
+    match &result {
+        Ok(report) => {
+            let _ = app.emit("extract:done", serde_json::json!({ "taskId": task_id, "files": report.files.len() }));
+        }
+        Err(msg) => {
+            let _ = app.emit("extract:error", serde_json::json!({ "taskId": task_id, "message": msg }));
+        }
+    }
+    result
+}
+
+/// 実行中の展開を中断する（書き込み済みのファイルは片付けてから CANCELLED で終わる）
+pub(crate) fn cancel_extract_task(task_id: &str) -> bool {
+    match EXTRACT_TASKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(task_id) {
+        Some(cancel) => {
+            cancel.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
 
 #[tauri::command]
-pub fn extract_zip(_app: tauri::AppHandle, zip_path: String, dest_path: String) -> Result<(), String> {
-    let file = File::open(&zip_path).map_err(|e| format!("open zip error: {}", e))?;
+pub async fn cancel_extract(task_id: String) -> bool {
+    cancel_extract_task(&task_id)
+}
+
+fn zip_error(e: ZipError) -> String {
+    match e {
+        ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => PASSWORD_REQUIRED.to_string(),
+        ZipError::InvalidPassword => WRONG_PASSWORD.to_string(),
+        e => format!("zip read error: {}", e),
+    }
+}
+
+// パスワードは暗号化されていないエントリでは無視される
+fn zip_entry<'a, R: Read + io::Seek>(archive: &'a mut ZipArchive<R>, index: usize, password: Option<&str>) -> Result<zip::read::ZipFile<'a, R>, String> {
+    archive.by_index_with_options(index, zip::ZipReadOptions::new().password(password.map(str::as_bytes))).map_err(zip_error)
+}
+
+// 暗号化されたエントリが復号後に壊れていればパスワード違いとみなす
+// （ZipCrypto の検査バイトは 1/256 の確率で誤ったパスワードを通してしまう）
+fn is_decrypt_failure(encrypted: bool, e: &io::Error) -> bool {
+    encrypted && matches!(e.kind(), io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput | io::ErrorKind::UnexpectedEof)
+}
+
+fn entry_error(name: &str, encrypted: bool, e: io::Error) -> String {
+    if is_decrypt_failure(encrypted, &e) { WRONG_PASSWORD.to_string() } else { format!("extract error ({}): {}", name, e) }
+}
+
+pub(crate) fn extract_zip_to(
+    zip_path: &Path,
+    dest: &Path,
+    options: &ExtractOptions,
+    cancel: Option<&AtomicBool>,
+    on_progress: &mut dyn FnMut(&ExtractProgress),
+) -> Result<ExtractReport, String> {
+    let file = File::open(zip_path).map_err(|e| format!("open zip error: {}", e))?;
     let mut archive = ZipArchive::new(file).map_err(|e| format!("zip open error: {}", e))?;
-    archive.extract(Path::new(&dest_path)).map_err(|e| format!("extract error: {}", e))?;
-    Ok(())
+    let mut report = ExtractReport::default();
+    let names = zip_entry_names(&mut archive, options.encoding.as_deref())?;
+    let planned = plan_entries(&names, options, &mut report)?;
+    let mut sizes = Vec::with_capacity(names.len());
+    for index in 0..names.len() {
+        let entry = archive.by_index_raw(index).map_err(|e| format!("zip read error: {}", e))?;
+        sizes.push((entry.size(), entry.compressed_size()));
+    }
+    let mut writer = EntryWriter::new(dest, report, options, cancel, on_progress);
+    writer.set_plan(
+        zip_path,
+        names.iter().zip(&planned).zip(&sizes).filter_map(|(((name, _), rel), (size, compressed))| Some((name.as_str(), rel.as_deref()?, *size, Some(*compressed)))),
+    )?;
+    let result = (|| {
+        for (index, ((name, _), rel)) in names.iter().zip(&planned).enumerate() {
+            let Some(rel) = rel else {
+                continue;
+            };
+            let mut entry = zip_entry(&mut archive, index, options.password.as_deref())?;
+            let (is_dir, encrypted, compressed) = (entry.is_dir(), entry.encrypted(), entry.compressed_size());
+            writer.write(name, rel, is_dir, Some(compressed), &mut entry).map_err(|e| entry_error(name, encrypted, e))?;
+        }
+        Ok(())
+    })();
+    writer.finish(result)
 }
 
 #[tauri::command]
-pub fn list_zip_entries(_app: tauri::AppHandle, zip_path: String) -> Result<Vec<String>, String> {
-    let file = File::open(&zip_path).map_err(|e| format!("open zip error: {}", e))?;
-    let mut archive = ZipArchive::new(file).map_err(|e| format!("zip open error: {}", e))?;
-    let mut entries = Vec::new();
+pub async fn extract_zip(app: tauri::AppHandle, zip_path: String, dest_path: String, options: Option<ExtractOptions>, task_id: Option<String>) -> Result<ExtractReport, String> {
+    extract_archive_as(app, ArchiveFormat::Zip, zip_path, dest_path, options.unwrap_or_default(), task_id).await
+}
+
+#[tauri::command]
+pub fn list_zip_entries(_app: tauri::AppHandle, zip_path: String, encoding: Option<String>) -> Result<Vec<String>, String> {
+    zip_file_names(Path::new(&zip_path), encoding.as_deref())
+}
+
+/// 中身から判定した書庫の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArchiveFormat {
+    Zip,
+    // 実行ファイルの後ろに書庫を連結した自己展開形式
+    ZipSfx,
+    SevenZip,
+    SevenZipSfx,
+    Tar,
+    TarGz,
+    TarZst,
+    Lzh,
+}
+
+const SEVEN_ZIP_SIGNATURE: &[u8] = b"\x37\x7A\xBC\xAF\x27\x1C";
+const GZIP_MAGIC: &[u8] = b"\x1F\x8B";
+const ZSTD_MAGIC: &[u8] = b"\x28\xB5\x2F\xFD";
+
+fn read_head(reader: impl Read, len: u64) -> io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    reader.take(len).read_to_end(&mut head)?;
+    Ok(head)
+}
+
+// ustar の識別子があるか、ヘッダーのチェックサムが合えば tar とみなす（古い形式には識別子がない）
+fn is_tar_header(block: &[u8]) -> bool {
+    if block.len() < 512 || block.iter().all(|b| *b == 0) {
+        return false;
+    }
+    if &block[257..262] == b"ustar" {
+        return true;
+    }
+    let stored = std::str::from_utf8(&block[148..156]).ok().and_then(|s| u32::from_str_radix(s.trim_matches(|c: char| c == '\0' || c == ' '), 8).ok());
+    let sum: u32 = block[..512].iter().enumerate().map(|(i, b)| if (148..156).contains(&i) { b' ' as u32 } else { *b as u32 }).sum();
+    stored == Some(sum)
+}
+
+impl ArchiveFormat {
+    /// 先頭のバイト列から形式を判定する（判定できなければ None）
+    pub(crate) fn sniff(path: &Path) -> Result<Option<Self>, String> {
+        let open = || File::open(path).map(BufReader::new).map_err(|e| format!("open archive error: {e}"));
+        let head = read_head(open()?, 512).map_err(|e| format!("read archive error: {e}"))?;
+        let format = if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else if head.starts_with(SEVEN_ZIP_SIGNATURE) {
+            Some(Self::SevenZip)
+        } else if head.starts_with(GZIP_MAGIC) {
+            // 中身が tar でなければ対象外（単体のファイルを圧縮した .gz など）
+            is_tar_header(&read_head(flate2::read::MultiGzDecoder::new(open()?), 512).unwrap_or_default()).then_some(Self::TarGz)
+        } else if head.starts_with(ZSTD_MAGIC) {
+            is_tar_header(&zstd::stream::read::Decoder::with_buffer(open()?).and_then(|d| read_head(d, 512)).unwrap_or_default()).then_some(Self::TarZst)
+        } else if lzh::looks_like_lzh(&head) {
+            Some(Self::Lzh)
+        } else if is_tar_header(&head) {
+            Some(Self::Tar)
+        } else if head.starts_with(b"MZ") {
+            if map_sfx(path).is_ok() { Some(Self::SevenZipSfx) } else { ZipArchive::new(open()?).is_ok().then_some(Self::ZipSfx) }
+        } else {
+            // 先頭にコメントなどが付いた zip は末尾の中央ディレクトリから見つける
+            ZipArchive::new(open()?).is_ok().then_some(Self::Zip)
+        };
+        Ok(format)
+    }
+
+    pub(crate) fn extract_to(
+        self,
+        path: &Path,
+        dest: &Path,
+        options: &ExtractOptions,
+        cancel: Option<&AtomicBool>,
+        on_progress: &mut dyn FnMut(&ExtractProgress),
+    ) -> Result<ExtractReport, String> {
+        let mut report = self.extract_entries_to(path, dest, options, cancel, on_progress)?;
+        if let Some(nested) = &options.nested {
+            extract_nested(&mut report, dest, nested, options, cancel, on_progress)?;
+        }
+        Ok(report)
+    }
+
+    fn extract_entries_to(
+        self,
+        path: &Path,
+        dest: &Path,
+        options: &ExtractOptions,
+        cancel: Option<&AtomicBool>,
+        on_progress: &mut dyn FnMut(&ExtractProgress),
+    ) -> Result<ExtractReport, String> {
+        match self {
+            Self::Zip | Self::ZipSfx => extract_zip_to(path, dest, options, cancel, on_progress),
+            Self::SevenZip | Self::SevenZipSfx => extract_7z_to(path, dest, options, cancel, on_progress),
+            Self::Tar | Self::TarGz | Self::TarZst => extract_tar_to(path, self, dest, options, cancel, on_progress),
+            Self::Lzh => extract_lzh_to(path, dest, options, cancel, on_progress),
+        }
+    }
+}
+
+// 入れ子の書庫を展開するフォルダ（拡張子を除いた名前。拡張子がなければ末尾に _extracted を付ける）
+fn nested_dest(archive: &Path) -> PathBuf {
+    let name = archive.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let lower = name.to_ascii_lowercase();
+    let stem = [".tar.gz", ".tar.zst", ".tgz", ".tar", ".zip", ".7z", ".lzh", ".lha", ".exe"]
+        .iter()
+        .find(|ext| lower.len() > ext.len() && lower.ends_with(*ext))
+        .map(|ext| &name[..name.len() - ext.len()]);
+    match stem {
+        Some(stem) => archive.with_file_name(stem),
+        None => archive.with_file_name(format!("{name}_extracted")),
+    }
+}
+
+/// 展開したファイルのうち pattern に一致する書庫を続けて展開する（中から出てきた書庫も max_depth まで展開する）
+fn extract_nested(
+    report: &mut ExtractReport,
+    dest: &Path,
+    nested: &NestedExtract,
+    options: &ExtractOptions,
+    cancel: Option<&AtomicBool>,
+    on_progress: &mut dyn FnMut(&ExtractProgress),
+) -> Result<(), String> {
+    let pattern = glob::Pattern::new(&nested.pattern.replace('\\', "/")).map_err(|e| format!("invalid glob pattern {}: {e}", nested.pattern))?;
+    let match_options = glob::MatchOptions {
+        case_sensitive: false,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    let max_depth = nested.max_depth.min(MAX_NESTED_DEPTH);
+    let mut pending: VecDeque<(usize, usize)> = (0..report.files.len()).map(|index| (index, 1)).collect();
+    while let Some((index, depth)) = pending.pop_front() {
+        if depth > max_depth {
+            continue;
+        }
+        let file = report.files[index].clone();
+        let archive_path = PathBuf::from(&file.path);
+        let rel = archive_path.strip_prefix(dest).unwrap_or(&archive_path).iter().map(|c| c.to_string_lossy().into_owned()).collect::<Vec<_>>().join("/");
+        let matched = if pattern.as_str().contains('/') {
+            pattern.matches_with(&rel, match_options)
+        } else {
+            rel.rsplit('/').next().is_some_and(|name| pattern.matches_with(name, match_options))
+        };
+        if !matched {
+            continue;
+        }
+        let Some(format) = ArchiveFormat::sniff(&archive_path)? else {
+            tracing::warn!("Skipping nested {}: not a supported archive", file.entry);
+            continue;
+        };
+        // 上限は外側の展開と合わせて数える
+        let used_bytes: u64 = report.files.iter().map(|f| f.size).sum();
+        let limits = ExtractLimits {
+            max_total_bytes: options.limits.max_total_bytes.saturating_sub(used_bytes),
+            max_entries: options.limits.max_entries.saturating_sub(report.files.len() as u64),
+            ..options.limits
+        };
+        let inner_options = ExtractOptions {
+            unsafe_entries: options.unsafe_entries,
+            password: options.password.clone(),
+            limits,
+            on_locked: options.on_locked,
+            ..Default::default()
+        };
+        let target = nested_dest(&archive_path);
+        tracing::info!("Extracting nested {} as {:?} into {}", file.entry, format, target.display());
+        let inner = format.extract_entries_to(&archive_path, &target, &inner_options, cancel, on_progress).map_err(|e| format!("{}: {e}", file.entry))?;
+        for extracted in inner.files {
+            pending.push_back((report.files.len(), depth + 1));
+            report.files.push(ExtractedFile { entry: format!("{}/{}", file.entry, extracted.entry), ..extracted });
+        }
+        report.directories.extend(inner.directories);
+        report.rejected.extend(inner.rejected.into_iter().map(|r| RejectedEntry { entry: format!("{}/{}", file.entry, r.entry), ..r }));
+        report.filtered_out += inner.filtered_out;
+        report.deferred.extend(inner.deferred);
+        report.nested.push(NestedArchive { entry: file.entry, format, path: target.to_string_lossy().into_owned(), depth });
+    }
+    Ok(())
+}
+
+/// zip のエントリ名の文字コード
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ZipNameEncoding {
+    Utf8,
+    // zip クレートの既定の解釈
+    Cp437,
+    Legacy(&'static Encoding),
+}
+
+impl ZipNameEncoding {
+    /// encoding 引数の値を解釈する（"auto" や空文字は自動判定）
+    fn from_label(label: &str) -> Result<Option<Self>, String> {
+        let label = label.trim().to_ascii_lowercase();
+        match label.as_str() {
+            "" | "auto" => Ok(None),
+            "cp437" | "ibm437" => Ok(Some(Self::Cp437)),
+            "cp932" | "ms932" | "windows-31j" => Ok(Some(Self::Legacy(SHIFT_JIS))),
+            _ => match Encoding::for_label(label.as_bytes()) {
+                Some(encoding) if encoding == UTF_8 => Ok(Some(Self::Utf8)),
+                Some(encoding) => Ok(Some(Self::Legacy(encoding))),
+                None => Err(format!("unsupported zip name encoding: {label}")),
+            },
+        }
+    }
+
+    fn decode(self, raw: &[u8], fallback: &str) -> String {
+        match self {
+            Self::Utf8 => String::from_utf8_lossy(raw).into_owned(),
+            Self::Cp437 => fallback.to_string(),
+            Self::Legacy(encoding) => encoding.decode_without_bom_handling(raw).0.into_owned(),
+        }
+    }
+}
+
+/// UTF-8 として不正な名前がすべて CP932 として読めるならその zip は CP932 で作られたとみなす
+fn detect_legacy_encoding<'a>(raw_names: impl Iterator<Item = &'a [u8]>) -> ZipNameEncoding {
+    let mut legacy = raw_names.filter(|raw| std::str::from_utf8(raw).is_err()).peekable();
+    if legacy.peek().is_some() && legacy.all(|raw| SHIFT_JIS.decode_without_bom_handling_and_without_replacement(raw).is_some()) {
+        ZipNameEncoding::Legacy(SHIFT_JIS)
+    } else {
+        ZipNameEncoding::Cp437
+    }
+}
+
+/// エントリ名をインデックス順に復号し、シンボリックリンクかどうかと組にして返す
+/// UTF-8 フラグの無い日本語の zip では CP932 で復号する（encoding を指定した場合は全エントリをその文字コードで復号する）
+fn zip_entry_names<R: io::Read + io::Seek>(archive: &mut ZipArchive<R>, encoding: Option<&str>) -> Result<Vec<(String, bool)>, String> {
+    let forced = encoding.map(ZipNameEncoding::from_label).transpose()?.flatten();
+    let mut entries = Vec::with_capacity(archive.len());
     for index in 0..archive.len() {
-        let entry = archive.by_index(index).map_err(|e| format!("zip read error: {}", e))?;
-        if entry.is_dir() {
+        let entry = archive.by_index_raw(index).map_err(|e| format!("zip read error: {}", e))?;
+        entries.push((entry.name_raw().to_vec(), entry.name().to_string(), entry.is_symlink()));
+    }
+    let legacy = match forced {
+        Some(_) => ZipNameEncoding::Cp437,
+        None => detect_legacy_encoding(entries.iter().map(|(raw, _, _)| raw.as_slice())),
+    };
+    Ok(entries
+        .into_iter()
+        .map(|(raw, fallback, symlink)| {
+            let name = match forced {
+                Some(encoding) => encoding.decode(&raw, &fallback),
+                None if std::str::from_utf8(&raw).is_ok() => ZipNameEncoding::Utf8.decode(&raw, &fallback),
+                None => legacy.decode(&raw, &fallback),
+            };
+            (name, symlink)
+        })
+        .collect())
+}
+
+fn zip_file_names(zip_path: &Path, encoding: Option<&str>) -> Result<Vec<String>, String> {
+    let file = File::open(zip_path).map_err(|e| format!("open zip error: {}", e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("zip open error: {}", e))?;
+    Ok(zip_entry_names(&mut archive, encoding)?.into_iter().map(|(name, _)| name.replace('\\', "/")).filter(|name| !name.ends_with('/')).collect())
+}
+
+// 7z の AES-256 + SHA-256 のメソッド ID
+const SEVEN_ZIP_AES_METHOD: &[u8] = &[0x06, 0xF1, 0x07, 0x01];
+
+fn open_7z<'a>(data: &'a [u8], password: Option<&str>) -> Result<ArchiveReader<Cursor<&'a [u8]>>, String> {
+    let password = password.map(Password::from).unwrap_or_else(Password::empty);
+    ArchiveReader::new(Cursor::new(data), password).map_err(|e| seven_zip_error(e, "7z open error", false))
+}
+
+/// エントリごとに AES で暗号化されたブロックに属するかどうか
+fn seven_zip_encrypted_entries(archive: &sevenz_rust2::Archive) -> Vec<bool> {
+    let encrypted_blocks: Vec<bool> = archive.blocks.iter().map(|block| block.coders.iter().any(|coder| coder.encoder_method_id() == SEVEN_ZIP_AES_METHOD)).collect();
+    (0..archive.files.len())
+        .map(|index| archive.stream_map.file_block_index.get(index).copied().flatten().and_then(|block| encrypted_blocks.get(block).copied()).unwrap_or(false))
+        .collect()
+}
+
+// decrypting はパスワードを指定して暗号化されたブロックを読んでいる場合（復号後のデータが壊れていればパスワード違い）
+fn seven_zip_error(e: sevenz_rust2::Error, context: &str, decrypting: bool) -> String {
+    match e {
+        sevenz_rust2::Error::PasswordRequired => PASSWORD_REQUIRED.to_string(),
+        sevenz_rust2::Error::MaybeBadPassword(_) => WRONG_PASSWORD.to_string(),
+        sevenz_rust2::Error::Io(..) | sevenz_rust2::Error::ChecksumVerificationFailed if decrypting => WRONG_PASSWORD.to_string(),
+        sevenz_rust2::Error::Other(message) if message == WRONG_PASSWORD => message.into_owned(),
+        e => format!("{context}: {e}"),
+    }
+}
+
+fn map_sfx(sfx_path: &Path) -> Result<(Mmap, usize), String> {
+    let file = File::open(sfx_path).map_err(|e| format!("open sfx error: {e}"))?;
+    let mmap = unsafe { MmapOptions::new().map(&file) }.map_err(|e| format!("mmap error: {e}"))?;
+    let offset = Finder::new(SEVEN_ZIP_SIGNATURE).find(&mmap).ok_or_else(|| "7z signature not found in SFX binary".to_string())?;
+    Ok((mmap, offset))
+}
+
+fn sfx_file_names(sfx_path: &Path) -> Result<Vec<String>, String> {
+    let (mmap, offset) = map_sfx(sfx_path)?;
+    let reader = open_7z(&mmap[offset..], None)?;
+    Ok(reader.archive().files.iter().filter(|entry| !entry.is_directory()).map(|entry| entry.name().replace('\\', "/")).collect())
+}
+
+pub(crate) fn archive_file_names(path: &Path, format: ArchiveFormat) -> Result<Vec<String>, String> {
+    match format {
+        ArchiveFormat::Zip | ArchiveFormat::ZipSfx => zip_file_names(path, None),
+        ArchiveFormat::SevenZip | ArchiveFormat::SevenZipSfx => sfx_file_names(path),
+        ArchiveFormat::Tar | ArchiveFormat::TarGz | ArchiveFormat::TarZst => {
+            Ok(tar_entries(path, format)?.into_iter().filter(|entry| !entry.is_dir).map(|entry| entry.name).collect())
+        }
+        ArchiveFormat::Lzh => lzh_file_names(path),
+    }
+}
+
+/// 展開せずに読み取ったエントリの情報
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntryInfo {
+    // 書庫に記録されている名前
+    pub name: String,
+    // 展開先からの相対パス（"/" 区切り）。展開先の外を指すなど展開できないエントリは None
+    pub path: Option<String>,
+    pub is_dir: bool,
+    pub size: u64,
+    // 7z のソリッド書庫ではブロックの先頭のエントリにまとめて計上される
+    pub compressed_size: u64,
+    pub crc32: Option<u32>,
+    pub modified: Option<String>,
+    pub encrypted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveListing {
+    pub format: ArchiveFormat,
+    pub entries: Vec<ArchiveEntryInfo>,
+    // すべてのエントリが 1 つのフォルダにまとめられている場合のフォルダ名
+    pub common_root: Option<String>,
+}
+
+fn filetime_to_rfc3339(ticks: u64) -> Option<String> {
+    const UNIX_EPOCH_OFFSET: i64 = 11_644_473_600;
+    let secs = (ticks / 10_000_000) as i64 - UNIX_EPOCH_OFFSET;
+    let nanos = (ticks % 10_000_000) as u32 * 100;
+    chrono::DateTime::from_timestamp(secs, nanos).map(|time| time.with_timezone(&chrono::Local).to_rfc3339())
+}
+
+// NTFS・拡張タイムスタンプの拡張フィールドを優先し、無ければローカル時刻の DOS 日時を使う
+fn zip_modified<R: Read>(entry: &zip::read::ZipFile<'_, R>) -> Option<String> {
+    for field in entry.extra_data_fields() {
+        match field {
+            zip::extra_fields::ExtraField::Ntfs(ntfs) => return filetime_to_rfc3339(ntfs.mtime()),
+            zip::extra_fields::ExtraField::ExtendedTimestamp(ts) => {
+                if let Some(secs) = ts.mod_time() {
+                    return chrono::DateTime::from_timestamp(secs as i64, 0).map(|time| time.with_timezone(&chrono::Local).to_rfc3339());
+                }
+            }
+        }
+    }
+    let dos = entry.last_modified().filter(|dos| dos.is_valid())?;
+    let naive =
+        chrono::NaiveDate::from_ymd_opt(dos.year() as i32, dos.month() as u32, dos.day() as u32)?.and_hms_opt(dos.hour() as u32, dos.minute() as u32, dos.second() as u32)?;
+    naive.and_local_timezone(chrono::Local).earliest().map(|time| time.to_rfc3339())
+}
+
+fn entry_info_path(name: &str) -> Option<String> {
+    normalize_entry_path(name).ok().flatten().map(|path| path.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+}
+
+fn zip_entry_infos(path: &Path, encoding: Option<&str>) -> Result<Vec<ArchiveEntryInfo>, String> {
+    let file = File::open(path).map_err(|e| format!("open zip error: {}", e))?;
+    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| format!("zip open error: {}", e))?;
+    let names = zip_entry_names(&mut archive, encoding)?;
+    let mut infos = Vec::with_capacity(names.len());
+    for (index, (name, _)) in names.into_iter().enumerate() {
+        let entry = archive.by_index_raw(index).map_err(|e| format!("zip read error: {}", e))?;
+        let is_dir = entry.is_dir();
+        infos.push(ArchiveEntryInfo {
+            path: entry_info_path(&name),
+            name,
+            is_dir,
+            size: entry.size(),
+            compressed_size: entry.compressed_size(),
+            crc32: (!is_dir).then(|| entry.crc32()),
+            modified: zip_modified(&entry),
+            encrypted: entry.encrypted(),
+        });
+    }
+    Ok(infos)
+}
+
+fn seven_zip_entry_infos(path: &Path) -> Result<Vec<ArchiveEntryInfo>, String> {
+    let (mmap, offset) = map_sfx(path)?;
+    let reader = open_7z(&mmap[offset..], None)?;
+    let archive = reader.archive();
+    let encrypted = seven_zip_encrypted_entries(archive);
+    Ok(archive
+        .files
+        .iter()
+        .zip(encrypted)
+        .map(|(entry, encrypted)| ArchiveEntryInfo {
+            name: entry.name().replace('\\', "/"),
+            path: entry_info_path(entry.name()),
+            is_dir: entry.is_directory(),
+            size: entry.size(),
+            compressed_size: entry.compressed_size,
+            crc32: entry.has_crc.then_some(entry.crc as u32),
+            modified: entry.has_last_modified_date.then(|| filetime_to_rfc3339(entry.last_modified_date().into())).flatten(),
+            encrypted,
+        })
+        .collect())
+}
+
+/// すべてのエントリが 1 つのフォルダの下にあればそのフォルダ名を返す（直下にファイルがあれば None）
+fn detect_common_root(entries: &[ArchiveEntryInfo]) -> Option<String> {
+    let mut root: Option<&str> = None;
+    let mut nested = false;
+    for entry in entries {
+        let Some(path) = entry.path.as_deref() else {
             continue;
+        };
+        let first = match path.split_once('/') {
+            Some((first, _)) => {
+                nested = true;
+                first
+            }
+            None if entry.is_dir => path,
+            None => return None,
+        };
+        if root.is_some_and(|root| root != first) {
+            return None;
         }
-        entries.push(entry.name().replace('\\', "/"));
+        root = Some(first);
     }
-    Ok(entries)
+    root.filter(|_| nested).map(str::to_string)
+}
+
+pub(crate) fn list_archive(path: &Path, encoding: Option<&str>) -> Result<ArchiveListing, String> {
+    let format = ArchiveFormat::sniff(path)?.ok_or_else(|| format!("unsupported archive format: {}", path.display()))?;
+    let entries = match format {
+        ArchiveFormat::Zip | ArchiveFormat::ZipSfx => zip_entry_infos(path, encoding)?,
+        ArchiveFormat::SevenZip | ArchiveFormat::SevenZipSfx => seven_zip_entry_infos(path)?,
+        _ => return Err(format!("entry listing is not supported for {:?}", format)),
+    };
+    let common_root = detect_common_root(&entries);
+    Ok(ArchiveListing { format, entries, common_root })
+}
+
+/// zip / 7z / 自己展開形式のエントリを展開せずに一覧する
+#[tauri::command]
+pub fn list_archive_entries(_app: tauri::AppHandle, archive_path: String, encoding: Option<String>) -> Result<ArchiveListing, String> {
+    list_archive(Path::new(&archive_path), encoding.as_deref())
+}
+
+// 展開せずにアーカイブ内の指定エントリの XXH3-128 を計算する
+pub(crate) fn hash_archive_entries(path: &Path, format: ArchiveFormat, wanted: &HashSet<String>) -> Result<HashMap<String, String>, String> {
+    let mut hashes = HashMap::new();
+    match format {
+        ArchiveFormat::Zip | ArchiveFormat::ZipSfx => {
+            let file = File::open(path).map_err(|e| format!("open zip error: {}", e))?;
+            let mut archive = ZipArchive::new(file).map_err(|e| format!("zip open error: {}", e))?;
+            let names = zip_entry_names(&mut archive, None)?;
+            for (index, (name, _)) in names.into_iter().enumerate() {
+                let name = name.replace('\\', "/");
+                if name.ends_with('/') || !wanted.contains(&name) {
+                    continue;
+                }
+                let mut entry = zip_entry(&mut archive, index, None)?;
+                let hex = super::version::xxh3_128_hex_reader(&mut entry).map_err(|e| format!("zip read error: {}", e))?;
+                hashes.insert(name, hex);
+            }
+        }
+        ArchiveFormat::SevenZip | ArchiveFormat::SevenZipSfx => {
+            let (mmap, offset) = map_sfx(path)?;
+            let mut reader = open_7z(&mmap[offset..], None)?;
+            reader
+                .for_each_entries(|entry, data| {
+                    let name = entry.name().replace('\\', "/");
+                    if !entry.is_directory() && wanted.contains(&name) {
+                        hashes.insert(name, super::version::xxh3_128_hex_reader(data)?);
+                    }
+                    Ok(true)
+                })
+                .map_err(|e| seven_zip_error(e, "7z decompress error", false))?;
+        }
+        ArchiveFormat::Tar | ArchiveFormat::TarGz | ArchiveFormat::TarZst => {
+            let mut archive = open_tar(path, format)?;
+            for entry in archive.entries().map_err(|e| format!("tar read error: {e}"))? {
+                let mut entry = entry.map_err(|e| format!("tar read error: {e}"))?;
+                let Some(info) = tar_entry_info(&entry).filter(|info| !info.is_dir && wanted.contains(&info.name)) else {
+                    continue;
+                };
+                let hex = super::version::xxh3_128_hex_reader(&mut entry).map_err(|e| format!("tar read error ({}): {}", info.name, e))?;
+                hashes.insert(info.name, hex);
+            }
+        }
+        ArchiveFormat::Lzh => {
+            let (mut archive, entries) = open_lzh(path)?;
+            for entry in entries.iter().filter(|entry| !entry.is_dir() && wanted.contains(&entry.name)) {
+                let hex = entry
+                    .reader(&mut archive)
+                    .and_then(|mut data| super::version::xxh3_128_hex_reader(&mut data))
+                    .map_err(|e| format!("lzh read error ({}): {}", entry.name, e))?;
+                hashes.insert(entry.name.clone(), hex);
+            }
+        }
+    }
+    Ok(hashes)
+}
+
+pub(crate) fn extract_7z_to(
+    archive_path: &Path,
+    dest: &Path,
+    options: &ExtractOptions,
+    cancel: Option<&AtomicBool>,
+    on_progress: &mut dyn FnMut(&ExtractProgress),
+) -> Result<ExtractReport, String> {
+    let (mmap, offset) = map_sfx(archive_path)?;
+    let mut reader = open_7z(&mmap[offset..], options.password.as_deref())?;
+    let mut report = ExtractReport::default();
+    let encrypted: HashSet<String> =
+        reader.archive().files.iter().zip(seven_zip_encrypted_entries(reader.archive())).filter(|(_, encrypted)| *encrypted).map(|(entry, _)| entry.name().to_string()).collect();
+    let decrypting = options.password.is_some() && !encrypted.is_empty();
+    let entries = &reader.archive().files;
+    let names: Vec<(String, bool)> = entries.iter().map(|entry| (entry.name().to_string(), is_7z_symlink(entry))).collect();
+    let planned = plan_entries(&names, options, &mut report)?;
+    let mut writer = EntryWriter::new(dest, report, options, cancel, on_progress);
+    // ソリッド書庫ではエントリごとの圧縮後の大きさが分からない
+    writer.set_plan(archive_path, entries.iter().zip(&planned).filter_map(|(entry, rel)| Some((entry.name(), rel.as_deref()?, entry.size(), None))))?;
+    let planned: HashMap<String, PathBuf> = names.into_iter().zip(planned).filter_map(|((name, _), rel)| Some((name, rel?))).collect();
+    let result = reader
+        .for_each_entries(|entry, data| {
+            let name = entry.name();
+            if let Some(rel) = planned.get(name) {
+                writer.write(name, rel, entry.is_directory(), None, data).map_err(|e| sevenz_rust2::Error::other(entry_error(name, encrypted.contains(name), e)))?;
+            } else {
+                writer.check_cancel()?;
+                // 読み飛ばさないと後続のエントリを展開できない
+                io::copy(data, &mut io::sink())?;
+            }
+            Ok(true)
+        })
+        .map_err(|e| seven_zip_error(e, "7z decompress error", decrypting));
+    writer.finish(result)
+}
+
+/// 形式を指定して展開する（extract:* イベントを送り、task_id で中断できる）
+pub(crate) async fn extract_archive_as(
+    app: tauri::AppHandle,
+    format: ArchiveFormat,
+    archive_path: String,
+    dest_path: String,
+    options: ExtractOptions,
+    task_id: Option<String>,
+) -> Result<ExtractReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        run_extract_task(&app, task_id, |cancel, on_progress| format.extract_to(Path::new(&archive_path), Path::new(&dest_path), &options, Some(cancel), on_progress))
+    })
+    .await
+    .map_err(|e| format!("task join error: {e}"))?
+}
+
+#[tauri::command]
+pub async fn extract_7z_sfx(app: tauri::AppHandle, sfx_path: String, dest_path: String, options: Option<ExtractOptions>, task_id: Option<String>) -> Result<ExtractReport, String> {
+    extract_archive_as(app, ArchiveFormat::SevenZipSfx, sfx_path, dest_path, options.unwrap_or_default(), task_id).await
+}
+
+fn open_lzh(path: &Path) -> Result<(BufReader<File>, Vec<LzhEntry>), String> {
+    let mut reader = BufReader::new(File::open(path).map_err(|e| format!("open lzh error: {e}"))?);
+    let entries = lzh::read_entries(&mut reader)?;
+    Ok((reader, entries))
+}
+
+fn lzh_file_names(path: &Path) -> Result<Vec<String>, String> {
+    let (_, entries) = open_lzh(path)?;
+    Ok(entries.into_iter().filter(|entry| !entry.is_dir()).map(|entry| entry.name).collect())
+}
+
+pub(crate) fn extract_lzh_to(
+    lzh_path: &Path,
+    dest: &Path,
+    options: &ExtractOptions,
+    cancel: Option<&AtomicBool>,
+    on_progress: &mut dyn FnMut(&ExtractProgress),
+) -> Result<ExtractReport, String> {
+    let (mut archive, entries) = open_lzh(lzh_path)?;
+    let mut report = ExtractReport::default();
+    let names: Vec<(String, bool)> = entries.iter().map(|entry| (entry.name.clone(), false)).collect();
+    let planned = plan_entries(&names, options, &mut report)?;
+    let mut writer = EntryWriter::new(dest, report, options, cancel, on_progress);
+    writer.set_plan(
+        lzh_path,
+        entries.iter().zip(&planned).filter_map(|(entry, rel)| Some((entry.name.as_str(), rel.as_deref()?, entry.original_size, Some(entry.compressed_size)))),
+    )?;
+    let result = (|| {
+        for (entry, rel) in entries.iter().zip(&planned) {
+            let Some(rel) = rel else {
+                continue;
+            };
+            let mut data = entry.reader(&mut archive).map_err(|e| format!("lzh read error ({}): {}", entry.name, e))?;
+            writer.write(&entry.name, rel, entry.is_dir(), Some(entry.compressed_size), &mut data).map_err(|e| format!("extract error ({}): {}", entry.name, e))?;
+        }
+        Ok(())
+    })();
+    writer.finish(result)
+}
+
+#[tauri::command]
+pub async fn extract_lzh(app: tauri::AppHandle, lzh_path: String, dest_path: String, options: Option<ExtractOptions>, task_id: Option<String>) -> Result<ExtractReport, String> {
+    extract_archive_as(app, ArchiveFormat::Lzh, lzh_path, dest_path, options.unwrap_or_default(), task_id).await
 }
 
 #[tauri::command]
-pub async fn extract_7z_sfx(_: tauri::AppHandle, sfx_path: String, dest_path: String) -> Result<(), String> {
-    tauri::async_runtime::spawn_blocking(move || -> Result<(), String> {
-        const SIGNATURE: &[u8] = b"\x37\x7A\xBC\xAF\x27\x1C";
-        let file = File::open(Path::new(&sfx_path)).map_err(|e| format!("open sfx error: {e}"))?;
-        let mmap = unsafe { MmapOptions::new().map(&file) }.map_err(|e| format!("mmap error: {e}"))?;
-        let offset = Finder::new(SIGNATURE).find(&mmap).ok_or_else(|| "7z signature not found in SFX binary".to_string())?;
-        let cursor = Cursor::new(&mmap[offset..]);
-        decompress_with_extract_fn_and_password(cursor, Path::new(&dest_path), Password::empty(), default_entry_extract_fn).map_err(|e| format!("7z decompress error: {e}"))?;
+pub fn list_lzh_entries(_app: tauri::AppHandle, lzh_path: String) -> Result<Vec<String>, String> {
+    lzh_file_names(Path::new(&lzh_path))
+}
+
+struct TarEntryInfo {
+    name: String,
+    is_dir: bool,
+    is_link: bool,
+    size: u64,
+}
+
+fn open_tar(path: &Path, format: ArchiveFormat) -> Result<tar::Archive<Box<dyn Read>>, String> {
+    let file = BufReader::new(File::open(path).map_err(|e| format!("open tar error: {e}"))?);
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::TarGz => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        ArchiveFormat::TarZst => Box::new(zstd::stream::read::Decoder::with_buffer(file).map_err(|e| format!("zstd open error: {e}"))?),
+        _ => Box::new(file),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+// 通常のファイル・フォルダ・リンク以外（FIFO や pax のグローバルヘッダーなど）は None
+fn tar_entry_info<R: Read>(entry: &tar::Entry<R>) -> Option<TarEntryInfo> {
+    let (is_dir, is_link) = match entry.header().entry_type() {
+        tar::EntryType::Regular | tar::EntryType::Continuous => (false, false),
+        tar::EntryType::Directory => (true, false),
+        tar::EntryType::Symlink | tar::EntryType::Link => (false, true),
+        _ => return None,
+    };
+    let name = String::from_utf8_lossy(&entry.path_bytes()).trim_end_matches('/').to_string();
+    Some(TarEntryInfo { name, is_dir, is_link, size: entry.size() })
+}
+
+fn tar_entries(path: &Path, format: ArchiveFormat) -> Result<Vec<TarEntryInfo>, String> {
+    let mut archive = open_tar(path, format)?;
+    let mut entries = Vec::new();
+    for entry in archive.entries().map_err(|e| format!("tar read error: {e}"))? {
+        entries.extend(tar_entry_info(&entry.map_err(|e| format!("tar read error: {e}"))?));
+    }
+    Ok(entries)
+}
+
+// 圧縮された tar は先頭から順にしか読めないため、エントリの一覧と展開で 2 回読む
+fn extract_tar_to(
+    path: &Path,
+    format: ArchiveFormat,
+    dest: &Path,
+    options: &ExtractOptions,
+    cancel: Option<&AtomicBool>,
+    on_progress: &mut dyn FnMut(&ExtractProgress),
+) -> Result<ExtractReport, String> {
+    let infos = tar_entries(path, format)?;
+    let mut report = ExtractReport::default();
+    let names: Vec<(String, bool)> = infos.iter().map(|info| (info.name.clone(), info.is_link)).collect();
+    let planned = plan_entries(&names, options, &mut report)?;
+    let mut writer = EntryWriter::new(dest, report, options, cancel, on_progress);
+    // tar はエントリごとに圧縮しないので圧縮後の大きさは None（gz / zst は書庫全体の圧縮率で確かめる）
+    writer.set_plan(path, infos.iter().zip(&planned).filter_map(|(info, rel)| Some((info.name.as_str(), rel.as_deref()?, info.size, None))))?;
+    let result = (|| {
+        let mut archive = open_tar(path, format)?;
+        let mut planned = planned.iter();
+        for entry in archive.entries().map_err(|e| format!("tar read error: {e}"))? {
+            let mut entry = entry.map_err(|e| format!("tar read error: {e}"))?;
+            let Some(info) = tar_entry_info(&entry) else {
+                continue;
+            };
+            let rel = match planned.next() {
+                Some(Some(rel)) => rel,
+                Some(None) => continue,
+                None => break,
+            };
+            writer.write(&info.name, rel, info.is_dir, None, &mut entry).map_err(|e| format!("extract error ({}): {}", info.name, e))?;
+        }
         Ok(())
+    })();
+    writer.finish(result)
+}
+
+/// 展開結果と判定した形式
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveExtraction {
+    pub format: ArchiveFormat,
+    #[serde(flatten)]
+    pub report: ExtractReport,
+}
+
+/// 中身から形式を判定して展開する（zip / 7z / 自己展開形式 / tar / tar.gz / tar.zst / lzh）
+#[tauri::command]
+pub async fn extract_archive(
+    app: tauri::AppHandle,
+    archive_path: String,
+    dest_path: String,
+    options: Option<ExtractOptions>,
+    task_id: Option<String>,
+) -> Result<ArchiveExtraction, String> {
+    let format = ArchiveFormat::sniff(Path::new(&archive_path))?.ok_or_else(|| format!("unsupported archive format: {archive_path}"))?;
+    tracing::info!("Extracting {} as {:?}", archive_path, format);
+    let report = extract_archive_as(app, format, archive_path, dest_path, options.unwrap_or_default(), task_id).await?;
+    Ok(ArchiveExtraction { format, report })
+}
+
+/// 展開前の検査結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveTestReport {
+    pub format: ArchiveFormat,
+    pub entries: u64,
+    pub bytes: u64,
+}
+
+// 展開結果を捨てながら最後まで読む（CRC は各形式のリーダーが読み終えたときに検証する）
+// read はこれまでに読んだ合計、max_bytes はその上限
+fn drain_entry(reader: &mut dyn Read, cancel: Option<&AtomicBool>, buf: &mut [u8], read: u64, max_bytes: u64) -> io::Result<u64> {
+    let mut total = 0;
+    loop {
+        if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            return Err(io::Error::other(CANCELLED));
+        }
+        match reader.read(buf)? {
+            0 => return Ok(total),
+            n => total += n as u64,
+        }
+        if read + total > max_bytes {
+            return Err(io::Error::other(format!("{LIMIT_EXCEEDED}: total size is over {} bytes", max_bytes)));
+        }
+    }
+}
+
+/// すべてのエントリを展開先に書き込まずに復号し、壊れていないか確かめる
+pub(crate) fn test_archive_at(path: &Path, format: ArchiveFormat, options: &ExtractOptions, cancel: Option<&AtomicBool>) -> Result<ArchiveTestReport, String> {
+    let mut report = ArchiveTestReport { format, entries: 0, bytes: 0 };
+    let mut buf = vec![0u8; 64 * 1024];
+    let password = options.password.as_deref();
+    let limits = options.limits;
+    // 展開と同じく合計サイズと書庫全体の圧縮率の上限を超えたら止める
+    let archive_size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let max_bytes = limits.max_total_bytes.min(archive_size.saturating_mul(limits.max_ratio).max(RATIO_MIN_BYTES));
+    let corrupted = |name: &str, e: &dyn std::fmt::Display| {
+        let message = e.to_string();
+        if message == CANCELLED || message.starts_with(LIMIT_EXCEEDED) { message } else { format!("archive is corrupted ({name}): {message}") }
+    };
+    match format {
+        ArchiveFormat::Zip | ArchiveFormat::ZipSfx => {
+            let file = File::open(path).map_err(|e| format!("open zip error: {}", e))?;
+            let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| format!("zip open error: {}", e))?;
+            for index in 0..archive.len() {
+                let mut entry = zip_entry(&mut archive, index, password)?;
+                if entry.is_dir() {
+                    continue;
+                }
+                let (name, encrypted) = (entry.name().to_string(), entry.encrypted());
+                report.bytes += drain_entry(&mut entry, cancel, &mut buf, report.bytes, max_bytes)
+                    .map_err(|e| if is_decrypt_failure(encrypted, &e) { WRONG_PASSWORD.to_string() } else { corrupted(&name, &e) })?;
+                report.entries += 1;
+                limits.check_entries(report.entries)?;
+            }
+        }
+        ArchiveFormat::SevenZip | ArchiveFormat::SevenZipSfx => {
+            let (mmap, offset) = map_sfx(path)?;
+            let mut reader = open_7z(&mmap[offset..], password)?;
+            let encrypted = seven_zip_encrypted_entries(reader.archive());
+            let decrypting = password.is_some() && encrypted.contains(&true);
+            let mut index = 0;
+            reader
+                .for_each_entries(|entry, data| {
+                    let encrypted = encrypted.get(index).copied().unwrap_or(false);
+                    index += 1;
+                    if entry.is_directory() {
+                        return Ok(true);
+                    }
+                    report.bytes += drain_entry(data, cancel, &mut buf, report.bytes, max_bytes)
+                        .map_err(|e| sevenz_rust2::Error::other(if is_decrypt_failure(encrypted, &e) { WRONG_PASSWORD.to_string() } else { corrupted(entry.name(), &e) }))?;
+                    report.entries += 1;
+                    limits.check_entries(report.entries).map_err(sevenz_rust2::Error::other)?;
+                    Ok(true)
+                })
+                .map_err(|e| match e {
+                    sevenz_rust2::Error::Other(message) => message.into_owned(),
+                    e => seven_zip_error(e, "archive is corrupted", decrypting),
+                })?;
+        }
+        ArchiveFormat::Tar | ArchiveFormat::TarGz | ArchiveFormat::TarZst => {
+            let mut archive = open_tar(path, format)?;
+            for entry in archive.entries().map_err(|e| format!("archive is corrupted: {e}"))? {
+                let mut entry = entry.map_err(|e| format!("archive is corrupted: {e}"))?;
+                let Some(info) = tar_entry_info(&entry).filter(|info| !info.is_dir) else {
+                    continue;
+                };
+                report.bytes += drain_entry(&mut entry, cancel, &mut buf, report.bytes, max_bytes).map_err(|e| corrupted(&info.name, &e))?;
+                report.entries += 1;
+                limits.check_entries(report.entries)?;
+            }
+        }
+        ArchiveFormat::Lzh => {
+            let (mut archive, entries) = open_lzh(path)?;
+            for entry in entries.iter().filter(|entry| !entry.is_dir()) {
+                let mut data = entry.reader(&mut archive).map_err(|e| corrupted(&entry.name, &e))?;
+                report.bytes += drain_entry(&mut data, cancel, &mut buf, report.bytes, max_bytes).map_err(|e| corrupted(&entry.name, &e))?;
+                report.entries += 1;
+                limits.check_entries(report.entries)?;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// 形式を指定して検査する（task_id を指定すると cancel_extract で中断できる）
+pub(crate) async fn test_archive_as(format: ArchiveFormat, archive_path: String, options: ExtractOptions, task_id: Option<String>) -> Result<ArchiveTestReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let cancel = Arc::new(AtomicBool::new(false));
+        if let Some(task_id) = &task_id {
+            EXTRACT_TASKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(task_id.clone(), cancel.clone());
+        }
+        let result = test_archive_at(Path::new(&archive_path), format, &options, Some(&cancel));
+        if let Some(task_id) = &task_id {
+            EXTRACT_TASKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).remove(task_id);
+        }
+        result
     })
     .await
     .map_err(|e| format!("task join error: {e}"))?
 }
 
-fn copy_item(src: &Path, dst: &Path) -> io::Result<usize> {
-    let mut count = 0;
+#[tauri::command]
+pub async fn test_archive(archive_path: String, password: Option<String>, limits: Option<ExtractLimits>, task_id: Option<String>) -> Result<ArchiveTestReport, String> {
+    let format = ArchiveFormat::sniff(Path::new(&archive_path))?.ok_or_else(|| format!("unsupported archive format: {archive_path}"))?;
+    let options = ExtractOptions { password, limits: limits.unwrap_or_default(), ..Default::default() };
+    test_archive_as(format, archive_path, options, task_id).await
+}
+
+/// コピー先に同名のファイルがある場合の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CopyPolicy {
+    #[default]
+    Overwrite,
+    SkipExisting,
+    // コピー元の更新日時の方が新しい場合だけ上書きする
+    OnlyIfNewer,
+    // 既存のファイルを <ファイル名>.bak に残してから上書きする
+    BackupThenOverwrite,
+    // 既存のファイルが 1 つでもあれば何も書き込まずに失敗する
+    FailOnConflict,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CopyOptions {
+    pub policy: CopyPolicy,
+    // "/" を含むパターンはコピー元からの相対パス、含まないパターンはいずれかのフォルダ名・ファイル名と照合する
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    // コピー元の更新日時をコピー先に引き継ぐ
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub preserve_mtime: bool,
+    // 上書きするファイルが起動中の AviUtl2 などに使われている場合の扱い
+    pub on_locked: LockedFilePolicy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverwrittenFile {
+    pub path: String,
+    // 上書き前の内容の xxh3-128
+    pub previous_hash: String,
+    pub backup: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyReport {
+    pub created: Vec<String>,
+    pub overwritten: Vec<OverwrittenFile>,
+    // policy に従って既存のファイルを残したコピー先
+    pub skipped: Vec<String>,
+    // include / exclude で対象外になったファイルの数
+    pub filtered_out: usize,
+    // 使用中だったため古いファイルを別名に変えて置き換えたファイル
+    pub deferred: Vec<DeferredFile>,
+}
+
+impl CopyReport {
+    /// コピー元として一致したファイルの数（書き込まなかったものを含む）
+    pub fn matched(&self) -> usize {
+        self.created.len() + self.overwritten.len() + self.skipped.len()
+    }
+}
+
+/// コピー元からの相対パスが include / exclude に一致するかを返す関数を作る
+pub(crate) fn copy_filter(options: &CopyOptions) -> Result<impl Fn(&Path) -> bool, String> {
+    let filter = EntryFilter::new(&EntrySelection {
+        include: options.include.clone(),
+        exclude: options.exclude.clone(),
+        ..Default::default()
+    })?;
+    Ok(move |rel: &Path| {
+        let parts: Vec<String> = rel.iter().map(|c| c.to_string_lossy().into_owned()).collect();
+        filter.apply(&parts.iter().map(String::as_str).collect::<Vec<_>>()).is_some()
+    })
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    (0..).map(|i| path.with_file_name(if i == 0 { format!("{name}.bak") } else { format!("{name}.bak{i}") })).find(|p| !p.exists()).unwrap()
+}
+
+fn copy_file(from: &Path, dest: &Path, options: &CopyOptions, report: &mut CopyReport) -> io::Result<()> {
+    let source = fs::metadata(from)?;
+    let existing = fs::metadata(dest).ok().filter(|m| m.is_file());
+    let dest_str = dest.to_string_lossy().into_owned();
+    if let Some(existing) = &existing {
+        let keep = match options.policy {
+            CopyPolicy::SkipExisting => true,
+            CopyPolicy::OnlyIfNewer => matches!((source.modified(), existing.modified()), (Ok(src), Ok(dst)) if src <= dst),
+            _ => false,
+        };
+        if keep {
+            report.skipped.push(dest_str);
+            return Ok(());
+        }
+    }
+    if let Some(parent) = dest.parent() {
+        transaction::create_dir_all(parent)?;
+    }
+    let previous_hash = match existing {
+        Some(_) => Some(super::version::xxh3_128_hex_reader(&mut File::open(dest)?)?),
+        None => None,
+    };
+    let backup = match previous_hash {
+        Some(_) if options.policy == CopyPolicy::BackupThenOverwrite => {
+            let backup = backup_path(dest);
+            transaction::prepare_write(&backup)?;
+            fs::copy(dest, &backup)?;
+            Some(backup.to_string_lossy().into_owned())
+        }
+        _ => None,
+    };
+    transaction::prepare_write(dest)?;
+    if let Some(deferred) = replace::replace_file(from, dest, options.on_locked)? {
+        transaction::record_deferred(&deferred);
+        report.deferred.push(deferred);
+    }
+    if options.preserve_mtime {
+        File::options().write(true).open(dest)?.set_modified(source.modified()?)?;
+    }
+    match previous_hash {
+        Some(previous_hash) => report.overwritten.push(OverwrittenFile { path: dest_str, previous_hash, backup }),
+        None => report.created.push(dest_str),
+    }
+    Ok(())
+}
+
+fn copy_item(src: &Path, dst: &Path, options: &CopyOptions) -> io::Result<CopyReport> {
+    let included = copy_filter(options).map_err(io::Error::other)?;
+    let mut report = CopyReport::default();
+    // 書き込む前にコピー元とコピー先の組と作るフォルダをすべて決める（FailOnConflict はフォルダも作らずに失敗させる）
+    let mut targets = Vec::new();
+    let mut dirs = vec![dst.to_path_buf()];
     if src.is_file() {
-        fs::create_dir_all(dst)?;
         let file_name = src.file_name().ok_or_else(|| io::Error::other("Failed to get file name"))?;
-        let dest_path = dst.join(file_name);
-        fs::copy(src, dest_path)?;
-        count += 1;
-        return Ok(count);
-    }
-    if src.is_dir() {
-        fs::create_dir_all(dst)?;
+        if included(Path::new(file_name)) {
+            targets.push((src.to_path_buf(), dst.join(file_name)));
+        } else {
+            report.filtered_out += 1;
+        }
+    } else if src.is_dir() {
         for entry in WalkDir::new(src) {
             let entry = entry?;
             let path = entry.path();
             let rel = path.strip_prefix(src).map_err(|_| io::Error::other("Failed to calculate relative path"))?;
-            let dest_path: PathBuf = dst.join(rel);
             if entry.file_type().is_dir() {
-                fs::create_dir_all(&dest_path)?;
-            } else {
-                if let Some(parent) = dest_path.parent() {
-                    fs::create_dir_all(parent)?;
+                // 絞り込みがなければ空のフォルダもそのまま作る
+                if options.include.is_empty() && options.exclude.is_empty() {
+                    dirs.push(dst.join(rel));
                 }
-                fs::copy(path, &dest_path)?;
-                count += 1;
+            } else if included(rel) {
+                targets.push((path.to_path_buf(), dst.join(rel)));
+            } else {
+                report.filtered_out += 1;
             }
         }
-        return Ok(count);
+    } else {
+        return Err(io::Error::other("Source is neither a file nor a directory"));
     }
-
-    Err(io::Error::other("Source is neither a file nor a directory"))
+    if options.policy == CopyPolicy::FailOnConflict
+        && let Some((_, dest)) = targets.iter().find(|(_, dest)| dest.exists())
+    {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("copy destination already exists: {}", dest.display())));
+    }
+    // AviUtl2 の終了を待つ場合は、途中まで書き込まないよう先に使用中のファイルを探す
+    if options.on_locked == LockedFilePolicy::WaitForExit
+        && let Some((_, dest)) = targets.iter().find(|(_, dest)| replace::is_locked(dest))
+    {
+        return Err(replace::locked_error(dest));
+    }
+    for dir in &dirs {
+        transaction::create_dir_all(dir)?;
+    }
+    for (from, dest) in targets {
+        copy_file(&from, &dest, options, &mut report)?;
+    }
+    Ok(report)
 }
 
 #[tauri::command]
-pub fn copy_item_js(src_str: String, dst_str: String) -> Result<usize, String> {
+pub fn copy_item_js(src_str: String, dst_str: String, options: Option<CopyOptions>) -> Result<CopyReport, String> {
     let src = PathBuf::from(src_str);
     let dst = PathBuf::from(dst_str);
-    copy_item(&src, &dst).map_err(|e| e.to_string())
+    copy_item(&src, &dst, &options.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_item_js(path_str: String) -> Result<bool, String> {
+    transaction::remove_path(Path::new(&path_str)).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aviutl2-archive-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // 展開先の外に書き込もうとするエントリを含む zip を作る
+    fn malicious_zip(dir: &Path) -> PathBuf {
+        let path = dir.join("malicious.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        let options = SimpleFileOptions::default();
+        for name in [
+            "good/readme.txt",
+            "../evil.txt",
+            "good/../../evil2.txt",
+            "..\\evil3.txt",
+            "/abs.txt",
+            "C:/drive.txt",
+            "c:evil4.txt",
+            "good/file.txt:stream",
+            "good/CON.txt",
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(name.as_bytes()).unwrap();
+        }
+        zip.add_symlink("good/link", "../../outside", options).unwrap();
+        zip.finish().unwrap();
+        path
+    }
+
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for byte in data {
+            crc ^= u32::from(*byte);
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            }
+        }
+        !crc
+    }
+
+    // UTF-8 フラグを立てずにエントリ名をそのままのバイト列で格納した（無圧縮の）zip を作る
+    fn raw_name_zip(path: &Path, entries: &[(&[u8], &[u8])]) {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, data) in entries {
+            let offset = out.len() as u32;
+            let header = |signature: u32, central: bool| {
+                let mut h = signature.to_le_bytes().to_vec();
+                if central {
+                    h.extend(20u16.to_le_bytes());
+                }
+                for v in [20u16, 0, 0, 0, 0x21] {
+                    h.extend(v.to_le_bytes());
+                }
+                for v in [crc32(data), data.len() as u32, data.len() as u32] {
+                    h.extend(v.to_le_bytes());
+                }
+                h.extend((name.len() as u16).to_le_bytes());
+                h.extend(0u16.to_le_bytes());
+                if central {
+                    for v in [0u16, 0, 0] {
+                        h.extend(v.to_le_bytes());
+                    }
+                    h.extend(0u32.to_le_bytes());
+                    h.extend(offset.to_le_bytes());
+                }
+                h.extend_from_slice(name);
+                h
+            };
+            out.extend(header(0x0403_4b50, false));
+            out.extend_from_slice(data);
+            central.extend(header(0x0201_4b50, true));
+        }
+        let (central_offset, central_len) = (out.len() as u32, central.len() as u32);
+        out.extend(central);
+        out.extend(0x0605_4b50u32.to_le_bytes());
+        for v in [0u16, 0, entries.len() as u16, entries.len() as u16] {
+            out.extend(v.to_le_bytes());
+        }
+        out.extend(central_len.to_le_bytes());
+        out.extend(central_offset.to_le_bytes());
+        out.extend(0u16.to_le_bytes());
+        fs::write(path, out).unwrap();
+    }
+
+    #[test]
+    fn decodes_cp932_entry_names() {
+        let dir = temp_dir("cp932");
+        let zip_path = dir.join("sjis.zip");
+        let (name, _, _) = SHIFT_JIS.encode("スクリプト/ﾃｽﾄ.anm2");
+        raw_name_zip(&zip_path, &[(b"readme.txt", b"readme"), (&name, b"script")]);
+
+        assert_eq!(zip_file_names(&zip_path, None).unwrap(), ["readme.txt", "スクリプト/ﾃｽﾄ.anm2"]);
+        assert_eq!(zip_file_names(&zip_path, Some("shift_jis")).unwrap(), ["readme.txt", "スクリプト/ﾃｽﾄ.anm2"]);
+        assert_ne!(zip_file_names(&zip_path, Some("cp437")).unwrap()[1], "スクリプト/ﾃｽﾄ.anm2");
+        assert!(zip_file_names(&zip_path, Some("no-such-encoding")).is_err());
+
+        let dest = dir.join("out");
+        let report = extract_zip_to(&zip_path, &dest, &ExtractOptions::default(), None, &mut |_| {}).unwrap();
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(fs::read_to_string(dest.join("スクリプト").join("ﾃｽﾄ.anm2")).unwrap(), "script");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn extracts_selected_subtree() {
+        let dir = temp_dir("subtree");
+        let zip_path = dir.join("bundle.zip");
+        let mut zip = ZipWriter::new(File::create(&zip_path).unwrap());
+        for name in [
+            "Bundle-1.0/readme.txt",
+            "Bundle-1.0/Plugin/a.aui2",
+            "Bundle-1.0/Plugin/docs/manual.txt",
+            "Bundle-1.0/Plugin/lib/b.dll",
+            "Bundle-1.0/samples/x.aup2",
+        ] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(name.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let selection = EntrySelection {
+            subpath: Some("bundle-1.0\\plugin".to_string()),
+            exclude: vec!["docs".to_string()],
+            ..Default::default()
+        };
+        let dest = dir.join("out");
+        let report = extract_zip_to(&zip_path, &dest, &ExtractOptions { selection, ..Default::default() }, None, &mut |_| {}).unwrap();
+        let mut written: Vec<String> = report.files.iter().map(|f| Path::new(&f.path).strip_prefix(&dest).unwrap().to_string_lossy().replace('\\', "/")).collect();
+        written.sort();
+        assert_eq!(written, ["a.aui2", "lib/b.dll"]);
+        assert_eq!(report.filtered_out, 3);
+
+        let selection = EntrySelection {
+            strip_components: 1,
+            include: vec!["**/*.dll".to_string(), "*.txt".to_string()],
+            ..Default::default()
+        };
+        let entries = select_archive_entries(zip_file_names(&zip_path, None).unwrap(), &selection).unwrap();
+        let targets: Vec<String> = entries.iter().map(|(_, rel)| rel.to_string_lossy().replace('\\', "/")).collect();
+        assert_eq!(targets, ["readme.txt", "Plugin/docs/manual.txt", "Plugin/lib/b.dll"]);
+
+        let missing = EntrySelection { subpath: Some("nothing".to_string()), ..Default::default() };
+        assert!(select_archive_entries(zip_file_names(&zip_path, None).unwrap(), &missing).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    // Plugin/a.aux2 を ZipCrypto（パスワード pw123）で暗号化した zip
+    const ENCRYPTED_ZIP: &str = concat!(
+        "504b0304140009000800b59c525d0000000000000000000000000d002000506c7567696e2f612e6175783275780b00010400000000040000000055540d000706",
+        "20d56a0620d56a0620d56a7eb5543f5828cb2cacbb0124f02f64fe951cf6cab2d1f79a9eb01705ea0dd6c3e1a96cea504b070891a8511c240000001600000050",
+        "4b01021403140009000800b59c525d91a8511c24000000160000000d0018000000000000000000a48100000000506c7567696e2f612e6175783275780b000104",
+        "00000000040000000055540500010620d56a504b05060000000001000100530000007f0000000000",
+    );
+
+    // readme.txt（レベル 0, -lh0-）、scripts/a.lua（レベル 1, -lh5-）、スクリプト/テスト.anm2（レベル 2, -lh5-, Shift_JIS）
+    const LZH_ARCHIVE: &str = concat!(
+        "20e82d6c68302d0600000006000000213c6b5a20000a726561646d652e747874f442726561646d651e662d6c68352d5c0000001e010000213c6b5a200105612e",
+        "6c7561709f550b000273637269707473ff0000003f4b6f4d452bfc0dfe4003f15c12f18d25a4c571e53d03d424000365529e777df90dbebf5ef13098804c25fe",
+        "617ccad989fb451bb8dc290b06c9d2d20f1d60fa141f4d60fd6d13eb1c4fd716eccfd5643b002d6c68352d3a0000006800000000f15365200226577705000063",
+        "cf0e00018365835883672e616e6d320e00028358834e838a83768367ff000000314daf88cd800800002aaaac04b00efcdeb01fb2760360381a0f1438a39259a7",
+        "a29aab1596dd7e18e59e9aedbf1cf5df9efdf8c8531d0952c000",
+    );
+
+    #[test]
+    fn extracts_lzh_archive() {
+        let dir = temp_dir("lzh");
+        let lzh_path = dir.join("pack.lzh");
+        let mut bytes: Vec<u8> = (0..LZH_ARCHIVE.len()).step_by(2).map(|i| u8::from_str_radix(&LZH_ARCHIVE[i..i + 2], 16).unwrap()).collect();
+        fs::write(&lzh_path, &bytes).unwrap();
+        assert_eq!(ArchiveFormat::sniff(&lzh_path).unwrap(), Some(ArchiveFormat::Lzh));
+        assert_eq!(lzh_file_names(&lzh_path).unwrap(), ["readme.txt", "scripts/a.lua", "スクリプト/テスト.anm2"]);
+
+        let dest = dir.join("out");
+        let report = extract_lzh_to(&lzh_path, &dest, &ExtractOptions::default(), None, &mut |_| {}).unwrap();
+        assert_eq!(report.files.len(), 3);
+        let lua: String = (0..12).map(|i| format!("--track{}:value,0,100,{}\n", i % 4, i * 7)).collect();
+        assert_eq!(fs::read_to_string(dest.join("scripts").join("a.lua")).unwrap(), lua);
+        let mut anm = SHIFT_JIS.encode(&"@テスト\n".repeat(8)).0.into_owned();
+        anm.extend(0..40u8);
+        assert_eq!(fs::read(dest.join("スクリプト").join("テスト.anm2")).unwrap(), anm);
+
+        // 圧縮データを壊すと CRC の不一致で失敗する
+        let at = bytes.len() - 12;
+        bytes[at] ^= 0x10;
+        fs::write(&lzh_path, &bytes).unwrap();
+        assert!(test_archive_at(&lzh_path, ArchiveFormat::Lzh, &ExtractOptions::default(), None).is_err());
+        assert!(extract_lzh_to(&lzh_path, &dir.join("broken"), &ExtractOptions::default(), None, &mut |_| {}).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rejects_lzh_headers_too_short_for_their_fields() {
+        let header = |size: u8, name_len: u8| {
+            let mut bytes = vec![0u8; 32];
+            bytes[0] = size;
+            bytes[2..7].copy_from_slice(b"-lh0-");
+            bytes[21] = name_len;
+            let end = (size as usize + 2).min(bytes.len());
+            bytes[1] = bytes[2..end].iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+            bytes
+        };
+        // 名前の長さや CRC の位置に届かないヘッダーサイズ
+        assert!(lzh::read_entries(&mut Cursor::new(header(10, 0))).is_err());
+        // 名前の後ろに CRC が収まらない
+        assert!(lzh::read_entries(&mut Cursor::new(header(22, 1))).is_err());
+    }
+
+    #[test]
+    fn sniffs_and_extracts_by_content() {
+        let dir = temp_dir("sniff");
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, data) in [("./pack/plugin.aux2", &b"aux2"[..]), ("./pack/script/a.lua", &b"--lua"[..])] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, data).unwrap();
+        }
+        let tar_bytes = builder.into_inner().unwrap();
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&tar_bytes).unwrap();
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("pack/plugin.aux2", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"aux2").unwrap();
+        // 実行ファイル部分の代わりに MZ から始まるダミーを前に付ける
+        let mut sfx = b"MZ".to_vec();
+        sfx.resize(1024, 0);
+        sfx.extend(zip.finish().unwrap().into_inner());
+
+        for (name, bytes, format) in [
+            ("pack.tar", tar_bytes.clone(), ArchiveFormat::Tar),
+            ("pack.tar.gz", gz.finish().unwrap(), ArchiveFormat::TarGz),
+            ("pack.tar.zst", zstd::encode_all(&tar_bytes[..], 0).unwrap(), ArchiveFormat::TarZst),
+            ("pack.exe", sfx, ArchiveFormat::ZipSfx),
+        ] {
+            let path = dir.join(name);
+            fs::write(&path, bytes).unwrap();
+            assert_eq!(ArchiveFormat::sniff(&path).unwrap(), Some(format), "{name}");
+            let dest = dir.join(format!("{name}.out"));
+            let report = format.extract_to(&path, &dest, &ExtractOptions::default(), None, &mut |_| {}).unwrap();
+            assert!(!report.files.is_empty(), "{name}");
+            assert_eq!(fs::read(dest.join("pack").join("plugin.aux2")).unwrap(), b"aux2", "{name}");
+        }
+        assert_eq!(fs::read(dir.join("pack.tar.zst.out").join("pack").join("script").join("a.lua")).unwrap(), b"--lua");
+
+        fs::write(dir.join("plain.gz"), flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()).finish().unwrap()).unwrap();
+        assert_eq!(ArchiveFormat::sniff(&dir.join("plain.gz")).unwrap(), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn lists_zip_entries_with_metadata() {
+        let dir = temp_dir("listing");
+        let zip_path = dir.join("pack.zip");
+        let mut zip = ZipWriter::new(File::create(&zip_path).unwrap());
+        let modified = zip::DateTime::from_date_and_time(2024, 5, 6, 7, 8, 10).unwrap();
+        zip.add_directory("MyPack/", SimpleFileOptions::default()).unwrap();
+        zip.start_file("MyPack/Plugin/a.aux2", SimpleFileOptions::default().last_modified_time(modified)).unwrap();
+        zip.write_all(&[7u8; 1000]).unwrap();
+        zip.start_file("MyPack/../../evil.txt", SimpleFileOptions::default()).unwrap();
+        zip.finish().unwrap();
+
+        let listing = list_archive(&zip_path, None).unwrap();
+        assert_eq!(listing.format, ArchiveFormat::Zip);
+        assert_eq!(listing.common_root.as_deref(), Some("MyPack"));
+        let file = &listing.entries[1];
+        assert_eq!(file.path.as_deref(), Some("MyPack/Plugin/a.aux2"));
+        assert_eq!((file.is_dir, file.size, file.crc32, file.encrypted), (false, 1000, Some(crc32(&[7u8; 1000])), false));
+        assert!(file.compressed_size < 1000);
+        assert!(file.modified.as_deref().is_some_and(|time| time.starts_with("2024-05-06T07:08:10")));
+        assert!(listing.entries[0].is_dir && listing.entries[0].crc32.is_none());
+        assert_eq!(listing.entries[2].path, None);
+
+        let entry = |path: &str, is_dir: bool| ArchiveEntryInfo {
+            name: path.to_string(),
+            path: Some(path.to_string()),
+            is_dir,
+            size: 0,
+            compressed_size: 0,
+            crc32: None,
+            modified: None,
+            encrypted: false,
+        };
+        assert_eq!(detect_common_root(&[entry("Plugin", true), entry("Plugin/a.aux2", false)]).as_deref(), Some("Plugin"));
+        assert_eq!(detect_common_root(&[entry("Root/a.lua", false), entry("readme.txt", false)]), None);
+        assert_eq!(detect_common_root(&[entry("A/a.lua", false), entry("B/b.lua", false)]), None);
+        assert_eq!(detect_common_root(&[entry("Empty", true)]), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn extracts_lists_and_tests_7z_and_sfx() {
+        // plugin/a.txt ("hello") と plugin/b.txt ("world!") を無圧縮で格納した 7z
+        let fixture = include_bytes!("fixtures/plugin.7z");
+        let dir = temp_dir("7z");
+        let mut sfx = b"MZ".to_vec();
+        sfx.resize(1024, 0);
+        sfx.extend_from_slice(fixture);
+
+        for (name, bytes, format) in [
+            ("plugin.7z", fixture.to_vec(), ArchiveFormat::SevenZip),
+            ("plugin.exe", sfx, ArchiveFormat::SevenZipSfx),
+        ] {
+            let path = dir.join(name);
+            fs::write(&path, bytes).unwrap();
+            assert_eq!(ArchiveFormat::sniff(&path).unwrap(), Some(format), "{name}");
+
+            let listing = list_archive(&path, None).unwrap();
+            assert_eq!(listing.format, format);
+            assert_eq!(listing.common_root.as_deref(), Some("plugin"), "{name}");
+            let mut entries: Vec<(&str, bool, u64, Option<u32>)> = listing.entries.iter().map(|e| (e.name.as_str(), e.is_dir, e.size, e.crc32)).collect();
+            entries.sort();
+            assert_eq!(
+                entries,
+                [
+                    ("plugin", true, 0, None),
+                    ("plugin/a.txt", false, 5, Some(crc32(b"hello"))),
+                    ("plugin/b.txt", false, 6, Some(crc32(b"world!")))
+                ]
+            );
+            let mut names = archive_file_names(&path, format).unwrap();
+            names.sort();
+            assert_eq!(names, ["plugin/a.txt", "plugin/b.txt"], "{name}");
+
+            let tested = test_archive_at(&path, format, &ExtractOptions::default(), None).unwrap();
+            assert_eq!((tested.entries, tested.bytes), (2, 11), "{name}");
+
+            let dest = dir.join(format!("{name}.out"));
+            let report = format.extract_to(&path, &dest, &ExtractOptions::default(), None, &mut |_| {}).unwrap();
+            assert_eq!(report.files.len(), 2, "{name}");
+            assert_eq!(fs::read(dest.join("plugin").join("a.txt")).unwrap(), b"hello", "{name}");
+            assert_eq!(fs::read(dest.join("plugin").join("b.txt")).unwrap(), b"world!", "{name}");
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn detects_corrupted_zip_entries() {
+        let dir = temp_dir("integrity");
+        let zip_path = dir.join("pack.zip");
+        let mut zip = ZipWriter::new(File::create(&zip_path).unwrap());
+        let stored = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("Plugin/a.aux2", stored).unwrap();
+        zip.write_all(b"first entry payload").unwrap();
+        zip.start_file("Plugin/b.aux2", stored).unwrap();
+        zip.write_all(b"second entry payload").unwrap();
+        zip.finish().unwrap();
+
+        let report = test_archive_at(&zip_path, ArchiveFormat::Zip, &ExtractOptions::default(), None).unwrap();
+        assert_eq!((report.entries, report.bytes), (2, 39));
+
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let at = Finder::new(b"second entry").find(&bytes).unwrap();
+        bytes[at] ^= 0x20;
+        fs::write(&zip_path, &bytes).unwrap();
+        let err = test_archive_at(&zip_path, ArchiveFormat::Zip, &ExtractOptions::default(), None).unwrap_err();
+        assert!(err.contains("archive is corrupted (Plugin/b.aux2)"), "{err}");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reports_password_errors_for_encrypted_zip() {
+        let dir = temp_dir("password");
+        let zip_path = dir.join("locked.zip");
+        let bytes: Vec<u8> = (0..ENCRYPTED_ZIP.len()).step_by(2).map(|i| u8::from_str_radix(&ENCRYPTED_ZIP[i..i + 2], 16).unwrap()).collect();
+        fs::write(&zip_path, bytes).unwrap();
+        let extract = |password: Option<&str>| {
+            let options = ExtractOptions { password: password.map(str::to_string), ..Default::default() };
+            extract_zip_to(&zip_path, &dir.join("out"), &options, None, &mut |_| {})
+        };
+
+        assert_eq!(extract(None).unwrap_err(), PASSWORD_REQUIRED);
+        assert_eq!(extract(Some("wrong")).unwrap_err(), WRONG_PASSWORD);
+        assert!(!dir.join("out").join("Plugin").exists());
+        assert_eq!(test_archive_at(&zip_path, ArchiveFormat::Zip, &ExtractOptions::default(), None).unwrap_err(), PASSWORD_REQUIRED);
+        assert_eq!(test_archive_at(&zip_path, ArchiveFormat::Zip, &ExtractOptions { password: Some("pw123".into()), ..Default::default() }, None).unwrap().entries, 1);
+        assert_eq!(extract(Some("pw123")).unwrap().files.len(), 1);
+        assert_eq!(fs::read_to_string(dir.join("out").join("Plugin").join("a.aux2")).unwrap(), "secret plugin payload\n");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn enforces_extract_limits() {
+        let dir = temp_dir("limits");
+        let zip_path = dir.join("bomb.zip");
+        let mut zip = ZipWriter::new(File::create(&zip_path).unwrap());
+        zip.start_file("a/b/zeros.bin", SimpleFileOptions::default()).unwrap();
+        zip.write_all(&vec![0u8; 4 << 20]).unwrap();
+        zip.start_file("readme.txt", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"readme").unwrap();
+        zip.finish().unwrap();
+
+        let dest = dir.join("out");
+        let extract = |limits: ExtractLimits| extract_zip_to(&zip_path, &dest, &ExtractOptions { limits, ..Default::default() }, None, &mut |_| {});
+        let defaults = ExtractLimits::default();
+        // ヘッダーの値で展開前に止める
+        for (limits, expected) in [
+            (ExtractLimits { max_total_bytes: 1 << 20, ..defaults }, "total size"),
+            (ExtractLimits { max_entries: 1, ..defaults }, "2 entries"),
+            (ExtractLimits { max_ratio: 100, ..defaults }, "a/b/zeros.bin expands"),
+            (ExtractLimits { max_depth: 2, ..defaults }, "a/b/zeros.bin is 3 levels deep"),
+        ] {
+            let err = extract(limits).unwrap_err();
+            assert!(err.starts_with(LIMIT_EXCEEDED) && err.contains(expected), "{err}");
+            assert!(!dest.exists());
+        }
+        assert_eq!(extract(defaults).unwrap().files.len(), 2);
+
+        // ヘッダーが偽られていても書き込んだ量で止め、書きかけのファイルを消す
+        let options = ExtractOptions {
+            limits: ExtractLimits { max_total_bytes: 1000, ..defaults },
+            ..Default::default()
+        };
+        let stream_dest = dir.join("stream");
+        let mut on_progress = |_: &ExtractProgress| {};
+        let mut writer = EntryWriter::new(&stream_dest, ExtractReport::default(), &options, None, &mut on_progress);
+        let result = writer.write("big.bin", Path::new("big.bin"), false, None, &mut io::repeat(0).take(5000)).map_err(|e| e.to_string());
+        let err = writer.finish(result).unwrap_err();
+        assert!(err.starts_with(LIMIT_EXCEEDED), "{err}");
+        assert!(!stream_dest.join("big.bin").exists() && !stream_dest.exists());
+
+        let err = test_archive_at(&zip_path, ArchiveFormat::Zip, &options, None).unwrap_err();
+        assert!(err.starts_with(LIMIT_EXCEEDED), "{err}");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn extracts_nested_archives_into_sibling_folders() {
+        let dir = temp_dir("nested");
+        let zip_bytes = |files: &[(&str, &[u8])]| {
+            let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+            for (name, data) in files {
+                zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+                zip.write_all(data).unwrap();
+            }
+            zip.finish().unwrap().into_inner()
+        };
+        let deep = zip_bytes(&[("x.txt", b"deep")]);
+        let plugin = zip_bytes(&[("Plugin/a.aux2", b"plugin"), ("deep.zip", &deep)]);
+        let zip_path = dir.join("release.zip");
+        fs::write(&zip_path, zip_bytes(&[("readme.txt", b"readme"), ("Release/Plugin_x64.zip", &plugin)])).unwrap();
+
+        let extract = |max_depth: usize| {
+            let dest = dir.join(format!("out{max_depth}"));
+            let options = ExtractOptions {
+                nested: Some(NestedExtract { pattern: "*.ZIP".to_string(), max_depth }),
+                ..Default::default()
+            };
+            let report = ArchiveFormat::Zip.extract_to(&zip_path, &dest, &options, None, &mut |_| {}).unwrap();
+            (dest, report)
+        };
+        let (dest, report) = extract(1);
+        assert_eq!(fs::read(dest.join("Release/Plugin_x64/Plugin/a.aux2")).unwrap(), b"plugin");
+        assert!(dest.join("Release/Plugin_x64/deep.zip").is_file() && !dest.join("Release/Plugin_x64/deep").exists());
+        assert!(report.files.iter().any(|f| f.entry == "Release/Plugin_x64.zip/Plugin/a.aux2"));
+        assert_eq!(report.nested.len(), 1);
+        assert_eq!(report.nested[0].entry, "Release/Plugin_x64.zip");
+
+        let (dest, report) = extract(2);
+        assert_eq!(fs::read(dest.join("Release/Plugin_x64/deep/x.txt")).unwrap(), b"deep");
+        assert_eq!(report.nested.iter().map(|n| n.depth).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(nested_dest(Path::new("a/pkg.tar.gz")), Path::new("a/pkg"));
+        assert_eq!(nested_dest(Path::new("a/payload")), Path::new("a/payload_extracted"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn copies_with_policies_and_reports_previous_hashes() {
+        let dir = temp_dir("copy");
+        let (src, dst) = (dir.join("src"), dir.join("dst"));
+        fs::create_dir_all(src.join("Plugin")).unwrap();
+        fs::create_dir_all(&dst).unwrap();
+        fs::write(src.join("Plugin/a.aui2"), b"new").unwrap();
+        fs::write(src.join("readme.txt"), b"readme").unwrap();
+        fs::write(dst.join("readme.txt"), b"old").unwrap();
+        let copy = |policy: CopyPolicy| copy_item(&src, &dst, &CopyOptions { policy, ..Default::default() });
+
+        let err = copy(CopyPolicy::FailOnConflict).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(!dst.join("Plugin").exists());
+
+        let report = copy(CopyPolicy::SkipExisting).unwrap();
+        assert_eq!(report.created, vec![dst.join("Plugin/a.aui2").to_string_lossy().into_owned()]);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(fs::read(dst.join("readme.txt")).unwrap(), b"old");
+
+        let report = copy(CopyPolicy::BackupThenOverwrite).unwrap();
+        assert_eq!(report.overwritten.len(), 2);
+        let readme = report.overwritten.iter().find(|f| f.path.ends_with("readme.txt")).unwrap();
+        assert_eq!(readme.previous_hash, super::super::version::xxh3_128_hex_reader(&mut &b"old"[..]).unwrap());
+        assert_eq!(fs::read(readme.backup.as_ref().unwrap()).unwrap(), b"old");
+        assert_eq!(fs::read(dst.join("readme.txt")).unwrap(), b"readme");
+
+        // コピー先の方が新しければ残す
+        let options = CopyOptions {
+            policy: CopyPolicy::OnlyIfNewer,
+            exclude: vec!["*.txt".to_string()],
+            preserve_mtime: true,
+            ..Default::default()
+        };
+        let old = std::time::SystemTime::now() - Duration::from_secs(3600);
+        File::options().write(true).open(src.join("Plugin/a.aui2")).unwrap().set_modified(old).unwrap();
+        let report = copy_item(&src, &dst, &options).unwrap();
+        assert_eq!((report.skipped.len(), report.filtered_out), (1, 1));
+        fs::remove_file(dst.join("Plugin/a.aui2")).unwrap();
+        copy_item(&src, &dst, &options).unwrap();
+        assert_eq!(fs::metadata(dst.join("Plugin/a.aui2")).unwrap().modified().unwrap(), old);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cancelled_extraction_removes_partial_output() {
+        let dir = temp_dir("cancel");
+        let zip_path = dir.join("large.zip");
+        let mut zip = ZipWriter::new(File::create(&zip_path).unwrap());
+        for name in ["a/1.bin", "a/2.bin", "b/3.bin"] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(&[0u8; 4096]).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let dest = dir.join("out");
+        fs::create_dir_all(dest.join("a")).unwrap();
+        fs::write(dest.join("a").join("keep.txt"), "keep").unwrap();
+        let cancel = AtomicBool::new(false);
+        let mut last = ExtractProgress::default();
+        let err = extract_zip_to(&zip_path, &dest, &ExtractOptions::default(), Some(&cancel), &mut |progress| {
+            last = *progress;
+            if progress.entries == 2 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        })
+        .unwrap_err();
+        assert_eq!(err, CANCELLED);
+        assert_eq!((last.entries, last.total_entries, last.bytes, last.total_bytes), (2, 3, 8192, 12288));
+        assert!(!dest.join("a").join("1.bin").exists());
+        assert!(!dest.join("b").exists());
+        assert_eq!(fs::read_to_string(dest.join("a").join("keep.txt")).unwrap(), "keep");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn normalizes_entry_paths() {
+        assert_eq!(normalize_entry_path("a/b.txt"), Ok(Some(PathBuf::from("a").join("b.txt"))));
+        assert_eq!(normalize_entry_path("a\\.\\b.txt"), Ok(Some(PathBuf::from("a").join("b.txt"))));
+        assert_eq!(normalize_entry_path("a/../b.txt"), Ok(Some(PathBuf::from("b.txt"))));
+        assert_eq!(normalize_entry_path("./"), Ok(None));
+        assert_eq!(normalize_entry_path("../b.txt"), Err(UnsafeEntryReason::ParentTraversal));
+        assert_eq!(normalize_entry_path("a/../../b.txt"), Err(UnsafeEntryReason::ParentTraversal));
+        assert_eq!(normalize_entry_path("..\\b.txt"), Err(UnsafeEntryReason::ParentTraversal));
+        assert_eq!(normalize_entry_path("/etc/passwd"), Err(UnsafeEntryReason::AbsolutePath));
+        assert_eq!(normalize_entry_path("\\\\server\\share\\x"), Err(UnsafeEntryReason::AbsolutePath));
+        assert_eq!(normalize_entry_path("C:\\Windows\\x.dll"), Err(UnsafeEntryReason::DriveLetter));
+        assert_eq!(normalize_entry_path("d:x.dll"), Err(UnsafeEntryReason::DriveLetter));
+        assert_eq!(normalize_entry_path("a/b.txt:ads"), Err(UnsafeEntryReason::InvalidName));
+        assert_eq!(normalize_entry_path("a/nul"), Err(UnsafeEntryReason::InvalidName));
+        assert_eq!(normalize_entry_path("a/com1.txt"), Err(UnsafeEntryReason::InvalidName));
+        assert_eq!(normalize_entry_path("a/com10.txt"), Ok(Some(PathBuf::from("a").join("com10.txt"))));
+        assert_eq!(normalize_entry_path("a/b\u{0}.txt"), Err(UnsafeEntryReason::InvalidName));
+    }
+
+    #[test]
+    fn rejects_malicious_zip_without_writing() {
+        let dir = temp_dir("reject");
+        let zip_path = malicious_zip(&dir);
+        let dest = dir.join("out");
+        let err = extract_zip_to(&zip_path, &dest, &ExtractOptions::default(), None, &mut |_| {}).unwrap_err();
+        assert!(err.contains("unsafe entries"), "{err}");
+        assert!(!dest.exists());
+        assert!(!dir.join("evil.txt").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn skips_and_reports_malicious_zip_entries() {
+        let dir = temp_dir("skip");
+        let zip_path = malicious_zip(&dir);
+        let dest = dir.join("out");
+        let report = extract_zip_to(&zip_path, &dest, &ExtractOptions { unsafe_entries: UnsafeEntryPolicy::Skip, ..Default::default() }, None, &mut |_| {}).unwrap();
+
+        let entries: Vec<&str> = report.files.iter().map(|f| f.entry.as_str()).collect();
+        assert_eq!(entries, ["good/readme.txt"]);
+        assert_eq!(fs::read_to_string(dest.join("good").join("readme.txt")).unwrap(), "good/readme.txt");
+
+        let rejected: HashMap<&str, UnsafeEntryReason> = report.rejected.iter().map(|r| (r.entry.as_str(), r.reason)).collect();
+        assert_eq!(rejected.len(), 9);
+        assert_eq!(rejected["../evil.txt"], UnsafeEntryReason::ParentTraversal);
+        assert_eq!(rejected["good/../../evil2.txt"], UnsafeEntryReason::ParentTraversal);
+        assert_eq!(rejected["..\\evil3.txt"], UnsafeEntryReason::ParentTraversal);
+        assert_eq!(rejected["/abs.txt"], UnsafeEntryReason::AbsolutePath);
+        assert_eq!(rejected["C:/drive.txt"], UnsafeEntryReason::DriveLetter);
+        assert_eq!(rejected["c:evil4.txt"], UnsafeEntryReason::DriveLetter);
+        assert_eq!(rejected["good/file.txt:stream"], UnsafeEntryReason::InvalidName);
+        assert_eq!(rejected["good/CON.txt"], UnsafeEntryReason::InvalidName);
+        assert_eq!(rejected["good/link"], UnsafeEntryReason::Symlink);
+
+        for escaped in ["evil.txt", "evil2.txt", "evil3.txt", "outside"] {
+            assert!(!dir.join(escaped).exists(), "{escaped} escaped the destination");
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -1,8 +1,11 @@
 use percent_encoding::percent_decode_str;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use tauri::{Emitter, Manager, WebviewUrl, WebviewWindowBuilder, webview::PageLoadEvent};
 use url::Url;
 
+use super::pathutil::is_abs;
+
 #[derive(thiserror::Error, Debug, serde::Serialize)]
 pub enum DriveError {
     #[error("io error: {0}")]
@@ -13,10 +16,8 @@ pub enum DriveError {
     Net(String),
 }
 
-fn is_abs(p: &str) -> bool {
-    let s = p.replace('\\', "/");
-    s.starts_with('/') || (s.len() >= 3 && s.as_bytes()[1] == b':' && (s.as_bytes()[2] == b'/' || s.as_bytes()[2] == b'\\'))
-}
+/// キャンセルによって中断したことを示すエラー文字列
+pub const CANCELLED: &str = "CANCELLED";
 
 fn resolve_rel_to_app_config(app: &tauri::AppHandle, p: &str) -> PathBuf {
     if is_abs(p) { PathBuf::from(p) } else { app.path().app_config_dir().unwrap_or_else(|_| std::env::temp_dir()).join(p) }
@@ -138,91 +139,132 @@ pub async fn drive_download_to_file(window: tauri::Window, file_id: String, dest
     Ok(final_dest_str)
 }
 
-#[tauri::command]
-pub async fn download_file_to_path(window: tauri::Window, url: String, dest_path: String, task_id: Option<String>) -> Result<String, String> {
-    use std::fs::{OpenOptions, create_dir_all};
+fn url_file_name(url: &Url) -> String {
+    let file_name_raw = url.path_segments().and_then(|mut segments| segments.rfind(|s| !s.is_empty())).map(|s| s.to_string()).unwrap_or_else(|| "download.bin".to_string());
+    let file_name = percent_decode_str(&file_name_raw).decode_utf8_lossy().to_string();
+    sanitize_filename(&file_name)
+}
+
+pub(crate) fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder().user_agent("AviUtl2Catalog").build().map_err(|e| format!("failed to build http client: {}", e))
+}
+
+fn prepare_dest_dir(dest_dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dest_dir).map_err(|e| format!("failed to prepare destination directory: {}", e))
+}
+
+/// レスポンス本体をファイルへ書き出す（キャンセルされた場合は書きかけのファイルを削除する）
+pub(crate) async fn write_response_to_file(
+    response: &mut reqwest::Response,
+    path: &Path,
+    cancel: Option<&AtomicBool>,
+    on_progress: &mut (dyn FnMut(u64, Option<u64>) + Send),
+) -> Result<(), String> {
     use std::io::Write;
 
-    if !url.trim_start().to_ascii_lowercase().starts_with("https://") {
-        return Err("Only https:// is permitted".to_string());
-    }
-    if dest_path.trim().is_empty() {
-        return Err("dest_path must not be empty".to_string());
+    let total_opt = response.content_length();
+    let mut file = std::fs::OpenOptions::new().create(true).truncate(true).write(true).open(path).map_err(|e| format!("failed to open destination file: {}", e))?;
+    let mut written: u64 = 0;
+    while let Some(chunk) = response.chunk().await.map_err(|e| format!("read error: {}", e))? {
+        if cancel.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+            drop(file);
+            let _ = std::fs::remove_file(path);
+            return Err(CANCELLED.to_string());
+        }
+        file.write_all(&chunk).map_err(|e| format!("write error: {}", e))?;
+        written += chunk.len() as u64;
+        on_progress(written, total_opt);
     }
+    Ok(())
+}
 
-    let app = window.app_handle();
-    let task_id = task_id.unwrap_or_else(|| format!("download-{}", chrono::Utc::now().timestamp_micros()));
-    let dest_dir = resolve_rel_to_app_config(app, &dest_path);
-    if let Err(e) = create_dir_all(&dest_dir) {
-        let msg = format!("failed to prepare destination directory: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        return Err(msg);
+/// https の URL から dest_dir にダウンロードし、保存先のパスを返す
+pub(crate) async fn fetch_url_to_dir(url: &str, dest_dir: &Path, cancel: Option<&AtomicBool>, on_progress: &mut (dyn FnMut(u64, Option<u64>) + Send)) -> Result<PathBuf, String> {
+    if !url.trim_start().to_ascii_lowercase().starts_with("https://") {
+        return Err("Only https:// is permitted".to_string());
     }
+    fetch_to_dir(url, dest_dir, cancel, on_progress).await
+}
 
-    let parsed_url = Url::parse(&url).map_err(|e| {
-        let msg = format!("invalid url: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        msg
-    })?;
-    let file_name_raw = parsed_url.path_segments().and_then(|mut segments| segments.rfind(|s| !s.is_empty())).map(|s| s.to_string()).unwrap_or_else(|| "download.bin".to_string());
-    let file_name = percent_decode_str(&file_name_raw).decode_utf8_lossy().to_string();
-    let final_name = sanitize_filename(&file_name);
-    let final_path = dest_dir.join(final_name);
-
-    let client = reqwest::Client::builder().user_agent("AviUtl2Catalog").build().map_err(|e| {
-        let msg = format!("failed to build http client: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        msg
-    })?;
-
-    let mut response = client.get(&url).send().await.map_err(|e| {
-        let msg = format!("network error: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        tracing::error!("download failed (url={}): {}", url, e);
-        msg
-    })?;
+/// url から dest_dir にダウンロードする（スキームは呼び出し側で確かめる）
+pub(crate) async fn fetch_to_dir(url: &str, dest_dir: &Path, cancel: Option<&AtomicBool>, on_progress: &mut (dyn FnMut(u64, Option<u64>) + Send)) -> Result<PathBuf, String> {
+    prepare_dest_dir(dest_dir)?;
+    let parsed_url = Url::parse(url).map_err(|e| format!("invalid url: {}", e))?;
+    let final_path = dest_dir.join(url_file_name(&parsed_url));
 
+    let mut response = http_client()?.get(url).send().await.map_err(|e| format!("network error: {}", e))?;
     let status = response.status();
     if !status.is_success() {
         let text = response.text().await.unwrap_or_default();
         let body_snippet: String = if text.len() > 500 { text[..500].to_string() } else { text };
-        let msg = if body_snippet.is_empty() { format!("HTTP error: {}", status) } else { format!("HTTP error: {}: {}", status, body_snippet) };
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        tracing::error!("download failed (url={}): {}", url, msg);
-        return Err(msg);
+        return Err(if body_snippet.is_empty() { format!("HTTP error: {}", status) } else { format!("HTTP error: {}: {}", status, body_snippet) });
     }
+    write_response_to_file(&mut response, &final_path, cancel, on_progress).await?;
+    Ok(final_path)
+}
 
-    let total_opt = response.content_length();
-    let mut file = OpenOptions::new().create(true).truncate(true).write(true).open(&final_path).map_err(|e| {
-        let msg = format!("failed to open destination file: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        tracing::error!("download failed (url={}): {}", url, msg);
-        msg
-    })?;
+/// ダウンロードしたファイルのサイズが期待値と一致するか確かめる（不一致の場合はファイルを削除する）
+pub(crate) fn verify_downloaded_size(path: &Path, expected: Option<u64>) -> Result<(), String> {
+    let Some(expected) = expected.filter(|size| *size > 0) else {
+        return Ok(());
+    };
+    let actual = std::fs::metadata(path).map_err(|e| format!("failed to stat downloaded file: {}", e))?.len();
+    if actual != expected {
+        let _ = std::fs::remove_file(path);
+        return Err(format!("downloaded size mismatch: expected {} bytes, got {} bytes", expected, actual));
+    }
+    Ok(())
+}
 
-    let mut written: u64 = 0;
-    while let Some(chunk) = response.chunk().await.map_err(|e| {
-        let msg = format!("read error: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        tracing::error!("download failed (url={}): {}", url, msg);
-        msg
-    })? {
-        file.write_all(&chunk).map_err(|e| {
-            let msg = format!("write error: {}", e);
-            let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-            tracing::error!("download failed (url={}): {}", url, msg);
-            msg
-        })?;
-        written += chunk.len() as u64;
+/// Google Drive のファイルを dest_dir にダウンロードする（ファイル名はレスポンスヘッダーから決める）
+pub(crate) async fn fetch_drive_to_dir(
+    file_id: &str,
+    dest_dir: &Path,
+    cancel: Option<&AtomicBool>,
+    on_progress: &mut (dyn FnMut(u64, Option<u64>) + Send),
+) -> Result<PathBuf, String> {
+    let mut response = drive_fetch_response(file_id).await.map_err(|e| e.to_string())?;
+    prepare_dest_dir(dest_dir)?;
+    let name = drive_filename_from_headers(response.headers()).ok_or_else(|| "missing filename in Google Drive response".to_string())?;
+    let final_path = dest_dir.join(name);
+    write_response_to_file(&mut response, &final_path, cancel, on_progress).await?;
+    Ok(final_path)
+}
+
+#[tauri::command]
+pub async fn download_file_to_path(window: tauri::Window, url: String, dest_path: String, task_id: Option<String>, expected_size: Option<u64>) -> Result<String, String> {
+    if !url.trim_start().to_ascii_lowercase().starts_with("https://") {
+        return Err("Only https:// is permitted".to_string());
+    }
+    if dest_path.trim().is_empty() {
+        return Err("dest_path must not be empty".to_string());
+    }
+
+    let app = window.app_handle();
+    let task_id = task_id.unwrap_or_else(|| format!("download-{}", chrono::Utc::now().timestamp_micros()));
+    let dest_dir = resolve_rel_to_app_config(app, &dest_path);
+    let mut on_progress = |read: u64, total: Option<u64>| {
         let _ = window.emit(
             "download:progress",
             serde_json::json!({
                 "taskId": task_id,
-                "read": written,
-                "total": total_opt,
+                "read": read,
+                "total": total,
             }),
         );
-    }
+    };
+    let result = match fetch_url_to_dir(&url, &dest_dir, None, &mut on_progress).await {
+        Ok(path) => verify_downloaded_size(&path, expected_size).map(|_| path),
+        Err(msg) => Err(msg),
+    };
+    let final_path = match result {
+        Ok(path) => path,
+        Err(msg) => {
+            let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
+            tracing::error!("download failed (url={}): {}", url, msg);
+            return Err(msg);
+        }
+    };
 
     let final_path_str = final_path.to_string_lossy().to_string();
     let _ = window.emit(
@@ -325,6 +367,48 @@ pub fn close_booth_auth_window(app: tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// BOOTH のログインセッション（認証用ウィンドウの Cookie）を使って dest_dir にダウンロードする
+pub(crate) async fn fetch_booth_to_dir(
+    app: &tauri::AppHandle,
+    url: &str,
+    dest_dir: &Path,
+    session_label: &str,
+    cancel: Option<&AtomicBool>,
+    on_progress: &mut (dyn FnMut(u64, Option<u64>) + Send),
+) -> Result<PathBuf, String> {
+    use reqwest::header::{CONTENT_DISPOSITION, CONTENT_TYPE, COOKIE};
+
+    if !url.trim_start().to_ascii_lowercase().starts_with("https://") {
+        return Err("Only https:// is permitted".to_string());
+    }
+    prepare_dest_dir(dest_dir)?;
+    let parsed_url = Url::parse(url).map_err(|e| format!("invalid url: {}", e))?;
+    let session_window = app.get_webview_window(session_label).ok_or_else(|| "AUTH_WINDOW_MISSING".to_string())?;
+    let cookies = session_window.cookies_for_url(parsed_url.clone()).map_err(|e| format!("AUTH_COOKIE_FETCH_FAILED: {}", e))?;
+    let cookie_header = cookies.iter().map(|c| format!("{}={}", c.name(), c.value())).collect::<Vec<_>>().join("; ");
+    let final_path = dest_dir.join(url_file_name(&parsed_url));
+
+    let mut req = http_client()?.get(url);
+    if !cookie_header.is_empty() {
+        req = req.header(COOKIE, cookie_header);
+    }
+    let mut response = req.send().await.map_err(|e| format!("network error: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP_ERROR:{} {}", status.as_u16(), status));
+    }
+    if is_booth_login_url(response.url()) {
+        return Err("AUTH_REQUIRED".to_string());
+    }
+    let content_type = response.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or("").to_ascii_lowercase();
+    if content_type.contains("text/html") && response.headers().get(CONTENT_DISPOSITION).is_none() {
+        return Err("AUTH_REQUIRED".to_string());
+    }
+    write_response_to_file(&mut response, &final_path, cancel, on_progress).await?;
+    Ok(final_path)
+}
+
 #[tauri::command]
 pub async fn download_file_to_path_booth(
     window: tauri::Window,
@@ -333,10 +417,6 @@ pub async fn download_file_to_path_booth(
     task_id: Option<String>,
     session_window_label: Option<String>,
 ) -> Result<String, String> {
-    use reqwest::header::{CONTENT_DISPOSITION, CONTENT_TYPE, COOKIE};
-    use std::fs::{OpenOptions, create_dir_all};
-    use std::io::Write;
-
     if !url.trim_start().to_ascii_lowercase().starts_with("https://") {
         return Err("Only https:// is permitted".to_string());
     }
@@ -347,113 +427,26 @@ pub async fn download_file_to_path_booth(
     let app = window.app_handle();
     let task_id = task_id.unwrap_or_else(|| format!("download-{}", chrono::Utc::now().timestamp_micros()));
     let dest_dir = resolve_rel_to_app_config(app, &dest_path);
-    if let Err(e) = create_dir_all(&dest_dir) {
-        let msg = format!("failed to prepare destination directory: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        return Err(msg);
-    }
-
-    let parsed_url = Url::parse(&url).map_err(|e| {
-        let msg = format!("invalid url: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        msg
-    })?;
-
     let session_label = session_window_label.unwrap_or_else(|| "booth-auth".to_string()).trim().to_string();
     let session_label = if session_label.is_empty() { "booth-auth".to_string() } else { session_label };
-    let session_window = match app.get_webview_window(&session_label) {
-        Some(w) => w,
-        None => {
-            let msg = "AUTH_WINDOW_MISSING".to_string();
-            let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-            return Err(msg);
-        }
-    };
-
-    let cookies = session_window.cookies_for_url(parsed_url.clone()).map_err(|e| {
-        let msg = format!("AUTH_COOKIE_FETCH_FAILED: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        msg
-    })?;
-    let cookie_header = cookies.iter().map(|c| format!("{}={}", c.name(), c.value())).collect::<Vec<_>>().join("; ");
-
-    let file_name_raw = parsed_url.path_segments().and_then(|mut segments| segments.rfind(|s| !s.is_empty())).map(|s| s.to_string()).unwrap_or_else(|| "download.bin".to_string());
-    let file_name = percent_decode_str(&file_name_raw).decode_utf8_lossy().to_string();
-    let final_name = sanitize_filename(&file_name);
-    let final_path = dest_dir.join(final_name);
-
-    let client = reqwest::Client::builder().user_agent("AviUtl2Catalog").build().map_err(|e| {
-        let msg = format!("failed to build http client: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        msg
-    })?;
-
-    let mut req = client.get(&url);
-    if !cookie_header.is_empty() {
-        req = req.header(COOKIE, cookie_header);
-    }
-
-    let mut response = req.send().await.map_err(|e| {
-        let msg = format!("network error: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        tracing::error!("booth download failed (url={}): {}", url, e);
-        msg
-    })?;
-
-    let status = response.status();
-    if !status.is_success() {
-        let msg = format!("HTTP_ERROR:{} {}", status.as_u16(), status);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        tracing::error!("booth download failed (url={}): {}", url, msg);
-        return Err(msg);
-    }
-
-    let final_url = response.url().clone();
-    if is_booth_login_url(&final_url) {
-        let msg = "AUTH_REQUIRED".to_string();
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        return Err(msg);
-    }
-
-    let content_type = response.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or("").to_ascii_lowercase();
-    let content_disposition = response.headers().get(CONTENT_DISPOSITION);
-    if content_type.contains("text/html") && content_disposition.is_none() {
-        let msg = "AUTH_REQUIRED".to_string();
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        return Err(msg);
-    }
-
-    let total_opt = response.content_length();
-    let mut file = OpenOptions::new().create(true).truncate(true).write(true).open(&final_path).map_err(|e| {
-        let msg = format!("failed to open destination file: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        tracing::error!("booth download failed (url={}): {}", url, msg);
-        msg
-    })?;
-
-    let mut written: u64 = 0;
-    while let Some(chunk) = response.chunk().await.map_err(|e| {
-        let msg = format!("read error: {}", e);
-        let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-        tracing::error!("booth download failed (url={}): {}", url, msg);
-        msg
-    })? {
-        file.write_all(&chunk).map_err(|e| {
-            let msg = format!("write error: {}", e);
-            let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
-            tracing::error!("booth download failed (url={}): {}", url, msg);
-            msg
-        })?;
-        written += chunk.len() as u64;
+    let mut on_progress = |read: u64, total: Option<u64>| {
         let _ = window.emit(
             "download:progress",
             serde_json::json!({
                 "taskId": task_id,
-                "read": written,
-                "total": total_opt,
+                "read": read,
+                "total": total,
             }),
         );
-    }
+    };
+    let final_path = match fetch_booth_to_dir(app, &url, &dest_dir, &session_label, None, &mut on_progress).await {
+        Ok(path) => path,
+        Err(msg) => {
+            let _ = window.emit("download:error", serde_json::json!({ "taskId": task_id, "message": msg }));
+            tracing::error!("booth download failed (url={}): {}", url, msg);
+            return Err(msg);
+        }
+    };
 
     let final_path_str = final_path.to_string_lossy().to_string();
     let _ = window.emit(
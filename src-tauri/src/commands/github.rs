@@ -0,0 +1,502 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+const DEFAULT_API_BASE: &str = "https://api.github.com";
+
+#[derive(Debug, Clone)]
+struct GithubApiConfig {
+    base_url: String,
+    token: Option<String>,
+}
+
+static API_CONFIG: Lazy<RwLock<GithubApiConfig>> = Lazy::new(|| RwLock::new(GithubApiConfig { base_url: DEFAULT_API_BASE.to_string(), token: None }));
+
+// URL → ETag とレスポンス本体（初回アクセス時にファイルから読み込む）
+static ETAG_CACHE: Lazy<Mutex<Option<HashMap<String, CachedResponse>>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "version", content = "data")]
+enum GithubCacheRoot {
+    #[serde(rename = "1")]
+    V1(HashMap<String, CachedResponse>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedResponse {
+    etag: String,
+    body: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ReleaseAsset {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    browser_download_url: String,
+    #[serde(default)]
+    size: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Release {
+    #[serde(default)]
+    tag_name: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    published_at: Option<String>,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+}
+
+impl Release {
+    fn timestamp(&self) -> i64 {
+        self.published_at.as_deref().or(self.created_at.as_deref()).and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok()).map(|t| t.timestamp()).unwrap_or(0)
+    }
+
+    fn label(&self) -> String {
+        let label = self.name.as_deref().map(str::trim).filter(|s| !s.is_empty()).unwrap_or(&self.tag_name);
+        if self.prerelease { format!("{label} (prerelease)") } else { label.to_string() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubReleaseAsset {
+    pub url: String,
+    pub name: String,
+    pub size: u64,
+    pub tag: String,
+    pub prerelease: bool,
+    pub published_at: Option<String>,
+}
+
+/// アセット名のパターン（既定は正規表現。"glob:" で始まる場合だけグロブとして扱う）
+enum AssetPattern {
+    Regex(regex::Regex),
+    Glob(glob::Pattern),
+}
+
+impl AssetPattern {
+    fn parse(pattern: &str) -> Result<Self, String> {
+        if let Some(glob) = pattern.strip_prefix("glob:") {
+            return glob::Pattern::new(glob).map(Self::Glob).map_err(|e| format!("GitHub asset pattern is invalid: {e}"));
+        }
+        regex::Regex::new(pattern).map(Self::Regex).map_err(|e| format!("GitHub asset pattern is invalid: {e}"))
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Regex(re) => re.is_match(name),
+            Self::Glob(glob) => glob.matches_with(name, glob::MatchOptions { case_sensitive: false, ..Default::default() }),
+        }
+    }
+}
+
+/// API の呼び出し先と ETag キャッシュの保存先（テストではローカルのサーバーと一時ファイルを指す）
+struct GithubApi {
+    config: GithubApiConfig,
+    cache_file: PathBuf,
+}
+
+impl GithubApi {
+    fn current(app: &tauri::AppHandle) -> Self {
+        let config = API_CONFIG.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let cache_file = app.path().app_config_dir().unwrap_or_else(|_| std::env::temp_dir()).join("github-cache.json");
+        Self { config, cache_file }
+    }
+}
+
+fn load_cache(path: &Path) -> HashMap<String, CachedResponse> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<GithubCacheRoot>(&text) {
+        Ok(GithubCacheRoot::V1(map)) => map,
+        Err(e) => {
+            tracing::warn!("Failed to parse GitHub cache {}: {}", path.display(), e);
+            HashMap::new()
+        }
+    }
+}
+
+fn cached_response(path: &Path, url: &str) -> Option<CachedResponse> {
+    let mut guard = ETAG_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    guard.get_or_insert_with(|| load_cache(path)).get(url).cloned()
+}
+
+fn store_response(path: &Path, url: &str, entry: CachedResponse) {
+    let mut guard = ETAG_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = guard.get_or_insert_with(|| load_cache(path));
+    cache.insert(url.to_string(), entry);
+    let tmp = path.with_extension("json.tmp");
+    let result = serde_json::to_vec(&GithubCacheRoot::V1(cache.clone()))
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(&tmp, json).map_err(|e| e.to_string()))
+        .and_then(|_| std::fs::rename(&tmp, path).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        tracing::warn!("Failed to write GitHub cache {}: {}", path.display(), e);
+    }
+}
+
+fn rate_limit_error(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if header("x-ratelimit-remaining") != Some("0") {
+        return None;
+    }
+    let reset_at = header("x-ratelimit-reset").and_then(|v| v.parse::<i64>().ok()).filter(|v| *v > 0)?;
+    let reset_at = chrono::DateTime::from_timestamp(reset_at, 0)?.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string();
+    Some(crate::paths::common_message_with_args(crate::paths::current_ui_locale(), "backend.errors.githubRateLimit", &[("resetAt", &reset_at)]))
+}
+
+/// 304 やレート制限の応答にキャッシュ済みのレスポンスで答えられるか（None はそのまま応答を処理する）
+fn cached_fallback(status: reqwest::StatusCode, headers: &reqwest::header::HeaderMap, cached: Option<CachedResponse>, url: &str) -> Option<Result<serde_json::Value, String>> {
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        return cached.map(|cached| Ok(cached.body));
+    }
+    if status != reqwest::StatusCode::FORBIDDEN && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    let message = rate_limit_error(headers)?;
+    Some(match cached {
+        Some(cached) => {
+            tracing::warn!("GitHub API rate limit reached; using cached response for {}", url);
+            Ok(cached.body)
+        }
+        None => Err(message),
+    })
+}
+
+/// GitHub API に GET し、JSON を返す（404 は None）
+/// ETag が一致した場合やレート制限に達した場合はキャッシュ済みのレスポンスを使う
+async fn get_json(api: &GithubApi, path: &str) -> Result<Option<serde_json::Value>, String> {
+    let url = format!("{}{}", api.config.base_url, path);
+    let cached = cached_response(&api.cache_file, &url);
+    let mut request = super::download::http_client()?.get(&url).header("Accept", "application/vnd.github+json").header("X-GitHub-Api-Version", "2022-11-28");
+    if let Some(token) = &api.config.token {
+        request = request.bearer_auth(token);
+    }
+    if let Some(cached) = &cached {
+        request = request.header("If-None-Match", &cached.etag);
+    }
+    let response = request.send().await.map_err(|e| format!("network error: {}", e))?;
+    let status = response.status();
+    if let Some(result) = cached_fallback(status, response.headers(), cached, &url) {
+        return result.map(Some);
+    }
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(format!("GitHub API returned HTTP {} ({})", status, url));
+    }
+    let etag = response.headers().get(reqwest::header::ETAG).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let bytes = response.bytes().await.map_err(|e| format!("read error: {}", e))?;
+    let body: serde_json::Value = serde_json::from_slice(&bytes).map_err(|e| format!("failed to parse GitHub API response: {}", e))?;
+    if let Some(etag) = etag {
+        store_response(&api.cache_file, &url, CachedResponse { etag, body: body.clone() });
+    }
+    Ok(Some(body))
+}
+
+// タグ名は "1.2.0" と "v1.2.0" の両方の表記を試す
+fn tag_candidates(version: &str) -> Vec<String> {
+    let bare = version.strip_prefix(['v', 'V']).unwrap_or(version);
+    let mut tags = vec![version.to_string()];
+    for tag in [bare.to_string(), format!("v{bare}")] {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+async fn find_release(api: &GithubApi, owner: &str, repo: &str, version: Option<&str>) -> Result<Release, String> {
+    let releases: Vec<Release> = match get_json(api, &format!("/repos/{owner}/{repo}/releases?per_page=30")).await? {
+        Some(body) => serde_json::from_value(body).map_err(|e| format!("failed to parse GitHub releases: {}", e))?,
+        None => Vec::new(),
+    };
+    let releases: Vec<Release> = releases.into_iter().filter(|r| !r.draft).collect();
+
+    if let Some(version) = version.map(str::trim).filter(|v| !v.is_empty()) {
+        let tags = tag_candidates(version);
+        if let Some(release) = releases.iter().find(|r| tags.contains(&r.tag_name)) {
+            return Ok(release.clone());
+        }
+        // 一覧に含まれない古いリリースはタグから直接引く
+        for tag in &tags {
+            let path = format!("/repos/{owner}/{repo}/releases/tags/{}", percent_encoding::utf8_percent_encode(tag, percent_encoding::NON_ALPHANUMERIC));
+            if let Some(body) = get_json(api, &path).await? {
+                return serde_json::from_value(body).map_err(|e| format!("failed to parse GitHub release: {}", e));
+            }
+        }
+        return Err(format!("GitHub release not found: {owner}/{repo} version {version}"));
+    }
+
+    let release = newest_release(&releases).ok_or_else(|| format!("GitHub release not found: {owner}/{repo}"))?;
+    if release.prerelease {
+        tracing::warn!("No stable release in {}/{}; using prerelease {}", owner, repo, release.tag_name);
+    }
+    Ok(release.clone())
+}
+
+// 正式版が無い場合に限りプレリリースを使う
+fn newest_release(releases: &[Release]) -> Option<&Release> {
+    let newest = |prerelease: bool| releases.iter().filter(|r| !r.draft && r.prerelease == prerelease).max_by_key(|r| r.timestamp());
+    newest(false).or_else(|| newest(true))
+}
+
+/// githubRelease ソースのダウンロード URL を解決する（version を省略した場合は最新の正式版）
+pub(crate) async fn resolve_release_asset(app: &tauri::AppHandle, owner: &str, repo: &str, pattern: &str, version: Option<&str>) -> Result<GithubReleaseAsset, String> {
+    resolve_asset_with(&GithubApi::current(app), owner, repo, pattern, version).await
+}
+
+async fn resolve_asset_with(api: &GithubApi, owner: &str, repo: &str, pattern: &str, version: Option<&str>) -> Result<GithubReleaseAsset, String> {
+    let matcher = AssetPattern::parse(pattern)?;
+    let release = find_release(api, owner, repo, version).await?;
+    let asset = release
+        .assets
+        .iter()
+        .find(|a| matcher.matches(&a.name) && !a.browser_download_url.is_empty())
+        .ok_or_else(|| format!("GitHub release asset not found for pattern \"{pattern}\" in {owner}/{repo} release {}", release.label()))?;
+    tracing::info!("Resolved GitHub asset {}/{} {} -> {} ({} bytes)", owner, repo, release.tag_name, asset.browser_download_url, asset.size);
+    Ok(GithubReleaseAsset {
+        url: asset.browser_download_url.clone(),
+        name: asset.name.clone(),
+        size: asset.size,
+        tag: release.tag_name.clone(),
+        prerelease: release.prerelease,
+        published_at: release.published_at.clone(),
+    })
+}
+
+#[tauri::command]
+pub async fn resolve_github_release(app: tauri::AppHandle, owner: String, repo: String, pattern: String, version: Option<String>) -> Result<GithubReleaseAsset, String> {
+    resolve_release_asset(&app, &owner, &repo, &pattern, version.as_deref()).await
+}
+
+/// 設定値を検証して API の設定にする（None で既定値。トークンを平文で送らないよう、トークンを指定する場合は https のみ受け付ける）
+fn parse_api_config(base_url: Option<&str>, token: Option<&str>) -> Result<GithubApiConfig, String> {
+    let token = token.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    let base_url = match base_url.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(raw) => {
+            let parsed = url::Url::parse(raw).map_err(|e| format!("invalid GitHub API base URL: {}", e))?;
+            if parsed.scheme() != "https" && parsed.scheme() != "http" {
+                return Err(format!("unsupported GitHub API base URL scheme: {}", parsed.scheme()));
+            }
+            if token.is_some() && parsed.scheme() != "https" {
+                return Err("a GitHub API token can only be sent to an https base URL".to_string());
+            }
+            raw.trim_end_matches('/').to_string()
+        }
+        None => DEFAULT_API_BASE.to_string(),
+    };
+    Ok(GithubApiConfig { base_url, token })
+}
+
+/// API のベース URL とトークンを設定する（None で既定値に戻す。トークンは保存しない）
+#[tauri::command]
+pub fn set_github_api_config(base_url: Option<String>, token: Option<String>) -> Result<(), String> {
+    let config = parse_api_config(base_url.as_deref(), token.as_deref())?;
+    *API_CONFIG.write().unwrap_or_else(|e| e.into_inner()) = config;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::super::download;
+    use super::*;
+
+    const ASSET_BODY: &[u8] = b"asset";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aviutl2-github-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // リリース一覧とアセットだけを返す HTTP サーバー（1 リクエストごとに接続を閉じる）
+    async fn serve(listener: tokio::net::TcpListener, base_url: String, not_modified: Arc<AtomicUsize>) {
+        let releases = serde_json::json!([
+            { "tag_name": "v3.0.0", "draft": true, "published_at": "2024-04-01T00:00:00Z", "assets": [] },
+            {
+                "tag_name": "v2.0.0-beta",
+                "prerelease": true,
+                "published_at": "2024-03-01T00:00:00Z",
+                "assets": [{ "name": "tool-2.0.0-beta.zip", "browser_download_url": format!("{base_url}/assets/tool.zip"), "size": ASSET_BODY.len() }],
+            },
+        ])
+        .to_string();
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let request = String::from_utf8_lossy(&request).to_ascii_lowercase();
+            let (status, headers, body): (&str, &str, &[u8]) = if request.starts_with("get /repos/owner/tool/releases?per_page=30 ") {
+                if request.contains("if-none-match: \"r1\"") {
+                    not_modified.fetch_add(1, Ordering::SeqCst);
+                    ("304 Not Modified", "ETag: \"r1\"\r\n", b"")
+                } else {
+                    ("200 OK", "ETag: \"r1\"\r\nContent-Type: application/json\r\n", releases.as_bytes())
+                }
+            } else if request.starts_with("get /assets/tool.zip ") {
+                ("200 OK", "Content-Type: application/octet-stream\r\n", ASSET_BODY)
+            } else {
+                ("404 Not Found", "", b"")
+            };
+            let head = format!("HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n", body.len());
+            let _ = stream.write_all(head.as_bytes()).await;
+            let _ = stream.write_all(body).await;
+            let _ = stream.shutdown().await;
+        }
+    }
+
+    fn release(tag: &str, prerelease: bool, published_at: &str) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            name: None,
+            draft: false,
+            prerelease,
+            published_at: Some(published_at.to_string()),
+            created_at: None,
+            assets: Vec::new(),
+        }
+    }
+
+    fn cached() -> Option<CachedResponse> {
+        Some(CachedResponse { etag: "\"abc\"".to_string(), body: serde_json::json!({ "cached": true }) })
+    }
+
+    fn rate_limited_headers() -> reqwest::header::HeaderMap {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset", "1700000000".parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn tag_candidates_try_both_prefixed_and_bare_tags() {
+        assert_eq!(tag_candidates("1.2.0"), vec!["1.2.0", "v1.2.0"]);
+        assert_eq!(tag_candidates("v1.2.0"), vec!["v1.2.0", "1.2.0"]);
+        assert_eq!(tag_candidates("V2"), vec!["V2", "2", "v2"]);
+    }
+
+    #[test]
+    fn asset_pattern_uses_glob_only_with_prefix() {
+        let regex = AssetPattern::parse(r"^plugin-.*\.zip$").unwrap();
+        assert!(regex.matches("plugin-1.0.zip"));
+        assert!(!regex.matches("plugin-1.0.7z"));
+
+        let glob = AssetPattern::parse("glob:Plugin-*.ZIP").unwrap();
+        assert!(glob.matches("plugin-1.0.zip"));
+        assert!(!glob.matches("other-1.0.zip"));
+
+        // 正規表現として不正なパターンはグロブに読み替えずエラーにする
+        assert!(AssetPattern::parse("*.zip").is_err());
+        assert!(AssetPattern::parse("glob:[").is_err());
+    }
+
+    #[test]
+    fn newest_release_prefers_stable_over_newer_prerelease() {
+        let releases = vec![
+            release("v1.0.0", false, "2024-01-01T00:00:00Z"),
+            release("v1.1.0", false, "2024-02-01T00:00:00Z"),
+            release("v2.0.0-beta", true, "2024-03-01T00:00:00Z"),
+        ];
+        assert_eq!(newest_release(&releases).unwrap().tag_name, "v1.1.0");
+
+        let prereleases = vec![
+            release("v0.1.0", true, "2024-01-01T00:00:00Z"),
+            release("v0.2.0", true, "2024-02-01T00:00:00Z"),
+        ];
+        assert_eq!(newest_release(&prereleases).unwrap().tag_name, "v0.2.0");
+        assert!(newest_release(&[]).is_none());
+    }
+
+    #[test]
+    fn not_modified_and_rate_limited_responses_fall_back_to_cache() {
+        let empty = reqwest::header::HeaderMap::new();
+        let url = "https://api.github.com/repos/o/r/releases";
+
+        let body = cached_fallback(reqwest::StatusCode::NOT_MODIFIED, &empty, cached(), url).unwrap().unwrap();
+        assert_eq!(body["cached"], true);
+        let body = cached_fallback(reqwest::StatusCode::FORBIDDEN, &rate_limited_headers(), cached(), url).unwrap().unwrap();
+        assert_eq!(body["cached"], true);
+
+        assert!(cached_fallback(reqwest::StatusCode::TOO_MANY_REQUESTS, &rate_limited_headers(), None, url).unwrap().is_err());
+        // レート制限ではない 403 や通常の応答はそのまま処理する
+        assert!(cached_fallback(reqwest::StatusCode::FORBIDDEN, &empty, cached(), url).is_none());
+        assert!(cached_fallback(reqwest::StatusCode::OK, &empty, cached(), url).is_none());
+    }
+
+    #[test]
+    fn token_requires_https_base_url() {
+        assert!(parse_api_config(Some("http://ghe.example.com/api/v3"), Some("secret")).is_err());
+        assert!(parse_api_config(Some("ftp://ghe.example.com"), None).is_err());
+
+        let config = parse_api_config(Some("https://ghe.example.com/api/v3/"), Some(" secret ")).unwrap();
+        assert_eq!(config.base_url, "https://ghe.example.com/api/v3");
+        assert_eq!(config.token.as_deref(), Some("secret"));
+
+        // トークンが無ければ http も使える（社内のミラーなど）
+        let config = parse_api_config(Some("http://127.0.0.1:8080"), Some("  ")).unwrap();
+        assert_eq!(config.base_url, "http://127.0.0.1:8080");
+        assert!(config.token.is_none());
+        assert_eq!(parse_api_config(None, None).unwrap().base_url, DEFAULT_API_BASE);
+    }
+
+    #[tokio::test]
+    async fn local_server_serves_prerelease_with_etag_reuse_and_asset_download() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let not_modified = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(serve(listener, base_url.clone(), not_modified.clone()));
+        let dir = temp_dir("server");
+        let api = GithubApi {
+            config: parse_api_config(Some(&base_url), None).unwrap(),
+            cache_file: dir.join("github-cache.json"),
+        };
+
+        // 正式版は下書きしか無いので、プレリリースを使う
+        let asset = resolve_asset_with(&api, "owner", "tool", r"\.zip$", None).await.unwrap();
+        assert_eq!(asset.tag, "v2.0.0-beta");
+        assert!(asset.prerelease);
+        assert_eq!(asset.url, format!("{base_url}/assets/tool.zip"));
+        assert_eq!(not_modified.load(Ordering::SeqCst), 0);
+        assert!(api.cache_file.exists());
+
+        // 2 回目は ETag を送り、304 が返ればキャッシュ済みの一覧を使う
+        let again = resolve_asset_with(&api, "owner", "tool", r"\.zip$", None).await.unwrap();
+        assert_eq!(again.url, asset.url);
+        assert_eq!(not_modified.load(Ordering::SeqCst), 1);
+
+        let path = download::fetch_to_dir(&asset.url, &dir.join("download"), None, &mut |_, _| {}).await.unwrap();
+        download::verify_downloaded_size(&path, Some(asset.size)).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), ASSET_BODY);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
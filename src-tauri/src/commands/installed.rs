@@ -1,20 +1,491 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use super::pathutil::{is_under_key, path_key};
+
+// UpdateChecker が読む互換ファイル（id→version のフラットな JSON）
+const INSTALLED_FILE: &str = "installed.json";
+// インストール状態の本体（バージョン付きスキーマ）
+const STATE_FILE: &str = "installed-state.json";
+
+static INSTALLED_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "version", content = "data")]
+enum InstalledRoot {
+    #[serde(rename = "2")]
+    V2(InstalledState),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct InstalledState {
+    pub packages: BTreeMap<String, InstalledPackage>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct InstalledPackage {
+    pub version: String,
+    pub installed_at: Option<String>,
+    pub source_type: Option<String>,
+    pub source_url: Option<String>,
+    pub files: Vec<InstalledFile>,
+    pub detected: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledFile {
+    pub path: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct InstallDetails {
+    pub source_type: Option<String>,
+    pub source_url: Option<String>,
+    pub detected: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UninstallReport {
+    pub package_id: String,
+    pub removed: Vec<String>,
+    pub skipped_modified: Vec<String>,
+    pub missing: Vec<String>,
+    pub pruned_dirs: Vec<String>,
+    pub errors: Vec<String>,
+    // 削除せずに記録へ残したファイル（編集済みまたは削除に失敗したもの）
+    pub kept: Vec<String>,
+}
+
+fn config_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path().app_config_dir().map_err(|e| e.to_string())
+}
+
+fn atomic_write(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("create dir error: {}", e))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, bytes).map_err(|e| format!("write {} error: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("rename {} error: {}", path.display(), e))
+}
+
+// 壊れたファイルは上書きされる前に退避しておく
+fn backup_corrupt(path: &Path) {
+    let backup = path.with_extension(format!("json.corrupt-{}", chrono::Local::now().format("%Y%m%d%H%M%S")));
+    match fs::copy(path, &backup) {
+        Ok(_) => tracing::warn!("Backed up corrupt file {} to {}", path.display(), backup.display()),
+        Err(e) => tracing::error!("Failed to back up corrupt file {}: {}", path.display(), e),
+    }
+}
+
+fn read_flat_map(dir: &Path) -> HashMap<String, String> {
+    let path = dir.join(INSTALLED_FILE);
+    let Ok(text) = fs::read_to_string(&path) else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, serde_json::Value>>(&text) {
+        Ok(map) => map.into_iter().map(|(id, v)| (id, v.as_str().unwrap_or_default().to_string())).collect(),
+        Err(e) => {
+            tracing::error!("Failed to parse {}: {}", path.display(), e);
+            backup_corrupt(&path);
+            HashMap::new()
+        }
+    }
+}
+
+// フラットな installed.json から状態を組み立てる（ファイルの記録は持たない）
+fn migrate_state(dir: &Path) -> InstalledState {
+    let mut state = InstalledState::default();
+    for (id, version) in read_flat_map(dir) {
+        state.packages.insert(id, InstalledPackage { version, ..Default::default() });
+    }
+    state
+}
+
+// 読めない installed-state.json はエラーにして記録を上書きしない
+// 壊れている場合は退避してから installed.json で作り直す（以降の更新が失敗し続けないように）
+fn load_state(dir: &Path) -> Result<InstalledState, String> {
+    let path = dir.join(STATE_FILE);
+    match fs::read_to_string(&path) {
+        Ok(text) => match serde_json::from_str::<InstalledRoot>(&text) {
+            Ok(InstalledRoot::V2(state)) => Ok(state),
+            Err(e) => {
+                tracing::error!("Failed to parse {}: {}", path.display(), e);
+                backup_corrupt(&path);
+                let state = migrate_state(dir);
+                save_state(dir, &state)?;
+                tracing::info!("Rebuilt {} from installed.json ({} packages)", STATE_FILE, state.packages.len());
+                Ok(state)
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let state = migrate_state(dir);
+            save_state(dir, &state)?;
+            tracing::info!("Migrated installed.json to {} ({} packages)", STATE_FILE, state.packages.len());
+            Ok(state)
+        }
+        Err(e) => Err(format!("read {} error: {}", path.display(), e)),
+    }
+}
+
+fn save_state(dir: &Path, state: &InstalledState) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(&InstalledRoot::V2(state.clone())).map_err(|e| format!("serialize installed state error: {}", e))?;
+    atomic_write(&dir.join(STATE_FILE), &json)?;
+    atomic_write(&dir.join(INSTALLED_FILE), &serde_json::to_vec_pretty(&flat_map(state)).map_err(|e| format!("serialize installed map error: {}", e))?)
+}
+
+fn flat_map(state: &InstalledState) -> HashMap<String, String> {
+    state.packages.iter().map(|(id, p)| (id.clone(), p.version.clone())).collect()
+}
+
+fn update_state<T>(dir: &Path, f: impl FnOnce(&mut InstalledState) -> T) -> Result<T, String> {
+    let _guard = INSTALLED_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut state = load_state(dir)?;
+    let out = f(&mut state);
+    save_state(dir, &state)?;
+    Ok(out)
+}
+
+pub fn load_installed_state(dir: &Path) -> InstalledState {
+    let _guard = INSTALLED_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    load_state(dir).unwrap_or_else(|e| {
+        // 読み取りだけなので、installed.json のバージョン情報で代用する
+        tracing::error!("Failed to load installed state: {}", e);
+        migrate_state(dir)
+    })
+}
+
+/// スナップショットから取り出した installed-state.json の内容で状態を置き換える
+pub fn restore_installed_state(dir: &Path, state_json: &str) -> Result<usize, String> {
+    let InstalledRoot::V2(state) = serde_json::from_str::<InstalledRoot>(state_json).map_err(|e| format!("parse installed state error: {}", e))?;
+    let _guard = INSTALLED_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    save_state(dir, &state)?;
+    Ok(state.packages.len())
+}
+
+pub fn read_installed_state(app: &tauri::AppHandle) -> InstalledState {
+    let Ok(dir) = config_dir(app) else {
+        return InstalledState::default();
+    };
+    load_installed_state(&dir)
+}
+
+pub fn read_installed_map(app: &tauri::AppHandle) -> HashMap<String, String> {
+    flat_map(&read_installed_state(app))
+}
+
+#[tauri::command]
+pub fn get_installed_map_cmd(app: tauri::AppHandle) -> Result<HashMap<String, String>, String> {
+    Ok(read_installed_map(&app))
+}
+
+#[tauri::command]
+pub fn get_installed_state(app: tauri::AppHandle) -> Result<InstalledState, String> {
+    Ok(read_installed_state(&app))
+}
+
 #[tauri::command]
-pub fn get_installed_map_cmd(app: tauri::AppHandle) -> Result<std::collections::HashMap<String, String>, String> {
-    Ok(crate::read_installed_map(&app))
+pub fn add_installed_id_cmd(app: tauri::AppHandle, id: String, version: Option<String>, details: Option<InstallDetails>) -> Result<HashMap<String, String>, String> {
+    let dir = config_dir(&app)?;
+    update_state(&dir, |state| {
+        let package = state.packages.entry(id).or_default();
+        package.version = version.unwrap_or_default();
+        package.installed_at = Some(chrono::Local::now().to_rfc3339());
+        if let Some(details) = details {
+            package.source_type = details.source_type;
+            package.source_url = details.source_url;
+            package.detected = details.detected;
+        }
+        flat_map(state)
+    })
 }
 
+/// インストール後にファイルのハッシュをカタログと照合し、実際に検出したバージョンでインストール済みマップを更新する
+/// 検出結果も返すので、呼び出し側で改めてハッシュを計算し直す必要はない
 #[tauri::command]
-pub fn add_installed_id_cmd(app: tauri::AppHandle, id: String, version: Option<String>) -> Result<std::collections::HashMap<String, String>, String> {
-    let mut map = crate::read_installed_map(&app);
-    map.insert(id, version.unwrap_or_default());
-    crate::write_installed_map(&app, &map)?;
-    Ok(map)
+pub fn verify_installed_package(app: tauri::AppHandle, package_id: String, version: Option<String>) -> Result<super::version::InstallVerificationReport, String> {
+    use super::version::{DetectResult, InstallVerification, InstallVerificationReport};
+
+    let (verification, detected) = super::version::verify_package_files(&app, &package_id, version.as_deref());
+    match &verification {
+        InstallVerification::Verified { version } => tracing::info!("Verified {} {}", package_id, version),
+        InstallVerification::Mismatch { expected_version, detected_version, files } => {
+            tracing::warn!("Verification of {} {} failed: {} files differ (detected={:?})", package_id, expected_version, files.len(), detected_version)
+        }
+        InstallVerification::Unverifiable { reason } => tracing::info!("Skipped verification of {}: {}", package_id, reason),
+    }
+    if let Some(detected) = &detected {
+        let dir = config_dir(&app)?;
+        update_state(&dir, |state| {
+            let Some(package) = state.packages.get_mut(&package_id) else {
+                return;
+            };
+            if let DetectResult::Detected { version } = detected {
+                package.version = version.clone();
+            }
+            package.detected = serde_json::to_value(detected).ok();
+        })?;
+    }
+    Ok(InstallVerificationReport { verification, detected })
 }
 
 #[tauri::command]
-pub fn remove_installed_id_cmd(app: tauri::AppHandle, id: String) -> Result<std::collections::HashMap<String, String>, String> {
-    let mut map = crate::read_installed_map(&app);
-    map.remove(&id);
-    crate::write_installed_map(&app, &map)?;
-    Ok(map)
+pub fn remove_installed_id_cmd(app: tauri::AppHandle, id: String) -> Result<HashMap<String, String>, String> {
+    let dir = config_dir(&app)?;
+    update_state(&dir, |state| {
+        state.packages.remove(&id);
+        flat_map(state)
+    })
+}
+
+// 検出結果から作ったスナップショットで置き換える（既存のメタデータは引き継ぐ）
+#[tauri::command]
+pub fn sync_installed_snapshot_cmd(app: tauri::AppHandle, snapshot: HashMap<String, String>) -> Result<HashMap<String, String>, String> {
+    let dir = config_dir(&app)?;
+    update_state(&dir, |state| {
+        state.packages.retain(|id, _| snapshot.contains_key(id));
+        for (id, version) in snapshot {
+            state.packages.entry(id).or_default().version = version;
+        }
+        flat_map(state)
+    })
+}
+
+/// インストール完了時に書き込んだファイルを記録する（更新時は既存の記録にマージする）
+pub fn record_package_files(dir: &Path, package_id: &str, written: &[String], removed: &[String]) -> Result<usize, String> {
+    let dropped: HashSet<String> = written.iter().chain(removed).map(path_key).collect();
+    let mut hashed = Vec::with_capacity(written.len());
+    for path in written {
+        match super::version::xxh3_128_hex(path) {
+            Ok(hash) => hashed.push(InstalledFile { path: path.clone(), hash }),
+            Err(e) => tracing::warn!("Failed to hash installed file {}: {}", path, e),
+        }
+    }
+    update_state(dir, |state| {
+        let package = state.packages.entry(package_id.to_string()).or_default();
+        package.files.retain(|f| !dropped.contains(&path_key(&f.path)) && Path::new(&f.path).is_file());
+        package.files.extend(hashed);
+        package.files.sort_by(|a, b| a.path.cmp(&b.path));
+        package.files.len()
+    })
+}
+
+// 空になったディレクトリを親へ辿りながら削除する（AviUtl2 の基本フォルダ自体は残す）
+fn prune_empty_dirs(start: &Path, roots: &[String], protected: &HashSet<String>, report: &mut UninstallReport) {
+    for dir in start.ancestors() {
+        let key = path_key(dir);
+        if protected.contains(&key) || !roots.iter().any(|root| is_under_key(&key, root)) {
+            break;
+        }
+        let is_empty = fs::read_dir(dir).map(|mut it| it.next().is_none()).unwrap_or(false);
+        if !is_empty || fs::remove_dir(dir).is_err() {
+            break;
+        }
+        report.pruned_dirs.push(dir.to_string_lossy().into_owned());
+    }
+}
+
+// 記録したファイルを削除し、残したファイルだけを記録に戻す
+fn remove_recorded_files(dir: &Path, package_id: &str, force: bool, roots: &[String], protected: &HashSet<String>) -> Result<UninstallReport, String> {
+    let mut report = UninstallReport { package_id: package_id.to_string(), ..Default::default() };
+    update_state(dir, |state| {
+        let Some(package) = state.packages.get_mut(package_id) else {
+            return;
+        };
+        let mut kept = Vec::new();
+        let mut parents = Vec::new();
+        for file in std::mem::take(&mut package.files) {
+            let path = Path::new(&file.path);
+            if !path.is_file() {
+                report.missing.push(file.path);
+                continue;
+            }
+            // インストール後にユーザーが編集したファイルは既定では残す
+            if !force && super::version::xxh3_128_hex(path).map(|hash| hash != file.hash).unwrap_or(true) {
+                report.skipped_modified.push(file.path.clone());
+                kept.push(file);
+                continue;
+            }
+            match fs::remove_file(path) {
+                Ok(()) => {
+                    if let Some(parent) = path.parent() {
+                        parents.push(parent.to_path_buf());
+                    }
+                    report.removed.push(file.path);
+                }
+                Err(e) => {
+                    report.errors.push(format!("{}: {}", file.path, e));
+                    kept.push(file);
+                }
+            }
+        }
+        report.kept = kept.iter().map(|f| f.path.clone()).collect();
+        package.files = kept;
+        // 深いディレクトリから順に整理する
+        parents.sort_by_key(|p| std::cmp::Reverse(p.components().count()));
+        parents.dedup();
+        for parent in parents {
+            prune_empty_dirs(&parent, roots, protected, &mut report);
+        }
+    })?;
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn get_installed_files(app: tauri::AppHandle, package_id: String) -> Result<Vec<InstalledFile>, String> {
+    Ok(read_installed_state(&app).packages.remove(&package_id).map(|p| p.files).unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn uninstall_package(app: tauri::AppHandle, package_id: String, force: Option<bool>) -> Result<UninstallReport, String> {
+    let package_id = package_id.trim().to_string();
+    if package_id.is_empty() {
+        return Err(crate::paths::common_message_current("backend.errors.packageIdEmpty"));
+    }
+    let force = force.unwrap_or(false);
+    let dir = config_dir(&app)?;
+    let dirs = crate::paths::dirs();
+    let roots: Vec<String> = [&dirs.aviutl2_root, &dirs.aviutl2_data].into_iter().map(path_key).collect();
+    let protected: HashSet<String> = [&dirs.aviutl2_root, &dirs.aviutl2_data, &dirs.plugin_dir, &dirs.script_dir].into_iter().map(path_key).collect();
+    tauri::async_runtime::spawn_blocking(move || -> Result<UninstallReport, String> {
+        let report = remove_recorded_files(&dir, &package_id, force, &roots, &protected)?;
+        tracing::info!(
+            "Uninstalled package files: {} (removed={}, skipped={}, missing={}, errors={})",
+            package_id,
+            report.removed.len(),
+            report.skipped_modified.len(),
+            report.missing.len(),
+            report.errors.len()
+        );
+        Ok(report)
+    })
+    .await
+    .map_err(|e| format!("task join error: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aviutl2-installed-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read_flat(dir: &Path) -> HashMap<String, String> {
+        serde_json::from_str(&fs::read_to_string(dir.join(INSTALLED_FILE)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn migrates_flat_installed_json_to_versioned_state() {
+        let dir = temp_dir("migrate");
+        fs::write(dir.join(INSTALLED_FILE), r#"{"pkg.a":"1.0.0","pkg.b":"2.0"}"#).unwrap();
+        let state = load_installed_state(&dir);
+        assert_eq!(state.packages.len(), 2);
+        assert_eq!(state.packages["pkg.a"].version, "1.0.0");
+        let text = fs::read_to_string(dir.join(STATE_FILE)).unwrap();
+        let InstalledRoot::V2(saved) = serde_json::from_str::<InstalledRoot>(&text).unwrap();
+        assert_eq!(saved.packages["pkg.b"].version, "2.0");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn recovers_from_a_corrupt_state_file() {
+        let dir = temp_dir("corrupt");
+        fs::write(dir.join(INSTALLED_FILE), r#"{"pkg.a":"1.0.0"}"#).unwrap();
+        fs::write(dir.join(STATE_FILE), "{ not json").unwrap();
+        update_state(&dir, |state| state.packages.entry("pkg.b".to_string()).or_default().version = "3.0".to_string()).unwrap();
+        let state = load_installed_state(&dir);
+        assert_eq!(state.packages.keys().collect::<Vec<_>>(), ["pkg.a", "pkg.b"]);
+        // 壊れたファイルは退避されている
+        let backups = fs::read_dir(&dir).unwrap().filter_map(|e| e.ok()).filter(|e| e.file_name().to_string_lossy().contains(".corrupt-")).count();
+        assert_eq!(backups, 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keeps_installed_json_in_sync_for_the_update_checker() {
+        let dir = temp_dir("sync");
+        update_state(&dir, |state| {
+            state.packages.insert("pkg.a".to_string(), InstalledPackage { version: "1.0.0".to_string(), ..Default::default() });
+            state.packages.insert("pkg.b".to_string(), InstalledPackage { version: "2.0".to_string(), ..Default::default() });
+        })
+        .unwrap();
+        update_state(&dir, |state| state.packages.remove("pkg.b")).unwrap();
+        assert_eq!(read_flat(&dir), HashMap::from([("pkg.a".to_string(), "1.0.0".to_string())]));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn uninstall_keeps_modified_files_unless_forced_and_prunes_up_to_protected_roots() {
+        let base = temp_dir("uninstall");
+        let config = base.join("config");
+        let root = base.join("aviutl2");
+        let plugin = root.join("Plugin");
+        fs::create_dir_all(plugin.join("pkg/sub")).unwrap();
+        let files = [
+            plugin.join("pkg/a.aux2"),
+            plugin.join("pkg/sub/b.lua"),
+            plugin.join("edited.txt"),
+        ];
+        for file in &files {
+            fs::write(file, "installed").unwrap();
+        }
+        let written: Vec<String> = files.iter().map(|f| f.to_string_lossy().into_owned()).collect();
+        record_package_files(&config, "pkg", &written, &[]).unwrap();
+        fs::write(&files[2], "edited by the user").unwrap();
+
+        let roots = vec![path_key(&root)];
+        let protected: HashSet<String> = [&root, &plugin].into_iter().map(path_key).collect();
+        let report = remove_recorded_files(&config, "pkg", false, &roots, &protected).unwrap();
+        assert_eq!(report.removed.len(), 2);
+        assert_eq!(report.skipped_modified, [written[2].clone()]);
+        assert_eq!(report.kept, [written[2].clone()]);
+        assert!(files[2].is_file());
+        assert!(!plugin.join("pkg").exists());
+        assert_eq!(report.pruned_dirs.len(), 2);
+        assert_eq!(load_installed_state(&config).packages["pkg"].files.len(), 1);
+
+        let report = remove_recorded_files(&config, "pkg", true, &roots, &protected).unwrap();
+        assert_eq!(report.removed, [written[2].clone()]);
+        assert!(report.kept.is_empty());
+        // 基本フォルダは空になっても残す
+        assert!(plugin.is_dir());
+        assert!(report.pruned_dirs.is_empty());
+        assert!(load_installed_state(&config).packages["pkg"].files.is_empty());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn uninstall_reports_missing_files_and_ignores_unknown_packages() {
+        let base = temp_dir("uninstall-missing");
+        let config = base.join("config");
+        let gone = base.join("gone.txt");
+        fs::write(&gone, "installed").unwrap();
+        record_package_files(&config, "pkg", &[gone.to_string_lossy().into_owned()], &[]).unwrap();
+        fs::remove_file(&gone).unwrap();
+
+        let roots = vec![path_key(&base)];
+        let report = remove_recorded_files(&config, "pkg", false, &roots, &HashSet::new()).unwrap();
+        assert_eq!(report.missing.len(), 1);
+        assert!(report.removed.is_empty() && report.kept.is_empty());
+
+        let report = remove_recorded_files(&config, "other", false, &roots, &HashSet::new()).unwrap();
+        assert!(report.removed.is_empty() && report.missing.is_empty());
+        let _ = fs::remove_dir_all(&base);
+    }
 }
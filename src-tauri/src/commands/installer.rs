@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::sync::atomic::AtomicBool;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use super::archive::{ArchiveFormat, CopyOptions, EntrySelection, ExtractOptions, NestedExtract};
+use super::pathutil::is_abs;
+
+static INSTALL_CATALOG: Lazy<RwLock<HashMap<String, CatalogInstallPackage>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum InstallerSource {
+    DirectUrl { url: String },
+    Booth { url: String },
+    GithubRelease { owner: String, repo: String, pattern: String },
+    GoogleDrive { id: String },
+}
+
+impl InstallerSource {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::DirectUrl { .. } => "directUrl",
+            Self::Booth { .. } => "booth",
+            Self::GithubRelease { .. } => "githubRelease",
+            Self::GoogleDrive { .. } => "googleDrive",
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::DirectUrl { url } | Self::Booth { url } => url.clone(),
+            Self::GithubRelease { owner, repo, pattern } => format!("https://github.com/{owner}/{repo} ({pattern})"),
+            Self::GoogleDrive { id } => format!("Google Drive fileId={id}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum InstallStep {
+    Download,
+    Extract {
+        #[serde(default)]
+        from: Option<String>,
+        #[serde(default)]
+        to: Option<String>,
+        #[serde(flatten)]
+        selection: EntrySelection,
+        // 展開結果に含まれる書庫をさらに展開する
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        nested: Option<NestedExtract>,
+        // 暗号化された書庫のパスワード
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+    },
+    ExtractSfx {
+        #[serde(default)]
+        from: Option<String>,
+        #[serde(default)]
+        to: Option<String>,
+        #[serde(flatten)]
+        selection: EntrySelection,
+        // 展開結果に含まれる書庫をさらに展開する
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        nested: Option<NestedExtract>,
+        // 暗号化された書庫のパスワード
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+    },
+    Copy {
+        from: String,
+        to: String,
+        #[serde(flatten)]
+        options: CopyOptions,
+    },
+    Delete {
+        path: String,
+    },
+    Run {
+        path: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        elevate: bool,
+    },
+    RunAuoSetup {
+        path: String,
+    },
+}
+
+impl InstallStep {
+    pub fn action(&self) -> &'static str {
+        match self {
+            Self::Download => "download",
+            Self::Extract { .. } => "extract",
+            Self::ExtractSfx { .. } => "extractSfx",
+            Self::Copy { .. } => "copy",
+            Self::Delete { .. } => "delete",
+            Self::Run { .. } => "run",
+            Self::RunAuoSetup { .. } => "runAuoSetup",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Installation {
+    pub source: InstallerSource,
+    #[serde(default)]
+    pub install_steps: Vec<InstallStep>,
+    #[serde(default)]
+    pub uninstall_steps: Vec<InstallStep>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RelationSet {
+    pub requires: Vec<String>,
+    pub recommends: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub similar: Vec<String>,
+    pub replaces: Vec<String>,
+    pub fork_of: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogInstallPackage {
+    #[serde(default)]
+    pub relations: Option<RelationSet>,
+    pub installation: Installation,
+}
+
+// インストーラーのマクロ展開に使う一時情報（{tmp} と {download}）
+#[derive(Debug, Clone, Default)]
+pub struct MacroContext {
+    pub tmp_dir: PathBuf,
+    pub download_path: Option<PathBuf>,
+}
+
+impl MacroContext {
+    pub fn for_package(package_id: &str, version: Option<&str>) -> Self {
+        let version = version.map(str::trim).filter(|v| !v.is_empty()).unwrap_or("latest");
+        let id_version: String = format!("{package_id}-{version}").chars().map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '_' }).collect();
+        let tmp_dir = crate::paths::dirs().catalog_config_dir.join("installer-tmp").join(id_version);
+        Self { tmp_dir, download_path: None }
+    }
+
+    pub fn expand(&self, raw: &str) -> String {
+        let download = self.download_path.as_ref().map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
+        let replaced = raw.replace("{tmp}", &self.tmp_dir.to_string_lossy()).replace("{download}", &download);
+        super::version::expand_macros(&replaced)
+    }
+}
+
+pub fn ensure_absolute(path: String, label: &str) -> Result<PathBuf, String> {
+    if !is_abs(&path) {
+        return Err(format!("{label} must be an absolute path: {path}"));
+    }
+    Ok(PathBuf::from(path))
+}
+
+pub fn catalog_package(package_id: &str) -> Result<CatalogInstallPackage, String> {
+    let guard = INSTALL_CATALOG.read().map_err(|_| String::from("install catalog lock poisoned"))?;
+    guard.get(package_id).cloned().ok_or_else(|| format!("package not found in install catalog: {package_id}"))
+}
+
+pub fn catalog_packages() -> Result<HashMap<String, CatalogInstallPackage>, String> {
+    let guard = INSTALL_CATALOG.read().map_err(|_| String::from("install catalog lock poisoned"))?;
+    Ok(guard.clone())
+}
+
+#[tauri::command]
+pub fn set_catalog_install(packages: HashMap<String, serde_json::Value>) -> Result<usize, String> {
+    let mut parsed = HashMap::with_capacity(packages.len());
+    for (id, value) in packages {
+        match serde_json::from_value::<CatalogInstallPackage>(value) {
+            Ok(package) => {
+                parsed.insert(id, package);
+            }
+            Err(e) => tracing::warn!("Skipped install catalog entry {}: {}", id, e),
+        }
+    }
+    let mut guard = INSTALL_CATALOG.write().map_err(|_| String::from("install catalog lock poisoned"))?;
+    *guard = parsed;
+    Ok(guard.len())
+}
+
+/// インストーラーの取得元からダウンロードする
+/// version は GitHub のリリースを選ぶときに使う（他の取得元は配布中のものしか取れない）
+pub async fn download_source(
+    app: &tauri::AppHandle,
+    source: &InstallerSource,
+    version: Option<&str>,
+    url_override: Option<&str>,
+    dest_dir: &Path,
+    cancel: &AtomicBool,
+    on_progress: &mut (dyn FnMut(u64, Option<u64>) + Send),
+) -> Result<PathBuf, String> {
+    use super::download;
+
+    if let Some(url) = url_override.map(str::trim).filter(|u| !u.is_empty()) {
+        return download::fetch_url_to_dir(url, dest_dir, Some(cancel), on_progress).await;
+    }
+    match source {
+        InstallerSource::DirectUrl { url } => download::fetch_url_to_dir(url, dest_dir, Some(cancel), on_progress).await,
+        InstallerSource::Booth { url } => {
+            download::ensure_booth_auth_window(app.clone()).await?;
+            download::fetch_booth_to_dir(app, url, dest_dir, "booth-auth", Some(cancel), on_progress).await
+        }
+        InstallerSource::GithubRelease { owner, repo, pattern } => {
+            let asset = super::github::resolve_release_asset(app, owner, repo, pattern, version).await?;
+            let path = download::fetch_url_to_dir(&asset.url, dest_dir, Some(cancel), on_progress).await?;
+            download::verify_downloaded_size(&path, Some(asset.size))?;
+            Ok(path)
+        }
+        InstallerSource::GoogleDrive { id } => download::fetch_drive_to_dir(id, dest_dir, Some(cancel), on_progress).await,
+    }
+}
+
+/// ダウンロード以外のインストール・アンインストール手順を 1 つ実行する
+/// 手順の意味はこの実装を正とする。フロントエンドの runInstallerForItem（単体インストールと登録画面のテスト）は同じ動作に揃える
+pub async fn execute_step(app: &tauri::AppHandle, step: &InstallStep, ctx: &MacroContext, label: &str, task_id: Option<&str>) -> Result<(), String> {
+    match step {
+        InstallStep::Download => Ok(()),
+        InstallStep::Extract { from, to, selection, nested, password } | InstallStep::ExtractSfx { from, to, selection, nested, password } => {
+            let from_raw = match from {
+                Some(from) => from.clone(),
+                None => ctx.download_path.as_ref().map(|p| p.to_string_lossy().into_owned()).ok_or_else(|| format!("{label}.{}: nothing has been downloaded", step.action()))?,
+            };
+            let from = ensure_absolute(ctx.expand(&from_raw), &format!("{label}.{}.from", step.action()))?;
+            let to = ensure_absolute(ctx.expand(to.as_deref().unwrap_or("{tmp}")), &format!("{label}.{}.to", step.action()))?;
+            let (from, to) = (from.to_string_lossy().into_owned(), to.to_string_lossy().into_owned());
+            let options = ExtractOptions {
+                selection: selection.clone(),
+                nested: nested.clone(),
+                password: password.clone(),
+                ..Default::default()
+            };
+            let task_id = task_id.map(str::to_string);
+            // extract は中身から形式を判定する（判定できなければ従来どおり zip として扱う）
+            let format = match step {
+                InstallStep::Extract { .. } => ArchiveFormat::sniff(Path::new(&from))?.unwrap_or(ArchiveFormat::Zip),
+                _ => ArchiveFormat::SevenZipSfx,
+            };
+            // 壊れた書庫を途中まで展開しないよう、書き込む前に全エントリの CRC を確かめる
+            let tested = super::archive::test_archive_as(format, from.clone(), options.clone(), task_id.clone()).await?;
+            tracing::info!("{label}.{}: verified {} entries ({} bytes)", step.action(), tested.entries, tested.bytes);
+            let report = super::archive::extract_archive_as(app.clone(), format, from, to, options, task_id).await?;
+            tracing::info!("{label}.{}: extracted {} files ({:?}, {} nested archives)", step.action(), report.files.len(), format, report.nested.len());
+            Ok(())
+        }
+        InstallStep::Copy { from, to, options } => {
+            let from = ensure_absolute(ctx.expand(from), &format!("{label}.copy.from"))?.to_string_lossy().into_owned();
+            let to = ensure_absolute(ctx.expand(to), &format!("{label}.copy.to"))?.to_string_lossy().into_owned();
+            let (src, dst, options) = (from.clone(), to.clone(), options.clone());
+            let report =
+                tauri::async_runtime::spawn_blocking(move || super::archive::copy_item_js(src, dst, Some(options))).await.map_err(|e| format!("task join error: {e}"))??;
+            if report.matched() == 0 {
+                return Err(format!("copy matched 0 files (from={from} to={to})"));
+            }
+            tracing::info!("{label}.copy: created {}, overwrote {}, skipped {} files (from={from} to={to})", report.created.len(), report.overwritten.len(), report.skipped.len());
+            Ok(())
+        }
+        InstallStep::Delete { path } => {
+            let path = ensure_absolute(ctx.expand(path), &format!("{label}.delete.path"))?.to_string_lossy().into_owned();
+            tauri::async_runtime::spawn_blocking(move || super::archive::delete_item_js(path)).await.map_err(|e| format!("task join error: {e}"))?.map(|_| ())
+        }
+        InstallStep::Run { path, args, elevate } => {
+            let path = ensure_absolute(ctx.expand(path), &format!("{label}.run.path"))?.to_string_lossy().into_owned();
+            super::system::run_installer_executable(path, args.iter().map(|a| ctx.expand(a)).collect(), *elevate).await
+        }
+        InstallStep::RunAuoSetup { path } => {
+            let path = ensure_absolute(ctx.expand(path), &format!("{label}.runAuoSetup.path"))?.to_string_lossy().into_owned();
+            super::system::run_auo_setup(app.clone(), path).await.map(|_| ())
+        }
+    }
+}
@@ -0,0 +1,741 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use tokio::sync::Semaphore;
+
+use super::installer::{self, CatalogInstallPackage, InstallStep, MacroContext};
+use super::ownership::OverlapPolicy;
+use super::pathutil::is_under;
+use super::replace::{self, DeferredFile};
+use super::version::InstallVerification;
+
+const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 3;
+const MAX_DOWNLOAD_CONCURRENCY: usize = 8;
+const DEPENDENCY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// ジョブ一覧（UI を再読み込みしても状態を取り直せるようにバックエンドで保持する）
+static JOBS: Lazy<Mutex<BTreeMap<String, JobEntry>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+static JOB_SEQ: AtomicU64 = AtomicU64::new(0);
+// ダウンロードは並列、ファイルを書き換える手順は 1 件ずつ
+static DOWNLOAD_SLOTS: Lazy<Arc<Semaphore>> = Lazy::new(|| Arc::new(Semaphore::new(DEFAULT_DOWNLOAD_CONCURRENCY)));
+static DOWNLOAD_LIMIT: Mutex<usize> = Mutex::new(DEFAULT_DOWNLOAD_CONCURRENCY);
+pub(crate) static MUTATION_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobKind {
+    Install,
+    Update,
+    Uninstall,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobState {
+    Queued,
+    Downloading,
+    Waiting,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    fn is_finished(self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed | Self::Cancelled)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRequest {
+    pub kind: JobKind,
+    pub package_id: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub download_url: Option<String>,
+    #[serde(default)]
+    pub overlap_policy: Option<OverlapPolicy>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub task_id: String,
+    pub kind: JobKind,
+    pub package_id: String,
+    pub version: Option<String>,
+    pub download_url: Option<String>,
+    pub overlap_policy: Option<OverlapPolicy>,
+    pub state: JobState,
+    pub step_index: Option<usize>,
+    pub total_steps: usize,
+    pub read: u64,
+    pub total: Option<u64>,
+    pub error: Option<String>,
+    // インストール後の検証結果
+    pub verification: Option<InstallVerification>,
+    // 使用中だったため置き換えを後回しにしたファイル
+    pub deferred_files: Vec<DeferredFile>,
+    // 先に終わっている必要がある同じバッチの依存パッケージのジョブ
+    pub depends_on: Vec<String>,
+    pub attempts: u32,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+struct JobEntry {
+    job: Job,
+    cancel: Arc<AtomicBool>,
+}
+
+fn lock_jobs() -> std::sync::MutexGuard<'static, BTreeMap<String, JobEntry>> {
+    JOBS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// 実行中のジョブ 1 回分（再試行すると別のハンドルになる）
+struct JobHandle {
+    app: tauri::AppHandle,
+    task_id: String,
+    cancel: Arc<AtomicBool>,
+}
+
+impl JobHandle {
+    fn update(&self, f: impl FnOnce(&mut Job)) {
+        let snapshot = {
+            let mut jobs = lock_jobs();
+            let Some(entry) = jobs.get_mut(&self.task_id) else {
+                return;
+            };
+            // キャンセル済み・再試行済みのジョブは古い実行から状態を上書きしない
+            if !Arc::ptr_eq(&entry.cancel, &self.cancel) || entry.job.state == JobState::Cancelled {
+                return;
+            }
+            f(&mut entry.job);
+            entry.job.updated_at = chrono::Local::now().to_rfc3339();
+            entry.job.clone()
+        };
+        let _ = self.app.emit("job:update", snapshot);
+    }
+
+    fn set_state(&self, state: JobState) {
+        self.update(|job| job.state = state);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    fn check_cancel(&self) -> Result<(), String> {
+        if self.is_cancelled() { Err(super::download::CANCELLED.to_string()) } else { Ok(()) }
+    }
+}
+
+/// 一時フォルダの外に copy 以外の手順（展開・削除・実行）で書き込むか
+/// これらの手順は使用中のファイルを置き換えられないので、AviUtl2 を終了してから実行する
+fn writes_outside_copy(steps: &[InstallStep], ctx: &MacroContext) -> bool {
+    steps.iter().any(|step| match step {
+        InstallStep::Download | InstallStep::Copy { .. } => false,
+        InstallStep::Extract { to, .. } | InstallStep::ExtractSfx { to, .. } => !is_under(Path::new(&ctx.expand(to.as_deref().unwrap_or("{tmp}"))), &ctx.tmp_dir),
+        InstallStep::Delete { path } => !is_under(Path::new(&ctx.expand(path)), &ctx.tmp_dir),
+        InstallStep::Run { .. } | InstallStep::RunAuoSetup { .. } => true,
+    })
+}
+
+pub(crate) fn ensure_aviutl_closed() -> Result<(), String> {
+    if super::system::is_aviutl_running() {
+        return Err(crate::paths::common_message_current("backend.errors.aviutlRunning"));
+    }
+    Ok(())
+}
+
+async fn run_steps(handle: &JobHandle, steps: &[InstallStep], ctx: &MacroContext, label: &str) -> Result<(), String> {
+    for (index, step) in steps.iter().enumerate() {
+        handle.check_cancel()?;
+        handle.update(|job| job.step_index = Some(index));
+        let mut waited_for: Option<String> = None;
+        loop {
+            let result = installer::execute_step(&handle.app, step, ctx, label, Some(&handle.task_id)).await;
+            // onLocked: waitForExit の手順は何も書き込まずに FILE_LOCKED で失敗するので、AviUtl2 の終了を待ってから 1 度だけやり直す
+            let locked = result.as_ref().err().and_then(|e| replace::locked_path(e)).map(str::to_string);
+            match locked {
+                Some(path) if waited_for.is_none() && super::system::is_aviutl_running() => {
+                    tracing::info!("{label} step {} is waiting for AviUtl2 to exit: {} is in use", index + 1, path);
+                    handle.set_state(JobState::Waiting);
+                    replace::wait_for_aviutl_exit(&handle.cancel).await?;
+                    handle.set_state(JobState::Running);
+                    waited_for = Some(path);
+                }
+                _ => {
+                    result.map_err(|e| format!("step {}/{} action={} failed: {}", index + 1, steps.len(), step.action(), e))?;
+                    // 終了を待ってから置き換えたファイルは、やり直しが成功した場合だけ報告する
+                    if let Some(path) = waited_for {
+                        handle.update(|job| job.deferred_files.push(DeferredFile { path, renamed_to: None }));
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// 依存パッケージのジョブが成功するまで待つ（失敗・キャンセルされたらこのジョブも失敗にする）
+async fn wait_for_dependencies(handle: &JobHandle) -> Result<(), String> {
+    loop {
+        handle.check_cancel()?;
+        let pending = {
+            let jobs = lock_jobs();
+            let Some(entry) = jobs.get(&handle.task_id) else {
+                return Ok(());
+            };
+            let mut pending = false;
+            for dependency in entry.job.depends_on.iter().filter_map(|id| jobs.get(id)) {
+                match dependency.job.state {
+                    JobState::Succeeded => {}
+                    state if state.is_finished() => return Err(format!("required package was not installed: {}", dependency.job.package_id)),
+                    _ => pending = true,
+                }
+            }
+            pending
+        };
+        if !pending {
+            return Ok(());
+        }
+        tokio::time::sleep(DEPENDENCY_POLL_INTERVAL).await;
+    }
+}
+
+async fn execute_install(handle: &JobHandle, request: &JobRequest) -> Result<(), String> {
+    let app = &handle.app;
+    let package = installer::catalog_package(&request.package_id)?;
+    let installation = package.installation;
+    let mut ctx = MacroContext::for_package(&request.package_id, request.version.as_deref());
+    std::fs::create_dir_all(&ctx.tmp_dir).map_err(|e| format!("failed to create tmp dir: {}", e))?;
+
+    if installation.install_steps.iter().any(|s| matches!(s, InstallStep::Download)) {
+        let _permit = DOWNLOAD_SLOTS.clone().acquire_owned().await.map_err(|e| format!("download queue closed: {}", e))?;
+        handle.check_cancel()?;
+        handle.set_state(JobState::Downloading);
+        let mut on_progress = |read: u64, total: Option<u64>| {
+            handle.update(|job| {
+                job.read = read;
+                job.total = total;
+            })
+        };
+        let path =
+            installer::download_source(app, &installation.source, request.version.as_deref(), request.download_url.as_deref(), &ctx.tmp_dir, &handle.cancel, &mut on_progress)
+                .await?;
+        ctx.download_path = Some(path);
+    }
+
+    handle.check_cancel()?;
+    handle.set_state(JobState::Waiting);
+    wait_for_dependencies(handle).await?;
+    let _guard = MUTATION_LOCK.lock().await;
+    handle.check_cancel()?;
+    if writes_outside_copy(&installation.install_steps, &ctx) {
+        ensure_aviutl_closed()?;
+    } else if super::system::is_aviutl_running() {
+        // 起動中の AviUtl2 が使っているファイルは copy 手順の onLocked に従って置き換える
+        tracing::info!("AviUtl2 is running; files in use will be replaced according to each copy step");
+    }
+    handle.set_state(JobState::Running);
+
+    super::transaction::begin_install_transaction(app.clone(), request.package_id.clone(), request.overlap_policy)?;
+    if let Err(e) = run_steps(handle, &installation.install_steps, &ctx, "install").await {
+        match super::transaction::rollback_install_transaction() {
+            Ok(report) if !report.errors.is_empty() => tracing::error!("Rollback for {} finished with {} errors", request.package_id, report.errors.len()),
+            Ok(_) => {}
+            Err(rollback_error) => tracing::error!("Rollback for {} failed: {}", request.package_id, rollback_error),
+        }
+        return Err(e);
+    }
+    let summary = super::transaction::commit_install_transaction(app.clone())?;
+    handle.update(|job| job.deferred_files.extend(summary.deferred_files));
+    let details = super::installed::InstallDetails {
+        source_type: Some(installation.source.kind().to_string()),
+        source_url: Some(request.download_url.clone().unwrap_or_else(|| installation.source.label())),
+        detected: None,
+    };
+    super::installed::add_installed_id_cmd(app.clone(), request.package_id.clone(), request.version.clone(), Some(details))?;
+    let (verify_app, package_id, version) = (app.clone(), request.package_id.clone(), request.version.clone());
+    let report = tauri::async_runtime::spawn_blocking(move || super::installed::verify_installed_package(verify_app, package_id, version))
+        .await
+        .map_err(|e| format!("task join error: {e}"))??;
+    handle.update(|job| job.verification = Some(report.verification));
+    if let Err(e) = std::fs::remove_dir_all(&ctx.tmp_dir) {
+        tracing::warn!("Failed to clean up {}: {}", ctx.tmp_dir.display(), e);
+    }
+    Ok(())
+}
+
+async fn execute_uninstall(handle: &JobHandle, request: &JobRequest) -> Result<(), String> {
+    let app = &handle.app;
+    let package = installer::catalog_package(&request.package_id)?;
+    let ctx = MacroContext::for_package(&request.package_id, request.version.as_deref());
+
+    handle.set_state(JobState::Waiting);
+    let _guard = MUTATION_LOCK.lock().await;
+    handle.check_cancel()?;
+    ensure_aviutl_closed()?;
+    handle.set_state(JobState::Running);
+
+    run_steps(handle, &package.installation.uninstall_steps, &ctx, "uninstall").await?;
+    let report = super::installed::uninstall_package(app.clone(), request.package_id.clone(), Some(false)).await?;
+    if !report.skipped_modified.is_empty() {
+        tracing::info!("Kept {} modified files of {}", report.skipped_modified.len(), request.package_id);
+    }
+    super::installed::remove_installed_id_cmd(app.clone(), request.package_id.clone())?;
+    Ok(())
+}
+
+async fn run_job(handle: JobHandle, request: JobRequest) {
+    tracing::info!("Job started: {} {:?} {}", handle.task_id, request.kind, request.package_id);
+    let result = match request.kind {
+        JobKind::Install | JobKind::Update => execute_install(&handle, &request).await,
+        JobKind::Uninstall => execute_uninstall(&handle, &request).await,
+    };
+    match result {
+        Ok(()) => {
+            tracing::info!("Job succeeded: {} {}", handle.task_id, request.package_id);
+            handle.update(|job| {
+                job.state = JobState::Succeeded;
+                job.step_index = None;
+            });
+        }
+        Err(_) if handle.is_cancelled() => {
+            tracing::info!("Job cancelled: {} {}", handle.task_id, request.package_id);
+            handle.set_state(JobState::Cancelled);
+        }
+        Err(e) => {
+            tracing::error!("Job failed: {} {}: {}", handle.task_id, request.package_id, e);
+            handle.update(|job| {
+                job.state = JobState::Failed;
+                job.error = Some(e);
+            });
+        }
+    }
+}
+
+fn spawn_job(app: &tauri::AppHandle, task_id: String, request: JobRequest, cancel: Arc<AtomicBool>) {
+    let handle = JobHandle { app: app.clone(), task_id, cancel };
+    tauri::async_runtime::spawn(run_job(handle, request));
+}
+
+fn next_task_id() -> String {
+    format!("job-{}-{}", chrono::Utc::now().timestamp_micros(), JOB_SEQ.fetch_add(1, Ordering::Relaxed))
+}
+
+fn new_job(task_id: String, request: &JobRequest, total_steps: usize) -> Job {
+    let now = chrono::Local::now().to_rfc3339();
+    Job {
+        task_id,
+        kind: request.kind,
+        package_id: request.package_id.clone(),
+        version: request.version.clone(),
+        download_url: request.download_url.clone(),
+        overlap_policy: request.overlap_policy,
+        state: JobState::Queued,
+        step_index: None,
+        total_steps,
+        read: 0,
+        total: None,
+        error: None,
+        verification: None,
+        deferred_files: Vec::new(),
+        depends_on: Vec::new(),
+        attempts: 1,
+        created_at: now.clone(),
+        updated_at: now,
+    }
+}
+
+// 受け付けなかった要求（ジョブ一覧には載せず、失敗したジョブとしてそのまま返す）
+// 一覧に無いので retry_job では再試行できない。検証からやり直すため enqueue_jobs で要求し直す
+fn rejected_job(request: &JobRequest, error: String) -> Job {
+    tracing::warn!("Job request rejected: {:?} {}: {}", request.kind, request.package_id, error);
+    let mut job = new_job(next_task_id(), request, 0);
+    job.state = JobState::Failed;
+    job.error = Some(error);
+    job
+}
+
+// インストール・更新の要求を依存関係の順に並べ、未インストールの依存パッケージのインストールを加える
+// 戻り値は (要求, 同じバッチで先に終わっている必要があるパッケージ) の一覧。アンインストールは先頭に元の順で置く
+// 置き換えられるインストール済みパッケージはアンインストールを加え、置き換え先はその完了を待ってから入れる
+fn order_requests(
+    requests: Vec<JobRequest>,
+    installed: &HashMap<String, String>,
+    catalog: &HashMap<String, CatalogInstallPackage>,
+    rejected: &mut Vec<Job>,
+) -> Vec<(JobRequest, Vec<String>)> {
+    let (uninstalls, installs): (Vec<JobRequest>, Vec<JobRequest>) = requests.into_iter().partition(|r| r.kind == JobKind::Uninstall);
+    let mut ordered: Vec<(JobRequest, Vec<String>)> = uninstalls.into_iter().map(|r| (r, Vec::new())).collect();
+    let ids: Vec<String> = installs.iter().map(|r| r.package_id.clone()).collect();
+    let mut requested: HashMap<String, JobRequest> = installs.into_iter().map(|r| (r.package_id.clone(), r)).collect();
+    let plan = super::resolver::resolve(&ids, installed, catalog);
+    for cycle in &plan.cycles {
+        tracing::warn!("Dependency cycle among requested packages: {}", cycle.join(" -> "));
+    }
+
+    let mut blocked: HashMap<String, String> = HashMap::new();
+    for missing in &plan.unavailable {
+        for id in &missing.required_by {
+            blocked.entry(id.clone()).or_insert_with(|| format!("required package is not available: {}", missing.id));
+        }
+    }
+    // 新しく入れるパッケージだけを止める（インストール済みどうしの更新は妨げない）
+    for conflict in &plan.conflicts {
+        let (id, other) =
+            if installed.contains_key(&conflict.package_id) { (&conflict.conflicts_with, &conflict.package_id) } else { (&conflict.package_id, &conflict.conflicts_with) };
+        if !installed.contains_key(id) {
+            blocked.entry(id.clone()).or_insert_with(|| format!("package conflicts with {}: {}", other, id));
+        }
+    }
+
+    let in_batch: HashSet<&str> = plan.order.iter().map(|p| p.id.as_str()).collect();
+    let mut batch_requires: HashMap<&str, Vec<String>> = HashMap::new();
+    for resolved in &plan.order {
+        let requires: Vec<String> = catalog
+            .get(&resolved.id)
+            .and_then(|p| p.relations.as_ref())
+            .map(|r| r.requires.iter().filter(|dep| in_batch.contains(dep.as_str())).cloned().collect())
+            .unwrap_or_default();
+        // 依存先が止まっていればこのパッケージも止める（order は依存先が先なので 1 回の走査で伝わる）
+        if !blocked.contains_key(&resolved.id)
+            && let Some(dep) = requires.iter().find(|dep| blocked.contains_key(*dep))
+        {
+            blocked.insert(resolved.id.clone(), format!("required package cannot be installed: {dep}"));
+        }
+        batch_requires.insert(&resolved.id, requires);
+    }
+
+    // 置き換え先を入れられない場合は、置き換えられる側も残す
+    for removal in plan.removals.iter().filter(|r| !blocked.contains_key(&r.replaced_by)) {
+        if !catalog.contains_key(&removal.package_id) {
+            tracing::warn!("Cannot uninstall {} replaced by {}: not in the install catalog", removal.package_id, removal.replaced_by);
+            continue;
+        }
+        if let Some(requires) = batch_requires.get_mut(removal.replaced_by.as_str()) {
+            requires.push(removal.package_id.clone());
+        }
+        if !ordered.iter().any(|(r, _)| r.package_id == removal.package_id) {
+            let request = JobRequest {
+                kind: JobKind::Uninstall,
+                package_id: removal.package_id.clone(),
+                version: None,
+                download_url: None,
+                overlap_policy: None,
+            };
+            ordered.push((request, Vec::new()));
+        }
+    }
+
+    for resolved in &plan.order {
+        let requires = batch_requires.remove(resolved.id.as_str()).unwrap_or_default();
+        let request = requested.remove(&resolved.id).unwrap_or_else(|| JobRequest {
+            kind: JobKind::Install,
+            package_id: resolved.id.clone(),
+            version: None,
+            download_url: None,
+            overlap_policy: None,
+        });
+        match blocked.get(&resolved.id) {
+            Some(error) if resolved.requested => rejected.push(rejected_job(&request, error.clone())),
+            Some(_) => {}
+            None => ordered.push((request, requires)),
+        }
+    }
+    ordered
+}
+
+// 1 件ずつ確かめて、受け付けられない要求はその要求だけを失敗にする
+fn validate_request(request: &JobRequest, catalog: &HashMap<String, CatalogInstallPackage>, holds: &BTreeMap<String, String>) -> Result<(), String> {
+    if request.package_id.is_empty() {
+        return Err(crate::paths::common_message_current("backend.errors.packageIdEmpty"));
+    }
+    if !catalog.contains_key(&request.package_id) {
+        return Err(format!("package not found in install catalog: {}", request.package_id));
+    }
+    // 固定中のパッケージは固定バージョン以外への更新を受け付けない
+    if request.kind == JobKind::Update
+        && let Some(held) = holds.get(&request.package_id)
+        && request.version.as_deref() != Some(held.as_str())
+    {
+        return Err(crate::paths::common_message_with_args(
+            crate::paths::current_ui_locale(),
+            "backend.errors.packageVersionHeld",
+            &[("packageId", &request.package_id), ("version", held)],
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn enqueue_jobs(app: tauri::AppHandle, jobs: Vec<JobRequest>) -> Result<Vec<Job>, String> {
+    let catalog = installer::catalog_packages()?;
+    let holds = crate::paths::package_version_holds(&app);
+    let installed = super::installed::read_installed_map(&app);
+    let mut accepted = Vec::new();
+    let mut rejected = Vec::new();
+    for request in jobs {
+        let request = JobRequest { package_id: request.package_id.trim().to_string(), ..request };
+        match validate_request(&request, &catalog, &holds) {
+            Ok(()) => accepted.push(request),
+            Err(e) => rejected.push(rejected_job(&request, e)),
+        }
+    }
+    let ordered = order_requests(accepted, &installed, &catalog, &mut rejected);
+
+    let mut queued = Vec::new();
+    let mut to_spawn = Vec::new();
+    {
+        let mut entries = lock_jobs();
+        let mut task_ids: HashMap<String, String> = HashMap::new();
+        for (request, requires) in ordered {
+            // 同じパッケージのジョブが進行中なら新しく積まずにそれを返す
+            if let Some(active) = entries.values().find(|e| e.job.package_id == request.package_id && !e.job.state.is_finished()) {
+                task_ids.insert(request.package_id.clone(), active.job.task_id.clone());
+                queued.push(active.job.clone());
+                continue;
+            }
+            let task_id = next_task_id();
+            let installation = &catalog[&request.package_id].installation;
+            let total_steps = match request.kind {
+                JobKind::Uninstall => installation.uninstall_steps.len(),
+                _ => installation.install_steps.len(),
+            };
+            let mut job = new_job(task_id.clone(), &request, total_steps);
+            job.depends_on = requires.iter().filter_map(|id| task_ids.get(id).cloned()).collect();
+            let cancel = Arc::new(AtomicBool::new(false));
+            entries.insert(task_id.clone(), JobEntry { job: job.clone(), cancel: cancel.clone() });
+            task_ids.insert(request.package_id.clone(), task_id.clone());
+            queued.push(job);
+            to_spawn.push((task_id, request, cancel));
+        }
+    }
+    for (task_id, request, cancel) in to_spawn {
+        spawn_job(&app, task_id, request, cancel);
+    }
+    queued.extend(rejected);
+    Ok(queued)
+}
+
+#[tauri::command]
+pub fn list_jobs() -> Vec<Job> {
+    lock_jobs().values().map(|e| e.job.clone()).collect()
+}
+
+#[tauri::command]
+pub fn cancel_job(app: tauri::AppHandle, task_id: String) -> Result<Job, String> {
+    let snapshot = {
+        let mut jobs = lock_jobs();
+        let entry = jobs.get_mut(&task_id).ok_or_else(|| format!("job not found: {task_id}"))?;
+        if entry.job.state.is_finished() {
+            return Ok(entry.job.clone());
+        }
+        entry.cancel.store(true, Ordering::Relaxed);
+        // 展開中ならその場で止める。それ以外の手順は区切りで止まり、ロールバックしてから Cancelled になる
+        super::archive::cancel_extract_task(&task_id);
+        if matches!(entry.job.state, JobState::Queued | JobState::Waiting) {
+            entry.job.state = JobState::Cancelled;
+            entry.job.updated_at = chrono::Local::now().to_rfc3339();
+        }
+        entry.job.clone()
+    };
+    let _ = app.emit("job:update", snapshot.clone());
+    Ok(snapshot)
+}
+
+#[tauri::command]
+pub fn retry_job(app: tauri::AppHandle, task_id: String) -> Result<Job, String> {
+    let (snapshot, request, cancel) = {
+        let mut jobs = lock_jobs();
+        let entry = jobs.get_mut(&task_id).ok_or_else(|| format!("job not found: {task_id}"))?;
+        if !matches!(entry.job.state, JobState::Failed | JobState::Cancelled) {
+            return Err(format!("job cannot be retried in its current state: {task_id}"));
+        }
+        let job = &mut entry.job;
+        job.state = JobState::Queued;
+        job.step_index = None;
+        job.read = 0;
+        job.total = None;
+        job.error = None;
+        job.verification = None;
+        job.deferred_files.clear();
+        job.attempts += 1;
+        job.updated_at = chrono::Local::now().to_rfc3339();
+        entry.cancel = Arc::new(AtomicBool::new(false));
+        let request = JobRequest {
+            kind: job.kind,
+            package_id: job.package_id.clone(),
+            version: job.version.clone(),
+            download_url: job.download_url.clone(),
+            overlap_policy: job.overlap_policy,
+        };
+        (job.clone(), request, entry.cancel.clone())
+    };
+    let _ = app.emit("job:update", snapshot.clone());
+    spawn_job(&app, task_id, request, cancel);
+    Ok(snapshot)
+}
+
+#[tauri::command]
+pub fn clear_finished_jobs() -> usize {
+    let mut jobs = lock_jobs();
+    let before = jobs.len();
+    jobs.retain(|_, e| !e.job.state.is_finished());
+    before - jobs.len()
+}
+
+#[tauri::command]
+pub fn set_download_concurrency(limit: usize) -> usize {
+    let limit = limit.clamp(1, MAX_DOWNLOAD_CONCURRENCY);
+    let mut current = DOWNLOAD_LIMIT.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // 新しいセマフォに差し替えると実行中のダウンロードが数に入らなくなるので、同じセマフォの許可数を増減する
+    if limit > *current {
+        DOWNLOAD_SLOTS.add_permits(limit - *current);
+    } else if limit < *current {
+        let excess = *current - limit;
+        let forgotten = DOWNLOAD_SLOTS.forget_permits(excess);
+        if forgotten < excess {
+            // 使用中の分は返ってきた時点で取り上げる
+            let rest = (excess - forgotten) as u32;
+            tauri::async_runtime::spawn(async move {
+                if let Ok(permits) = DOWNLOAD_SLOTS.clone().acquire_many_owned(rest).await {
+                    permits.forget();
+                }
+            });
+        }
+    }
+    *current = limit;
+    limit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog(entries: &[(&str, serde_json::Value)]) -> HashMap<String, CatalogInstallPackage> {
+        entries
+            .iter()
+            .map(|(id, relations)| {
+                let package = serde_json::json!({
+                    "relations": relations,
+                    "installation": { "source": { "type": "directUrl", "url": "https://example.com/a.zip" }, "installSteps": [], "uninstallSteps": [] },
+                });
+                (id.to_string(), serde_json::from_value(package).unwrap())
+            })
+            .collect()
+    }
+
+    fn request(kind: JobKind, id: &str) -> JobRequest {
+        JobRequest {
+            kind,
+            package_id: id.to_string(),
+            version: None,
+            download_url: None,
+            overlap_policy: None,
+        }
+    }
+
+    fn summary(ordered: &[(JobRequest, Vec<String>)]) -> Vec<(JobKind, &str, Vec<&str>)> {
+        ordered.iter().map(|(r, requires)| (r.kind, r.package_id.as_str(), requires.iter().map(String::as_str).collect())).collect()
+    }
+
+    #[test]
+    fn orders_dependencies_first_after_requested_uninstalls() {
+        let catalog = catalog(&[
+            ("app", serde_json::json!({ "requires": ["lib", "base"] })),
+            ("lib", serde_json::json!({})),
+            ("base", serde_json::json!({})),
+            ("old", serde_json::json!({})),
+        ]);
+        let installed = HashMap::from([("base".to_string(), "1.0".to_string()), ("app".to_string(), "1.0".to_string())]);
+        let mut rejected = Vec::new();
+        let ordered = order_requests(vec![request(JobKind::Update, "app"), request(JobKind::Uninstall, "old")], &installed, &catalog, &mut rejected);
+        assert_eq!(
+            summary(&ordered),
+            [
+                (JobKind::Uninstall, "old", vec![]),
+                (JobKind::Install, "lib", vec![]),
+                (JobKind::Update, "app", vec!["lib"])
+            ]
+        );
+        assert!(rejected.is_empty());
+    }
+
+    #[test]
+    fn rejects_new_conflicting_packages_but_not_updates_of_installed_ones() {
+        let catalog = catalog(&[
+            ("new", serde_json::json!({ "conflicts": ["old"] })),
+            ("old", serde_json::json!({ "conflicts": ["peer"] })),
+            ("peer", serde_json::json!({})),
+        ]);
+        let installed = HashMap::from([("old".to_string(), "1.0".to_string()), ("peer".to_string(), "1.0".to_string())]);
+        let mut rejected = Vec::new();
+        let ordered = order_requests(vec![request(JobKind::Install, "new"), request(JobKind::Update, "old")], &installed, &catalog, &mut rejected);
+        assert_eq!(summary(&ordered), [(JobKind::Update, "old", vec![])]);
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].package_id, "new");
+        assert_eq!(rejected[0].state, JobState::Failed);
+    }
+
+    #[test]
+    fn rejects_requests_whose_dependencies_cannot_be_installed() {
+        let catalog = catalog(&[
+            ("app", serde_json::json!({ "requires": ["lib"] })),
+            ("lib", serde_json::json!({ "requires": ["missing"] })),
+            ("other", serde_json::json!({})),
+        ]);
+        let mut rejected = Vec::new();
+        let ordered = order_requests(vec![request(JobKind::Install, "app"), request(JobKind::Install, "other")], &HashMap::new(), &catalog, &mut rejected);
+        assert_eq!(summary(&ordered), [(JobKind::Install, "other", vec![])]);
+        // 自動で加えた依存パッケージは要求ではないので、要求した側だけを失敗にする
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].package_id, "app");
+        assert_eq!(rejected[0].error.as_deref(), Some("required package cannot be installed: lib"));
+    }
+
+    #[test]
+    fn uninstalls_replaced_packages_before_their_replacement() {
+        let catalog = catalog(&[
+            ("next", serde_json::json!({ "replaces": ["legacy"] })),
+            ("legacy", serde_json::json!({})),
+        ]);
+        let installed = HashMap::from([("legacy".to_string(), "0.9".to_string())]);
+        let mut rejected = Vec::new();
+        let ordered = order_requests(vec![request(JobKind::Install, "next")], &installed, &catalog, &mut rejected);
+        assert_eq!(
+            summary(&ordered),
+            [
+                (JobKind::Uninstall, "legacy", vec![]),
+                (JobKind::Install, "next", vec!["legacy"])
+            ]
+        );
+        assert!(rejected.is_empty());
+    }
+
+    #[test]
+    fn keeps_replaced_packages_when_the_replacement_is_blocked() {
+        let catalog = catalog(&[
+            ("next", serde_json::json!({ "replaces": ["legacy"], "requires": ["missing"] })),
+            ("legacy", serde_json::json!({})),
+        ]);
+        let installed = HashMap::from([("legacy".to_string(), "0.9".to_string())]);
+        let mut rejected = Vec::new();
+        let ordered = order_requests(vec![request(JobKind::Install, "next")], &installed, &catalog, &mut rejected);
+        assert!(ordered.is_empty());
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].package_id, "next");
+    }
+}
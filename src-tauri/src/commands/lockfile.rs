@@ -0,0 +1,440 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::installed::InstalledPackage;
+use super::installer::{CatalogInstallPackage, InstallerSource};
+use super::jobs::{Job, JobKind, JobRequest};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "version", content = "data")]
+enum LockRoot {
+    #[serde(rename = "1")]
+    V1(EnvironmentLock),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentLock {
+    pub generated_at: String,
+    #[serde(default)]
+    pub packages: BTreeMap<String, LockedPackage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedPackage {
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    #[serde(default)]
+    pub files: Vec<LockedFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedFile {
+    // {appDir} などのマクロ形式（端末ごとのフォルダ構成の違いを吸収する）
+    pub path: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LockFormat {
+    Json,
+    Toml,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockExportSummary {
+    pub path: String,
+    pub format: LockFormat,
+    pub packages: usize,
+    pub files: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LockActionReason {
+    Missing,
+    VersionMismatch,
+    Modified,
+    NotLocked,
+    // ロックしたパッケージの置き換え先としてアンインストールされる
+    Replaced,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockAction {
+    pub package_id: String,
+    pub kind: JobKind,
+    pub reason: LockActionReason,
+    pub locked_version: Option<String>,
+    pub installed_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnavailableLockedPackage {
+    pub package_id: String,
+    pub locked_version: String,
+    pub available_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockApplyReport {
+    pub actions: Vec<LockAction>,
+    pub unavailable: Vec<UnavailableLockedPackage>,
+    pub unchanged: Vec<String>,
+    // カタログに無いためアンインストールできないパッケージ
+    pub unmanaged: Vec<String>,
+    pub jobs: Vec<Job>,
+}
+
+fn detect_format(path: &Path, format: Option<LockFormat>) -> LockFormat {
+    format.unwrap_or_else(|| match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("toml") => LockFormat::Toml,
+        _ => LockFormat::Json,
+    })
+}
+
+// 検出済みのバージョンがあればそれを優先する
+fn effective_version(package: &InstalledPackage) -> String {
+    package
+        .detected
+        .as_ref()
+        .filter(|d| d.get("kind").and_then(|k| k.as_str()) == Some("detected"))
+        .and_then(|d| d.get("version"))
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| package.version.clone())
+}
+
+fn lock_package(id: &str, package: &InstalledPackage) -> LockedPackage {
+    let version = effective_version(package);
+    let mut paths: Vec<PathBuf> = package.files.iter().map(|f| PathBuf::from(&f.path)).collect();
+    if paths.is_empty() {
+        paths = super::version::catalog_version_files(id, Some(&version));
+    }
+    let files = paths
+        .iter()
+        .filter_map(|path| match super::version::xxh3_128_hex(path) {
+            Ok(hash) => Some(LockedFile { path: super::version::collapse_macros(&path.to_string_lossy()), hash }),
+            Err(e) => {
+                tracing::warn!("Skipping {} in environment lock: {}", path.display(), e);
+                None
+            }
+        })
+        .collect();
+    LockedPackage {
+        version,
+        source_type: package.source_type.clone(),
+        source_url: package.source_url.clone(),
+        files,
+    }
+}
+
+fn read_lock(path: &Path) -> Result<EnvironmentLock, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    let root: LockRoot = match detect_format(path, None) {
+        LockFormat::Toml => toml::from_str(&text).map_err(|e| format!("failed to parse {}: {}", path.display(), e))?,
+        LockFormat::Json => serde_json::from_str(&text).map_err(|e| format!("failed to parse {}: {}", path.display(), e))?,
+    };
+    let LockRoot::V1(lock) = root;
+    Ok(lock)
+}
+
+fn files_match(locked: &LockedPackage) -> bool {
+    locked.files.iter().all(|f| {
+        let path = super::version::expand_macros(&f.path);
+        super::version::xxh3_128_hex(&path).is_ok_and(|hash| hash == f.hash)
+    })
+}
+
+#[tauri::command]
+pub async fn export_environment_lock(app: tauri::AppHandle, path: String, format: Option<LockFormat>) -> Result<LockExportSummary, String> {
+    let state = super::installed::read_installed_state(&app);
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = PathBuf::from(path);
+        let format = detect_format(&path, format);
+        let packages: BTreeMap<String, LockedPackage> = state.packages.iter().map(|(id, package)| (id.clone(), lock_package(id, package))).collect();
+        let files = packages.values().map(|p| p.files.len()).sum();
+        let root = LockRoot::V1(EnvironmentLock { generated_at: chrono::Local::now().to_rfc3339(), packages });
+        let text = match format {
+            LockFormat::Toml => toml::to_string_pretty(&root).map_err(|e| format!("serialize environment lock error: {}", e))?,
+            LockFormat::Json => serde_json::to_string_pretty(&root).map_err(|e| format!("serialize environment lock error: {}", e))?,
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("create dir error: {}", e))?;
+        }
+        fs::write(&path, text).map_err(|e| format!("write {} error: {}", path.display(), e))?;
+        let LockRoot::V1(lock) = root;
+        tracing::info!("Exported environment lock to {} ({} packages, {} files)", path.display(), lock.packages.len(), files);
+        Ok(LockExportSummary {
+            path: path.to_string_lossy().into_owned(),
+            format,
+            packages: lock.packages.len(),
+            files,
+        })
+    })
+    .await
+    .map_err(|e| format!("task join error: {e}"))?
+}
+
+fn diff_lock(
+    lock: &EnvironmentLock,
+    installed: &BTreeMap<String, InstalledPackage>,
+    catalog: &HashMap<String, CatalogInstallPackage>,
+    latest_version: impl Fn(&str) -> Option<String>,
+    files_match: impl Fn(&LockedPackage) -> bool,
+) -> LockApplyReport {
+    let mut report = LockApplyReport::default();
+    for (id, locked) in &lock.packages {
+        let installed_version = installed.get(id).map(effective_version);
+        let (kind, reason) = match &installed_version {
+            None => (JobKind::Install, LockActionReason::Missing),
+            Some(v) if *v != locked.version => (JobKind::Update, LockActionReason::VersionMismatch),
+            Some(_) if !files_match(locked) => (JobKind::Update, LockActionReason::Modified),
+            Some(_) => {
+                report.unchanged.push(id.clone());
+                continue;
+            }
+        };
+        // GitHub のリリースはタグから過去のバージョンも取れる。それ以外の取得元は配布中の最新版のみ
+        let available_version = latest_version(id);
+        let available = match catalog.get(id) {
+            Some(package) if matches!(package.installation.source, InstallerSource::GithubRelease { .. }) => true,
+            Some(_) => available_version.as_deref() == Some(locked.version.as_str()),
+            None => false,
+        };
+        if !available {
+            report.unavailable.push(UnavailableLockedPackage {
+                package_id: id.clone(),
+                locked_version: locked.version.clone(),
+                available_version,
+            });
+            continue;
+        }
+        report.actions.push(LockAction {
+            package_id: id.clone(),
+            kind,
+            reason,
+            locked_version: Some(locked.version.clone()),
+            installed_version,
+        });
+    }
+    for (id, package) in installed {
+        if lock.packages.contains_key(id) {
+            continue;
+        }
+        if !catalog.contains_key(id) {
+            report.unmanaged.push(id.clone());
+            continue;
+        }
+        report.actions.push(LockAction {
+            package_id: id.clone(),
+            kind: JobKind::Uninstall,
+            reason: LockActionReason::NotLocked,
+            locked_version: None,
+            installed_version: Some(effective_version(package)),
+        });
+    }
+    // 積まれるジョブと同じく、アンインストールを先に、依存パッケージを依存元より先に並べる
+    let ids: Vec<String> = report.actions.iter().filter(|a| a.kind != JobKind::Uninstall).map(|a| a.package_id.clone()).collect();
+    let installed_versions: HashMap<String, String> = installed.iter().map(|(id, package)| (id.clone(), effective_version(package))).collect();
+    let plan = super::resolver::resolve(&ids, &installed_versions, catalog);
+    // 置き換えられるパッケージはジョブを積むときにアンインストールが加わるので、差分にも載せておく
+    for removal in plan.removals {
+        if report.actions.iter().any(|a| a.package_id == removal.package_id) || !catalog.contains_key(&removal.package_id) {
+            continue;
+        }
+        report.unchanged.retain(|id| *id != removal.package_id);
+        report.actions.push(LockAction {
+            package_id: removal.package_id,
+            kind: JobKind::Uninstall,
+            reason: LockActionReason::Replaced,
+            locked_version: None,
+            installed_version: Some(removal.installed_version),
+        });
+    }
+    let order: HashMap<String, usize> = plan.order.into_iter().enumerate().map(|(i, p)| (p.id, i)).collect();
+    report.actions.sort_by_key(|a| (a.kind != JobKind::Uninstall, order.get(&a.package_id).copied().unwrap_or(usize::MAX)));
+    report
+}
+
+/// ロックファイルと現在の環境の差分を求め、dry_run でなければインストール・アンインストールのジョブを積む
+#[tauri::command]
+pub async fn apply_environment_lock(app: tauri::AppHandle, path: String, dry_run: Option<bool>) -> Result<LockApplyReport, String> {
+    let state = super::installed::read_installed_state(&app);
+    let mut report = tauri::async_runtime::spawn_blocking(move || {
+        let lock = read_lock(Path::new(&path))?;
+        let catalog = super::installer::catalog_packages()?;
+        Ok::<_, String>(diff_lock(&lock, &state.packages, &catalog, super::version::catalog_latest_version, files_match))
+    })
+    .await
+    .map_err(|e| format!("task join error: {e}"))??;
+    tracing::info!(
+        "Environment lock diff: actions={} unavailable={} unchanged={} unmanaged={}",
+        report.actions.len(),
+        report.unavailable.len(),
+        report.unchanged.len(),
+        report.unmanaged.len()
+    );
+    if dry_run.unwrap_or(false) || report.actions.is_empty() {
+        return Ok(report);
+    }
+    // アンインストールを先に積んで、置き換え先と同じファイルを触る場合に備える（diff_lock で並べ済み）
+    let requests: Vec<JobRequest> = report
+        .actions
+        .iter()
+        .map(|a| JobRequest {
+            kind: a.kind,
+            package_id: a.package_id.clone(),
+            version: a.locked_version.clone(),
+            download_url: None,
+            overlap_policy: None,
+        })
+        .collect();
+    report.jobs = super::jobs::enqueue_jobs(app, requests)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog(entries: &[(&str, serde_json::Value, serde_json::Value)]) -> HashMap<String, CatalogInstallPackage> {
+        entries
+            .iter()
+            .map(|(id, source, relations)| {
+                let package = serde_json::json!({
+                    "relations": relations,
+                    "installation": { "source": source, "installSteps": [], "uninstallSteps": [] },
+                });
+                (id.to_string(), serde_json::from_value(package).unwrap())
+            })
+            .collect()
+    }
+
+    fn direct() -> serde_json::Value {
+        serde_json::json!({ "type": "directUrl", "url": "https://example.com/a.zip" })
+    }
+
+    fn locked(version: &str) -> LockedPackage {
+        LockedPackage {
+            version: version.to_string(),
+            source_type: None,
+            source_url: None,
+            files: vec![LockedFile { path: "{pluginsDir}\\a.aux2".to_string(), hash: "0123".to_string() }],
+        }
+    }
+
+    fn installed(version: &str) -> InstalledPackage {
+        InstalledPackage { version: version.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn lock_round_trips_through_json_and_toml() {
+        let dir = std::env::temp_dir().join(format!("aviutl2-lockfile-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let lock = EnvironmentLock {
+            generated_at: "2026-01-01T00:00:00+09:00".to_string(),
+            packages: BTreeMap::from([("author.pkg".to_string(), locked("1.2.0"))]),
+        };
+        for (name, text) in [
+            ("lock.json", serde_json::to_string_pretty(&LockRoot::V1(lock.clone())).unwrap()),
+            ("lock.toml", toml::to_string_pretty(&LockRoot::V1(lock.clone())).unwrap()),
+        ] {
+            let path = dir.join(name);
+            fs::write(&path, text).unwrap();
+            let read = read_lock(&path).unwrap();
+            assert_eq!(read.generated_at, lock.generated_at);
+            let package = &read.packages["author.pkg"];
+            assert_eq!(package.version, "1.2.0");
+            assert_eq!(package.files[0].path, "{pluginsDir}\\a.aux2");
+            assert_eq!(package.files[0].hash, "0123");
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn diff_orders_uninstalls_first_and_dependencies_before_dependents() {
+        let catalog = catalog(&[
+            ("app", direct(), serde_json::json!({ "requires": ["lib"] })),
+            ("lib", direct(), serde_json::json!({})),
+            ("same", direct(), serde_json::json!({})),
+            ("edited", direct(), serde_json::json!({})),
+            ("extra", direct(), serde_json::json!({})),
+        ]);
+        let lock = EnvironmentLock {
+            generated_at: String::new(),
+            packages: BTreeMap::from([
+                ("app".to_string(), locked("2.0")),
+                ("lib".to_string(), locked("1.0")),
+                ("same".to_string(), locked("1.0")),
+                (
+                    "edited".to_string(),
+                    LockedPackage {
+                        files: vec![LockedFile { path: "{pluginsDir}\\b.aux2".to_string(), hash: "edited".to_string() }],
+                        ..locked("1.0")
+                    },
+                ),
+            ]),
+        };
+        let installed = BTreeMap::from([
+            ("app".to_string(), installed("1.0")),
+            ("same".to_string(), installed("1.0")),
+            ("edited".to_string(), installed("1.0")),
+            ("extra".to_string(), installed("1.0")),
+            ("manual".to_string(), installed("1.0")),
+        ]);
+        let latest = |id: &str| Some(if id == "app" { "2.0" } else { "1.0" }.to_string());
+        let report = diff_lock(&lock, &installed, &catalog, latest, |p| p.files[0].hash != "edited");
+        let actions: Vec<(&str, JobKind, LockActionReason)> = report.actions.iter().map(|a| (a.package_id.as_str(), a.kind, a.reason)).collect();
+        assert_eq!(
+            actions,
+            [
+                ("extra", JobKind::Uninstall, LockActionReason::NotLocked),
+                ("lib", JobKind::Install, LockActionReason::Missing),
+                ("app", JobKind::Update, LockActionReason::VersionMismatch),
+                ("edited", JobKind::Update, LockActionReason::Modified),
+            ]
+        );
+        assert_eq!(report.unchanged, ["same"]);
+        assert_eq!(report.unmanaged, ["manual"]);
+        assert!(report.unavailable.is_empty());
+    }
+
+    #[test]
+    fn older_locked_versions_are_available_only_from_github_releases() {
+        let github = serde_json::json!({ "type": "githubRelease", "owner": "author", "repo": "pkg", "pattern": "glob:*.zip" });
+        let catalog = catalog(&[
+            ("github", github, serde_json::json!({})),
+            ("direct", direct(), serde_json::json!({})),
+        ]);
+        let lock = EnvironmentLock {
+            generated_at: String::new(),
+            packages: BTreeMap::from([
+                ("github".to_string(), locked("1.0")),
+                ("direct".to_string(), locked("1.0")),
+                ("gone".to_string(), locked("1.0")),
+            ]),
+        };
+        let report = diff_lock(&lock, &BTreeMap::new(), &catalog, |_| Some("2.0".to_string()), |_| true);
+        assert_eq!(report.actions.len(), 1);
+        assert_eq!(report.actions[0].package_id, "github");
+        assert_eq!(report.actions[0].locked_version.as_deref(), Some("1.0"));
+        let mut unavailable: Vec<&str> = report.unavailable.iter().map(|u| u.package_id.as_str()).collect();
+        unavailable.sort();
+        assert_eq!(unavailable, ["direct", "gone"]);
+    }
+}
@@ -0,0 +1,512 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use encoding_rs::SHIFT_JIS;
+
+// LHA (.lzh) 書庫の読み込み。ヘッダーレベル 0〜2、-lh0- / -lh5- / -lh6- / -lh7- / -lhd- に対応する
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LzhMethod {
+    Stored,
+    Directory,
+    Lh5,
+    Lh6,
+    Lh7,
+    Unsupported([u8; 5]),
+}
+
+impl LzhMethod {
+    fn parse(id: &[u8]) -> Self {
+        match id {
+            b"-lh0-" | b"-lz4-" => Self::Stored,
+            b"-lhd-" => Self::Directory,
+            b"-lh5-" => Self::Lh5,
+            b"-lh6-" => Self::Lh6,
+            b"-lh7-" => Self::Lh7,
+            _ => Self::Unsupported(id.try_into().unwrap_or([b'?'; 5])),
+        }
+    }
+
+    // (辞書サイズのビット数, 位置コードの種類数, 位置コード表の長さのビット数)
+    fn params(self) -> Option<(u32, usize, u32)> {
+        match self {
+            Self::Lh5 => Some((13, 14, 4)),
+            Self::Lh6 => Some((15, 16, 5)),
+            Self::Lh7 => Some((16, 17, 5)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct LzhEntry {
+    // Shift_JIS から復号し、区切りを / にそろえた名前
+    pub name: String,
+    pub method: LzhMethod,
+    pub compressed_size: u64,
+    pub original_size: u64,
+    pub crc: u16,
+    data_offset: u64,
+}
+
+fn truncated() -> String {
+    "truncated LZH header".to_string()
+}
+
+fn le16(buf: &[u8], at: usize) -> Result<u16, String> {
+    buf.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]])).ok_or_else(truncated)
+}
+
+fn le32(buf: &[u8], at: usize) -> Result<u32, String> {
+    buf.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).ok_or_else(truncated)
+}
+
+fn decode_name(raw: &[u8]) -> String {
+    match std::str::from_utf8(raw) {
+        Ok(name) => name.to_string(),
+        Err(_) => SHIFT_JIS.decode_without_bom_handling(raw).0.into_owned(),
+    }
+}
+
+#[derive(Default)]
+struct HeaderFields {
+    file_name: Vec<u8>,
+    dir_name: Vec<u8>,
+}
+
+impl HeaderFields {
+    // 拡張ヘッダー（種別 1 バイト + データ）を反映する
+    fn apply_extension(&mut self, kind: u8, data: &[u8]) {
+        match kind {
+            0x01 => self.file_name = data.to_vec(),
+            0x02 => self.dir_name = data.to_vec(),
+            _ => {}
+        }
+    }
+
+    fn name(&self) -> String {
+        // ディレクトリ名は 0xFF 区切り（Shift_JIS の 2 バイト目に 0xFF は現れない）
+        let mut parts: Vec<String> = self.dir_name.split(|b| *b == 0xFF).filter(|part| !part.is_empty()).map(decode_name).collect();
+        if !self.file_name.is_empty() {
+            parts.push(decode_name(&self.file_name));
+        }
+        // レベル 0 のヘッダーは名前に \ を含む。2 バイト文字の 0x5C と区別するため復号してから置き換える
+        parts.join("/").replace('\\', "/").trim_end_matches('/').to_string()
+    }
+}
+
+fn read_extensions<R: Read>(reader: &mut R, mut next: usize) -> Result<(HeaderFields, u64), String> {
+    let mut fields = HeaderFields::default();
+    let mut total = 0u64;
+    while next > 0 {
+        if next < 3 {
+            return Err("invalid LZH extended header".to_string());
+        }
+        let mut ext = vec![0u8; next];
+        reader.read_exact(&mut ext).map_err(|_| truncated())?;
+        fields.apply_extension(ext[0], &ext[1..next - 2]);
+        total += next as u64;
+        next = le16(&ext, next - 2)? as usize;
+    }
+    Ok((fields, total))
+}
+
+fn parse_level01<R: Read + Seek>(reader: &mut R, offset: u64, base: &[u8]) -> Result<LzhEntry, String> {
+    let header_size = base[0] as usize + 2;
+    // 固定部分（名前の長さまで 22 バイト）と CRC が収まらないヘッダーは壊れている
+    if header_size < 24 {
+        return Err(truncated());
+    }
+    let mut header = vec![0u8; header_size];
+    reader.seek(SeekFrom::Start(offset)).map_err(|e| format!("lzh seek error: {e}"))?;
+    reader.read_exact(&mut header).map_err(|_| truncated())?;
+    let checksum = header[2..].iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    if checksum != header[1] {
+        return Err(format!("LZH header checksum mismatch at offset {offset}"));
+    }
+    let name_len = header[21] as usize;
+    if 22 + name_len + 2 > header_size {
+        return Err(truncated());
+    }
+    let file_name = header[22..22 + name_len].to_vec();
+    let crc = le16(&header, 22 + name_len)?;
+    let mut compressed_size = le32(&header, 7)? as u64;
+    let mut data_offset = offset + header_size as u64;
+    let mut fields = HeaderFields { file_name, ..Default::default() };
+    if header[20] == 1 {
+        // 基本ヘッダーの末尾が最初の拡張ヘッダーのサイズ。拡張ヘッダーの分は圧縮サイズに含まれている
+        let (mut extensions, size) = read_extensions(reader, le16(&header, header_size - 2)? as usize)?;
+        if extensions.file_name.is_empty() {
+            extensions.file_name = std::mem::take(&mut fields.file_name);
+        }
+        fields = extensions;
+        compressed_size = compressed_size.checked_sub(size).ok_or_else(|| "invalid LZH extended header size".to_string())?;
+        data_offset += size;
+    }
+    Ok(LzhEntry {
+        name: fields.name(),
+        method: LzhMethod::parse(&header[2..7]),
+        compressed_size,
+        original_size: le32(&header, 11)? as u64,
+        crc,
+        data_offset,
+    })
+}
+
+fn parse_level2<R: Read + Seek>(reader: &mut R, offset: u64, base: &[u8]) -> Result<LzhEntry, String> {
+    let header_size = le16(base, 0)? as usize;
+    if header_size < 26 {
+        return Err(truncated());
+    }
+    reader.seek(SeekFrom::Start(offset + 26)).map_err(|e| format!("lzh seek error: {e}"))?;
+    let mut header = vec![0u8; header_size - 26];
+    reader.read_exact(&mut header).map_err(|_| truncated())?;
+    let (fields, _) = read_extensions(&mut header.as_slice(), le16(base, 24)? as usize)?;
+    Ok(LzhEntry {
+        name: fields.name(),
+        method: LzhMethod::parse(&base[2..7]),
+        compressed_size: le32(base, 7)? as u64,
+        original_size: le32(base, 11)? as u64,
+        crc: le16(base, 21)?,
+        data_offset: offset + header_size as u64,
+    })
+}
+
+fn is_header(base: &[u8]) -> bool {
+    base.len() >= 21 && base[2] == b'-' && base[3] == b'l' && base[6] == b'-'
+}
+
+/// 先頭が LZH のヘッダーかどうか
+pub(crate) fn looks_like_lzh(head: &[u8]) -> bool {
+    is_header(head) && head[20] <= 2
+}
+
+/// 全エントリのヘッダーを読む（データは読み飛ばす）
+pub(crate) fn read_entries<R: Read + Seek>(reader: &mut R) -> Result<Vec<LzhEntry>, String> {
+    let mut entries = Vec::new();
+    let mut offset = 0u64;
+    loop {
+        reader.seek(SeekFrom::Start(offset)).map_err(|e| format!("lzh seek error: {e}"))?;
+        let mut base = [0u8; 26];
+        let mut len = 0;
+        while len < base.len() {
+            match reader.read(&mut base[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(format!("lzh read error: {e}")),
+            }
+        }
+        // 0 バイトのヘッダーが書庫の終端
+        if len == 0 || base[0] == 0 {
+            break;
+        }
+        if !is_header(&base[..len]) {
+            return Err(if entries.is_empty() { "not an LZH archive".to_string() } else { format!("broken LZH header at offset {offset}") });
+        }
+        let entry = match base[20] {
+            0 | 1 => parse_level01(reader, offset, &base)?,
+            2 => parse_level2(reader, offset, &base)?,
+            level => return Err(format!("unsupported LZH header level: {level}")),
+        };
+        offset = entry.data_offset + entry.compressed_size;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+impl LzhEntry {
+    pub(crate) fn is_dir(&self) -> bool {
+        self.method == LzhMethod::Directory
+    }
+
+    /// 展開したデータを読むリーダー（読み終えた時点で CRC を検証する）
+    pub(crate) fn reader<'a, R: Read + Seek>(&self, archive: &'a mut R) -> io::Result<LzhEntryReader<'a>> {
+        archive.seek(SeekFrom::Start(self.data_offset))?;
+        let data = archive.take(self.compressed_size);
+        let inner: Box<dyn Read + 'a> = match self.method {
+            LzhMethod::Stored => Box::new(data),
+            LzhMethod::Directory => Box::new(io::empty()),
+            LzhMethod::Unsupported(id) => return Err(io::Error::new(io::ErrorKind::Unsupported, format!("unsupported LZH method: {}", String::from_utf8_lossy(&id)))),
+            method => {
+                let (dict_bits, np, pbit) = method.params().unwrap_or((13, 14, 4));
+                Box::new(LhDecoder::new(data, dict_bits, np, pbit, self.original_size))
+            }
+        };
+        let (expected_crc, remaining) = if self.is_dir() { (0, 0) } else { (self.crc, self.original_size) };
+        Ok(LzhEntryReader { inner, crc: 0, expected_crc, remaining })
+    }
+}
+
+const CRC16_TABLE: [u16; 256] = {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u16;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { (c >> 1) ^ 0xA001 } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+pub(crate) struct LzhEntryReader<'a> {
+    inner: Box<dyn Read + 'a>,
+    crc: u16,
+    expected_crc: u16,
+    remaining: u64,
+}
+
+impl Read for LzhEntryReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let limit = buf.len().min(self.remaining.min(usize::MAX as u64) as usize);
+        if limit == 0 {
+            if !buf.is_empty() && self.crc != self.expected_crc {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("LZH CRC mismatch (expected {:04x}, got {:04x})", self.expected_crc, self.crc)));
+            }
+            return Ok(0);
+        }
+        let n = self.inner.read(&mut buf[..limit])?;
+        if n == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated LZH data"));
+        }
+        for b in &buf[..n] {
+            self.crc = (self.crc >> 8) ^ CRC16_TABLE[((self.crc ^ *b as u16) & 0xFF) as usize];
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("LZH decode error: {message}"))
+}
+
+struct BitReader<R> {
+    inner: R,
+    bits: u32,
+    count: u32,
+}
+
+impl<R: Read> BitReader<R> {
+    fn bit(&mut self) -> io::Result<u32> {
+        if self.count == 0 {
+            let mut byte = [0u8];
+            // データの末尾以降は 0 で埋まっているものとして扱う
+            self.bits = loop {
+                match self.inner.read(&mut byte) {
+                    Ok(0) => break 0,
+                    Ok(_) => break byte[0] as u32,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            };
+            self.count = 8;
+        }
+        self.count -= 1;
+        Ok((self.bits >> self.count) & 1)
+    }
+
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        let mut value = 0;
+        for _ in 0..n {
+            value = (value << 1) | self.bit()?;
+        }
+        Ok(value)
+    }
+}
+
+// 符号長から組み立てた正準ハフマン符号
+enum Huffman {
+    // 符号長 0 で常に同じ記号になる
+    Single(usize),
+    Codes { counts: [u16; 17], symbols: Vec<usize> },
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Self> {
+        let mut counts = [0u16; 17];
+        for &len in lengths {
+            if len > 16 {
+                return Err(invalid_data("code length too long"));
+            }
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let symbols = (1..=16u8).flat_map(|len| lengths.iter().enumerate().filter(move |(_, l)| **l == len).map(|(symbol, _)| symbol)).collect();
+        Ok(Self::Codes { counts, symbols })
+    }
+
+    fn decode<R: Read>(&self, bits: &mut BitReader<R>) -> io::Result<usize> {
+        let (counts, symbols) = match self {
+            Self::Single(symbol) => return Ok(*symbol),
+            Self::Codes { counts, symbols } => (counts, symbols),
+        };
+        let (mut code, mut first, mut index) = (0usize, 0usize, 0usize);
+        for &count in &counts[1..] {
+            code |= bits.bit()? as usize;
+            let count = count as usize;
+            if code - first < count {
+                return Ok(symbols[index + code - first]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid_data("invalid Huffman code"))
+    }
+}
+
+// 文字・一致長の記号数（256 + 一致長 3〜256）
+const NC: usize = 510;
+const NT: usize = 19;
+const TBIT: u32 = 5;
+const CBIT: u32 = 9;
+const THRESHOLD: usize = 3;
+
+/// -lh5- / -lh6- / -lh7- の静的ハフマン + LZSS の復号器
+struct LhDecoder<R> {
+    bits: BitReader<R>,
+    window: Vec<u8>,
+    pos: usize,
+    np: usize,
+    pbit: u32,
+    remaining: u64,
+    block_left: u32,
+    c_table: Huffman,
+    p_table: Huffman,
+    copy_left: usize,
+    copy_dist: usize,
+}
+
+impl<R: Read> LhDecoder<R> {
+    fn new(inner: R, dict_bits: u32, np: usize, pbit: u32, original_size: u64) -> Self {
+        Self {
+            bits: BitReader { inner, bits: 0, count: 0 },
+            window: vec![0; 1 << dict_bits],
+            pos: 0,
+            np,
+            pbit,
+            remaining: original_size,
+            block_left: 0,
+            c_table: Huffman::Single(0),
+            p_table: Huffman::Single(0),
+            copy_left: 0,
+            copy_dist: 0,
+        }
+    }
+
+    fn read_pt_len(&mut self, nn: usize, nbit: u32, special: Option<usize>) -> io::Result<Huffman> {
+        let n = self.bits.bits(nbit)? as usize;
+        if n == 0 {
+            return Ok(Huffman::Single(self.bits.bits(nbit)? as usize));
+        }
+        if n > nn {
+            return Err(invalid_data("too many code lengths"));
+        }
+        let mut lengths = vec![0u8; nn];
+        let mut i = 0;
+        while i < n {
+            // 3 ビットで 0〜6、7 以上は続く 1 の数だけ加算する
+            let mut len = self.bits.bits(3)?;
+            if len == 7 {
+                while self.bits.bit()? == 1 {
+                    len += 1;
+                    if len > 16 {
+                        return Err(invalid_data("code length too long"));
+                    }
+                }
+            }
+            lengths[i] = len as u8;
+            i += 1;
+            if special == Some(i) {
+                i = (i + self.bits.bits(2)? as usize).min(nn);
+            }
+        }
+        Huffman::new(&lengths)
+    }
+
+    fn read_c_len(&mut self, pt: &Huffman) -> io::Result<Huffman> {
+        let n = self.bits.bits(CBIT)? as usize;
+        if n == 0 {
+            return Ok(Huffman::Single(self.bits.bits(CBIT)? as usize));
+        }
+        if n > NC {
+            return Err(invalid_data("too many code lengths"));
+        }
+        let mut lengths = vec![0u8; NC];
+        let mut i = 0;
+        while i < n {
+            // 0〜2 は 0 の連続（1 個 / 3〜18 個 / 20〜531 個）
+            match pt.decode(&mut self.bits)? {
+                0 => i += 1,
+                1 => i += self.bits.bits(4)? as usize + 3,
+                2 => i += self.bits.bits(CBIT)? as usize + 20,
+                len => {
+                    lengths[i] = (len - 2) as u8;
+                    i += 1;
+                }
+            }
+        }
+        if i > NC {
+            return Err(invalid_data("code lengths overflow"));
+        }
+        Huffman::new(&lengths)
+    }
+
+    fn read_block_header(&mut self) -> io::Result<()> {
+        self.block_left = self.bits.bits(16)?;
+        if self.block_left == 0 {
+            return Err(invalid_data("empty block"));
+        }
+        let pt = self.read_pt_len(NT, TBIT, Some(3))?;
+        self.c_table = self.read_c_len(&pt)?;
+        self.p_table = self.read_pt_len(self.np, self.pbit, None)?;
+        Ok(())
+    }
+
+    fn decode_position(&mut self) -> io::Result<usize> {
+        let j = self.p_table.decode(&mut self.bits)?;
+        if j <= 1 { Ok(j) } else { Ok((1 << (j - 1)) + self.bits.bits(j as u32 - 1)? as usize) }
+    }
+
+    fn push(&mut self, byte: u8) {
+        let mask = self.window.len() - 1;
+        self.window[self.pos & mask] = byte;
+        self.pos = self.pos.wrapping_add(1);
+        self.remaining -= 1;
+    }
+}
+
+impl<R: Read> Read for LhDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() && self.remaining > 0 {
+            if self.copy_left == 0 {
+                if self.block_left == 0 {
+                    self.read_block_header()?;
+                }
+                self.block_left -= 1;
+                let c = self.c_table.decode(&mut self.bits)?;
+                if c < 256 {
+                    self.push(c as u8);
+                    buf[n] = c as u8;
+                    n += 1;
+                } else {
+                    self.copy_left = c - 256 + THRESHOLD;
+                    self.copy_dist = self.decode_position()? + 1;
+                }
+                continue;
+            }
+            let byte = self.window[self.pos.wrapping_sub(self.copy_dist) & (self.window.len() - 1)];
+            self.copy_left -= 1;
+            self.push(byte);
+            buf[n] = byte;
+            n += 1;
+        }
+        Ok(n)
+    }
+}
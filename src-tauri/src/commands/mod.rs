@@ -2,9 +2,21 @@ pub mod archive;
 pub mod catalog;
 pub mod diagnostics;
 pub mod download;
+pub mod github;
 pub mod installed;
+pub mod installer;
+pub mod jobs;
+pub mod lockfile;
 pub mod logging;
+pub mod lzh;
 pub mod niconi_commons;
+pub mod ownership;
+pub mod pathutil;
+pub mod plan;
+pub mod replace;
+pub mod resolver;
+pub mod snapshot;
 pub mod system;
+pub mod transaction;
 pub mod version;
 pub mod zstd;
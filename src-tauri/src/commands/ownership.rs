@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use super::pathutil::path_key;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OverlapPolicy {
+    #[default]
+    Warn,
+    Block,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOwner {
+    pub package_id: String,
+    // インストール記録に含まれている
+    pub recorded: bool,
+    // カタログのバージョン情報に含まれている
+    pub catalog: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOverlap {
+    pub path: String,
+    pub owners: Vec<FileOwner>,
+}
+
+struct RegistryEntry {
+    path: PathBuf,
+    owners: BTreeMap<String, FileOwner>,
+}
+
+/// ファイル → 所有パッケージの対応表
+#[derive(Default)]
+pub struct OwnershipRegistry {
+    entries: BTreeMap<String, RegistryEntry>,
+}
+
+impl OwnershipRegistry {
+    /// インストール記録とカタログのファイル一覧からインストール済みパッケージの所有ファイルを集める
+    pub fn build(config_dir: &Path) -> Self {
+        let mut registry = Self::default();
+        let state = super::installed::load_installed_state(config_dir);
+        for (id, package) in &state.packages {
+            for file in &package.files {
+                registry.insert(Path::new(&file.path), id, true);
+            }
+            for path in super::version::catalog_version_files(id, Some(&package.version)) {
+                registry.insert(&path, id, false);
+            }
+        }
+        registry
+    }
+
+    fn insert(&mut self, path: &Path, package_id: &str, recorded: bool) {
+        let entry = self.entries.entry(path_key(path)).or_insert_with(|| RegistryEntry { path: path.to_path_buf(), owners: BTreeMap::new() });
+        let owner = entry.owners.entry(package_id.to_string()).or_insert_with(|| FileOwner { package_id: package_id.to_string(), recorded: false, catalog: false });
+        if recorded {
+            owner.recorded = true;
+        } else {
+            owner.catalog = true;
+        }
+    }
+
+    /// 指定したパッケージ以外の所有者を返す
+    pub fn foreign_owners(&self, path: &Path, package_id: &str) -> Vec<FileOwner> {
+        self.entries.get(&path_key(path)).map(|e| e.owners.values().filter(|o| o.package_id != package_id).cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn overlaps(&self) -> Vec<FileOverlap> {
+        self.entries
+            .values()
+            .filter(|e| e.owners.len() > 1)
+            .map(|e| FileOverlap {
+                path: e.path.to_string_lossy().into_owned(),
+                owners: e.owners.values().cloned().collect(),
+            })
+            .collect()
+    }
+}
+
+#[tauri::command]
+pub fn list_file_overlaps(app: tauri::AppHandle) -> Result<Vec<FileOverlap>, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(OwnershipRegistry::build(&config_dir).overlaps())
+}
+
+/// インストール前に、他のパッケージが所有するファイルを上書きするかどうかを調べる
+/// paths を省略した場合はカタログに記載されたファイル一覧を使う
+#[tauri::command]
+pub fn check_file_ownership(app: tauri::AppHandle, package_id: String, version: Option<String>, paths: Option<Vec<String>>) -> Result<Vec<FileOverlap>, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let registry = OwnershipRegistry::build(&config_dir);
+    let paths = match paths {
+        Some(paths) => paths.into_iter().map(PathBuf::from).collect(),
+        None => super::version::catalog_version_files(&package_id, version.as_deref()),
+    };
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| {
+            let owners = registry.foreign_owners(&path, &package_id);
+            (!owners.is_empty()).then(|| FileOverlap { path: path.to_string_lossy().into_owned(), owners })
+        })
+        .collect())
+}
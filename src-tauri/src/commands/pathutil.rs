@@ -0,0 +1,21 @@
+use std::path::Path;
+
+/// Windows 形式（C:\ や C:/）か / で始まる絶対パスか
+pub fn is_abs(p: &str) -> bool {
+    let s = p.replace('\\', "/");
+    s.starts_with('/') || (s.len() >= 3 && s.as_bytes()[1] == b':' && (s.as_bytes()[2] == b'/' || s.as_bytes()[2] == b'\\'))
+}
+
+/// パスの比較用キー（Windows と同じく区切り文字と大文字小文字を区別しない）
+pub fn path_key(path: impl AsRef<Path>) -> String {
+    path.as_ref().to_string_lossy().replace('/', "\\").trim_end_matches('\\').to_lowercase()
+}
+
+/// path が root 自身かその配下か
+pub fn is_under(path: &Path, root: &Path) -> bool {
+    is_under_key(&path_key(path), &path_key(root))
+}
+
+pub fn is_under_key(key: &str, root: &str) -> bool {
+    key == root || key.starts_with(&format!("{root}\\"))
+}
@@ -0,0 +1,399 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::Manager;
+use walkdir::WalkDir;
+
+use super::archive::{self, ArchiveFormat, CopyPolicy};
+use super::installer::{self, InstallStep, Installation, MacroContext};
+use super::pathutil::{is_under, path_key};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileChangeKind {
+    Create,
+    Overwrite,
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedFileChange {
+    pub path: String,
+    pub action: FileChangeKind,
+    pub step_index: usize,
+    pub current_hash: Option<String>,
+    pub new_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedExecutable {
+    pub step_index: usize,
+    pub action: &'static str,
+    pub path: String,
+    pub args: Vec<String>,
+    pub elevate: bool,
+    pub available: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedStep {
+    pub index: usize,
+    pub action: &'static str,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub path: Option<String>,
+    pub entries: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallPlan {
+    pub package_id: String,
+    pub source_type: &'static str,
+    pub source: String,
+    pub steps: Vec<PlannedStep>,
+    pub files: Vec<PlannedFileChange>,
+    pub executables: Vec<PlannedExecutable>,
+    pub warnings: Vec<String>,
+}
+
+/// アンインストールの計画（手順による変更に加え、記録済みのファイルの削除を含む）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UninstallPlan {
+    #[serde(flatten)]
+    pub plan: InstallPlan,
+    // インストール後に編集されたため残すファイル
+    pub skipped_modified: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+// 計画上のファイルの中身の出どころ
+#[derive(Debug, Clone)]
+enum Origin {
+    Disk(PathBuf),
+    Archive { archive: PathBuf, kind: ArchiveFormat, entry: String },
+}
+
+// 実際のディスクに手を加えず、インストール手順を追跡するための仮想ファイルシステム
+#[derive(Default)]
+struct Simulation {
+    files: BTreeMap<String, (PathBuf, Origin)>,
+    deleted: HashSet<String>,
+    changes: BTreeMap<String, (PathBuf, FileChangeKind, usize, Origin)>,
+}
+
+impl Simulation {
+    fn exists_on_disk(&self, path: &Path) -> bool {
+        !self.deleted.contains(&path_key(path)) && path.is_file()
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(&path_key(path)) || self.exists_on_disk(path)
+    }
+
+    // コピー元に一致するファイルを (相対パス, 実体パス, 出どころ) で列挙する
+    fn resolve_sources(&self, src: &Path) -> Vec<(PathBuf, PathBuf, Origin)> {
+        let key = path_key(src);
+        if let Some((path, origin)) = self.files.get(&key) {
+            let name = path.file_name().map(PathBuf::from).unwrap_or_default();
+            return vec![(name, path.clone(), origin.clone())];
+        }
+        let prefix = format!("{key}\\");
+        let mut out: Vec<(PathBuf, PathBuf, Origin)> = self
+            .files
+            .iter()
+            .filter(|(k, _)| k.starts_with(&prefix))
+            .filter_map(|(_, (path, origin))| path.strip_prefix(src).ok().map(|rel| (rel.to_path_buf(), path.clone(), origin.clone())))
+            .collect();
+        if src.is_file() && !self.deleted.contains(&key) {
+            let name = src.file_name().map(PathBuf::from).unwrap_or_default();
+            out.push((name, src.to_path_buf(), Origin::Disk(src.to_path_buf())));
+        } else if src.is_dir() {
+            let seen: HashSet<String> = out.iter().map(|(_, p, _)| path_key(p)).collect();
+            for entry in WalkDir::new(src).into_iter().filter_map(Result::ok).filter(|e| e.file_type().is_file()) {
+                let path = entry.path();
+                let entry_key = path_key(path);
+                if seen.contains(&entry_key) || self.deleted.contains(&entry_key) {
+                    continue;
+                }
+                if let Ok(rel) = path.strip_prefix(src) {
+                    out.push((rel.to_path_buf(), path.to_path_buf(), Origin::Disk(path.to_path_buf())));
+                }
+            }
+        }
+        out
+    }
+
+    fn write(&mut self, path: PathBuf, origin: Origin, step_index: usize, tmp_dir: &Path) {
+        let key = path_key(&path);
+        if !is_under(&path, tmp_dir) {
+            let action = if self.changes.get(&key).map(|(_, a, _, _)| *a == FileChangeKind::Create).unwrap_or(!self.exists_on_disk(&path)) {
+                FileChangeKind::Create
+            } else {
+                FileChangeKind::Overwrite
+            };
+            self.changes.insert(key.clone(), (path.clone(), action, step_index, origin.clone()));
+        }
+        self.deleted.remove(&key);
+        self.files.insert(key, (path, origin));
+    }
+
+    fn delete(&mut self, path: &Path, step_index: usize, tmp_dir: &Path) {
+        let key = path_key(path);
+        let prefix = format!("{key}\\");
+        self.files.retain(|k, _| *k != key && !k.starts_with(&prefix));
+        let staged: Vec<String> =
+            self.changes.iter().filter(|(k, (_, a, _, _))| (**k == key || k.starts_with(&prefix)) && *a == FileChangeKind::Create).map(|(k, _)| k.clone()).collect();
+        for k in staged {
+            self.changes.remove(&k);
+        }
+        let mut on_disk = Vec::new();
+        if path.is_file() {
+            on_disk.push(path.to_path_buf());
+        } else if path.is_dir() {
+            on_disk.extend(WalkDir::new(path).into_iter().filter_map(Result::ok).filter(|e| e.file_type().is_file()).map(|e| e.into_path()));
+        }
+        for file in on_disk {
+            let file_key = path_key(&file);
+            if self.deleted.insert(file_key.clone()) && !is_under(&file, tmp_dir) {
+                self.changes.insert(file_key, (file.clone(), FileChangeKind::Delete, step_index, Origin::Disk(file)));
+            }
+        }
+    }
+}
+
+fn locate_download(ctx: &MacroContext, archive_path: Option<&str>) -> Option<PathBuf> {
+    if let Some(p) = archive_path.map(str::trim).filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(p));
+    }
+    // 以前のインストールで取得済みのファイルが一時フォルダに残っていれば流用する
+    let entries: Vec<PathBuf> = std::fs::read_dir(&ctx.tmp_dir).ok()?.filter_map(Result::ok).map(|e| e.path()).filter(|p| p.is_file()).collect();
+    if entries.len() == 1 { entries.into_iter().next() } else { None }
+}
+
+fn empty_plan(package_id: &str, installation: &Installation) -> InstallPlan {
+    InstallPlan {
+        package_id: package_id.to_string(),
+        source_type: installation.source.kind(),
+        source: installation.source.label(),
+        steps: Vec::new(),
+        files: Vec::new(),
+        executables: Vec::new(),
+        warnings: Vec::new(),
+    }
+}
+
+fn simulate_steps(
+    steps: &[InstallStep],
+    installation: &Installation,
+    ctx: &mut MacroContext,
+    sim: &mut Simulation,
+    plan: &mut InstallPlan,
+    archive_path: Option<&str>,
+) -> Result<(), String> {
+    for (index, step) in steps.iter().enumerate() {
+        let mut planned = PlannedStep { index, action: step.action(), from: None, to: None, path: None, entries: None };
+        match step {
+            InstallStep::Download => {
+                let download = locate_download(ctx, archive_path);
+                match &download {
+                    Some(path) => {
+                        sim.files.insert(path_key(path), (path.clone(), Origin::Disk(path.clone())));
+                    }
+                    None => plan.warnings.push(format!("step {}: the download is not available locally; archive contents are unknown", index + 1)),
+                }
+                planned.from = Some(installation.source.label());
+                planned.to = Some(download.as_ref().unwrap_or(&ctx.tmp_dir).to_string_lossy().into_owned());
+                ctx.download_path = download;
+            }
+            InstallStep::Extract { from, to, selection, nested, .. } | InstallStep::ExtractSfx { from, to, selection, nested, .. } => {
+                let from_raw = from.clone().unwrap_or_else(|| ctx.download_path.as_ref().map(|p| p.to_string_lossy().into_owned()).unwrap_or_default());
+                let to_raw = to.clone().unwrap_or_else(|| "{tmp}".to_string());
+                let to_path = installer::ensure_absolute(ctx.expand(&to_raw), &format!("install.{}.to", step.action()))?;
+                planned.to = Some(to_path.to_string_lossy().into_owned());
+                if from_raw.is_empty() {
+                    plan.warnings.push(format!("step {}: nothing to extract because the download is not available", index + 1));
+                    plan.steps.push(planned);
+                    continue;
+                }
+                let from_path = installer::ensure_absolute(ctx.expand(&from_raw), &format!("install.{}.from", step.action()))?;
+                planned.from = Some(from_path.to_string_lossy().into_owned());
+                if !from_path.is_file() {
+                    plan.warnings.push(format!("step {}: archive is not available on disk: {}", index + 1, from_path.display()));
+                    plan.steps.push(planned);
+                    continue;
+                }
+                let kind = match step {
+                    InstallStep::Extract { .. } => ArchiveFormat::sniff(&from_path)?.unwrap_or(ArchiveFormat::Zip),
+                    _ => ArchiveFormat::SevenZipSfx,
+                };
+                let entries = archive::select_archive_entries(archive::archive_file_names(&from_path, kind)?, selection)?;
+                planned.entries = Some(entries.len());
+                for (name, rel) in entries {
+                    sim.write(to_path.join(rel), Origin::Archive { archive: from_path.clone(), kind, entry: name }, index, &ctx.tmp_dir);
+                }
+                if let Some(nested) = nested {
+                    plan.warnings.push(format!("step {}: nested archives matching {} are extracted at install time and are not listed", index + 1, nested.pattern));
+                }
+            }
+            InstallStep::Copy { from, to, options } => {
+                let from_path = installer::ensure_absolute(ctx.expand(from), "install.copy.from")?;
+                let to_path = installer::ensure_absolute(ctx.expand(to), "install.copy.to")?;
+                planned.from = Some(from_path.to_string_lossy().into_owned());
+                planned.to = Some(to_path.to_string_lossy().into_owned());
+                let included = archive::copy_filter(options)?;
+                let mut sources = sim.resolve_sources(&from_path);
+                sources.retain(|(rel, _, _)| included(rel));
+                planned.entries = Some(sources.len());
+                if sources.is_empty() {
+                    plan.warnings.push(format!("step {}: copy would match 0 files (from={})", index + 1, from_path.display()));
+                }
+                for (rel, _, origin) in sources {
+                    let target = to_path.join(rel);
+                    if sim.exists(&target) {
+                        match options.policy {
+                            CopyPolicy::SkipExisting => continue,
+                            CopyPolicy::FailOnConflict => plan.warnings.push(format!("step {}: copy would fail because {} already exists", index + 1, target.display())),
+                            // OnlyIfNewer は実行時の更新日時で決まるので上書きとして扱う
+                            _ => {}
+                        }
+                    }
+                    sim.write(target, origin, index, &ctx.tmp_dir);
+                }
+            }
+            InstallStep::Delete { path } => {
+                let target = installer::ensure_absolute(ctx.expand(path), "install.delete.path")?;
+                planned.path = Some(target.to_string_lossy().into_owned());
+                sim.delete(&target, index, &ctx.tmp_dir);
+            }
+            InstallStep::Run { path, args, elevate } => {
+                let exe = installer::ensure_absolute(ctx.expand(path), "install.run.path")?;
+                planned.path = Some(exe.to_string_lossy().into_owned());
+                plan.executables.push(PlannedExecutable {
+                    step_index: index,
+                    action: step.action(),
+                    path: exe.to_string_lossy().into_owned(),
+                    args: args.iter().map(|a| ctx.expand(a)).collect(),
+                    elevate: *elevate,
+                    available: sim.exists(&exe),
+                });
+            }
+            InstallStep::RunAuoSetup { path } => {
+                let exe = installer::ensure_absolute(ctx.expand(path), "install.runAuoSetup.path")?;
+                planned.path = Some(exe.to_string_lossy().into_owned());
+                plan.executables.push(PlannedExecutable {
+                    step_index: index,
+                    action: step.action(),
+                    path: exe.to_string_lossy().into_owned(),
+                    args: Vec::new(),
+                    elevate: false,
+                    available: sim.exists(&exe),
+                });
+            }
+        }
+        plan.steps.push(planned);
+    }
+    Ok(())
+}
+
+// 計画上の変更にハッシュを付けて plan.files にまとめる
+fn finish_plan(sim: Simulation, plan: &mut InstallPlan) {
+    let mut wanted: HashMap<(PathBuf, ArchiveFormat), HashSet<String>> = HashMap::new();
+    for (_, _, _, origin) in sim.changes.values() {
+        if let Origin::Archive { archive, kind, entry } = origin {
+            wanted.entry((archive.clone(), *kind)).or_default().insert(entry.clone());
+        }
+    }
+    let mut entry_hashes: HashMap<(PathBuf, String), String> = HashMap::new();
+    for ((archive_path, kind), names) in wanted {
+        match archive::hash_archive_entries(&archive_path, kind, &names) {
+            Ok(hashes) => entry_hashes.extend(hashes.into_iter().map(|(name, hex)| ((archive_path.clone(), name), hex))),
+            Err(e) => plan.warnings.push(format!("failed to hash archive entries in {}: {}", archive_path.display(), e)),
+        }
+    }
+
+    for (path, action, step_index, origin) in sim.changes.into_values() {
+        let current_hash = if action == FileChangeKind::Create { None } else { super::version::xxh3_128_hex(&path).ok() };
+        let new_hash = match (action, origin) {
+            (FileChangeKind::Delete, _) => None,
+            (_, Origin::Disk(src)) => super::version::xxh3_128_hex(&src).ok(),
+            (_, Origin::Archive { archive, entry, .. }) => entry_hashes.get(&(archive, entry)).cloned(),
+        };
+        plan.files.push(PlannedFileChange {
+            path: path.to_string_lossy().into_owned(),
+            action,
+            step_index,
+            current_hash,
+            new_hash,
+        });
+    }
+    plan.files.sort_by(|a, b| a.step_index.cmp(&b.step_index).then_with(|| a.path.cmp(&b.path)));
+}
+
+fn build_plan(package_id: &str, version: Option<&str>, archive_path: Option<&str>) -> Result<InstallPlan, String> {
+    let installation = installer::catalog_package(package_id)?.installation;
+    let mut ctx = MacroContext::for_package(package_id, version);
+    let mut sim = Simulation::default();
+    let mut plan = empty_plan(package_id, &installation);
+    simulate_steps(&installation.install_steps, &installation, &mut ctx, &mut sim, &mut plan, archive_path)?;
+    finish_plan(sim, &mut plan);
+    Ok(plan)
+}
+
+fn build_uninstall_plan(config_dir: &Path, package_id: &str, force: bool) -> Result<UninstallPlan, String> {
+    let installation = installer::catalog_package(package_id)?.installation;
+    let recorded = super::installed::load_installed_state(config_dir).packages.remove(package_id);
+    let version = recorded.as_ref().map(|p| p.version.clone());
+    let mut ctx = MacroContext::for_package(package_id, version.as_deref());
+    let mut sim = Simulation::default();
+    let mut plan = UninstallPlan {
+        plan: empty_plan(package_id, &installation),
+        skipped_modified: Vec::new(),
+        missing: Vec::new(),
+    };
+    simulate_steps(&installation.uninstall_steps, &installation, &mut ctx, &mut sim, &mut plan.plan, None)?;
+
+    // 手順の後に uninstall_package が記録済みのファイルを削除する（step_index は手順数）
+    let cleanup_index = installation.uninstall_steps.len();
+    for file in recorded.map(|p| p.files).unwrap_or_default() {
+        let path = PathBuf::from(&file.path);
+        if sim.deleted.contains(&path_key(&path)) {
+            continue;
+        }
+        if !path.is_file() {
+            plan.missing.push(file.path);
+            continue;
+        }
+        if !force && super::version::xxh3_128_hex(&path).map(|hash| hash != file.hash).unwrap_or(true) {
+            plan.skipped_modified.push(file.path);
+            continue;
+        }
+        sim.delete(&path, cleanup_index, &ctx.tmp_dir);
+    }
+    finish_plan(sim, &mut plan.plan);
+    Ok(plan)
+}
+
+#[tauri::command]
+pub async fn plan_install(package_id: String, version: Option<String>, archive_path: Option<String>) -> Result<InstallPlan, String> {
+    let package_id = package_id.trim().to_string();
+    if package_id.is_empty() {
+        return Err(crate::paths::common_message_current("backend.errors.packageIdEmpty"));
+    }
+    tauri::async_runtime::spawn_blocking(move || build_plan(&package_id, version.as_deref(), archive_path.as_deref())).await.map_err(|e| format!("task join error: {e}"))?
+}
+
+#[tauri::command]
+pub async fn plan_uninstall(app: tauri::AppHandle, package_id: String, force: Option<bool>) -> Result<UninstallPlan, String> {
+    let package_id = package_id.trim().to_string();
+    if package_id.is_empty() {
+        return Err(crate::paths::common_message_current("backend.errors.packageIdEmpty"));
+    }
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || build_uninstall_plan(&config_dir, &package_id, force.unwrap_or(false))).await.map_err(|e| format!("task join error: {e}"))?
+}
@@ -0,0 +1,231 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+/// 使用中のファイルを置き換えられず、AviUtl2 の終了を待つ必要があることを表すエラー（"FILE_LOCKED: <パス>"）
+pub const FILE_LOCKED: &str = "FILE_LOCKED";
+
+// 次回起動時に削除するファイルの一覧（起動時に delete_pending_files で場所が決まる）
+static PENDING_DELETES: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+const WAIT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 置き換え先のファイルが他のプロセス（主に起動中の AviUtl2）に使われている場合の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LockedFilePolicy {
+    // 使用中のファイルを別名に変えてから新しいファイルを置き、古いファイルは次回起動時に削除する
+    #[default]
+    RenameAside,
+    // 手順ごと AviUtl2 の終了を待ってからやり直す
+    WaitForExit,
+    Fail,
+}
+
+/// 置き換えを後回しにしたファイル
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeferredFile {
+    pub path: String,
+    // 別名にした古いファイル（次回起動時に削除する）。None は AviUtl2 の終了を待ってから置き換えたもの
+    pub renamed_to: Option<String>,
+}
+
+/// 共有違反・ロック違反で開けなかったかどうか（ERROR_SHARING_VIOLATION / ERROR_LOCK_VIOLATION）
+pub(crate) fn is_locked_error(e: &io::Error) -> bool {
+    matches!(e.raw_os_error(), Some(32 | 33))
+}
+
+/// 既存のファイルが書き込めない状態かを書き込まずに確かめる
+pub(crate) fn is_locked(path: &Path) -> bool {
+    path.is_file() && fs::OpenOptions::new().write(true).open(path).err().is_some_and(|e| is_locked_error(&e))
+}
+
+pub(crate) fn locked_error(path: &Path) -> io::Error {
+    io::Error::other(format!("{FILE_LOCKED}: {}", path.display()))
+}
+
+/// FILE_LOCKED のエラーから対象のパスを取り出す
+pub(crate) fn locked_path(message: &str) -> Option<&str> {
+    message.split_once(&format!("{FILE_LOCKED}: ")).map(|(_, path)| path)
+}
+
+fn aside_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let stamp = chrono::Local::now().format("%Y%m%d%H%M%S");
+    (0..).map(|i| path.with_file_name(if i == 0 { format!("{name}.{stamp}.old") } else { format!("{name}.{stamp}-{i}.old") })).find(|p| !p.exists()).unwrap()
+}
+
+fn read_pending(list: &Path) -> Vec<PathBuf> {
+    fs::read_to_string(list).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+fn write_pending(list: &Path, paths: &[PathBuf]) -> io::Result<()> {
+    if paths.is_empty() {
+        return match fs::remove_file(list) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    fs::write(list, serde_json::to_string_pretty(paths)?)
+}
+
+fn schedule_delete(path: &Path) -> io::Result<()> {
+    let guard = PENDING_DELETES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Some(list) = guard.as_ref() else {
+        tracing::warn!("Pending delete list is not available; {} is left in place", path.display());
+        return Ok(());
+    };
+    let mut paths = read_pending(list);
+    paths.push(path.to_path_buf());
+    write_pending(list, &paths)
+}
+
+/// from を dest にコピーする。dest が使用中なら policy に従い、後回しにした場合はその内容を返す
+pub(crate) fn replace_file(from: &Path, dest: &Path, policy: LockedFilePolicy) -> io::Result<Option<DeferredFile>> {
+    let e = match fs::copy(from, dest) {
+        Ok(_) => return Ok(None),
+        Err(e) if is_locked_error(&e) => e,
+        Err(e) => return Err(e),
+    };
+    match policy {
+        LockedFilePolicy::Fail => Err(e),
+        LockedFilePolicy::WaitForExit => Err(locked_error(dest)),
+        LockedFilePolicy::RenameAside => {
+            // 読み込み中の DLL でも名前の変更はできる
+            let aside = aside_path(dest);
+            fs::rename(dest, &aside)?;
+            if let Err(e) = fs::copy(from, dest) {
+                let _ = fs::rename(&aside, dest);
+                return Err(e);
+            }
+            schedule_delete(&aside)?;
+            tracing::info!("{} is in use; renamed it to {} and replaced it", dest.display(), aside.display());
+            Ok(Some(DeferredFile {
+                path: dest.to_string_lossy().into_owned(),
+                renamed_to: Some(aside.to_string_lossy().into_owned()),
+            }))
+        }
+    }
+}
+
+/// AviUtl2 が終了するまで待つ（中断されたら CANCELLED）
+pub(crate) async fn wait_for_aviutl_exit(cancel: &AtomicBool) -> Result<(), String> {
+    while super::system::is_aviutl_running() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(super::download::CANCELLED.to_string());
+        }
+        tokio::time::sleep(WAIT_INTERVAL).await;
+    }
+    Ok(())
+}
+
+// 起動時に前回別名にしたファイルを削除する（まだ使用中のものは次回に回す）
+pub fn delete_pending_files(app: &tauri::AppHandle) {
+    let Ok(config_dir) = app.path().app_config_dir() else {
+        return;
+    };
+    let list = config_dir.join("pending-deletes.json");
+    delete_listed_files(&list);
+    *PENDING_DELETES.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(list);
+}
+
+/// 一覧にあるファイルを削除し、削除できなかったものだけを一覧に残す
+fn delete_listed_files(list: &Path) {
+    let remaining: Vec<PathBuf> = read_pending(list)
+        .into_iter()
+        .filter(|path| match fs::remove_file(path) {
+            Ok(()) => false,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+            Err(e) => {
+                tracing::warn!("Failed to delete {} (will retry on next start): {}", path.display(), e);
+                true
+            }
+        })
+        .collect();
+    if let Err(e) = write_pending(list, &remaining) {
+        tracing::error!("Failed to update pending delete list: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aviutl2-replace-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn aside_path_adds_a_timestamp_and_avoids_existing_files() {
+        let dir = temp_dir("aside");
+        let dll = dir.join("plugin.aux2");
+        let pattern = regex::Regex::new(r"^plugin\.aux2\.\d{14}(-1)?\.old$").unwrap();
+
+        let first = aside_path(&dll);
+        assert_eq!(first.parent(), Some(dir.as_path()));
+        assert!(pattern.is_match(&first.file_name().unwrap().to_string_lossy()), "{}", first.display());
+
+        // 同じ時刻に別名にしたファイルが残っていれば連番を付ける
+        fs::write(&first, b"old").unwrap();
+        let second = aside_path(&dll);
+        assert_ne!(second, first);
+        assert!(pattern.is_match(&second.file_name().unwrap().to_string_lossy()), "{}", second.display());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn pending_list_round_trips_and_is_removed_when_empty() {
+        let dir = temp_dir("pending");
+        let list = dir.join("pending-deletes.json");
+        assert!(read_pending(&list).is_empty());
+
+        let paths = vec![
+            dir.join("a.aux2.20240101000000.old"),
+            dir.join("プラグイン").join("b.dll.20240101000000-1.old"),
+        ];
+        write_pending(&list, &paths).unwrap();
+        assert_eq!(read_pending(&list), paths);
+
+        write_pending(&list, &[]).unwrap();
+        assert!(!list.exists());
+        write_pending(&list, &[]).unwrap();
+
+        // 壊れた一覧は空として扱う
+        fs::write(&list, "not json").unwrap();
+        assert!(read_pending(&list).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn delete_listed_files_keeps_only_paths_that_could_not_be_deleted() {
+        let dir = temp_dir("delete");
+        let list = dir.join("pending-deletes.json");
+        let old = dir.join("plugin.aux2.20240101000000.old");
+        let missing = dir.join("gone.old");
+        // フォルダは remove_file で消せないので、使用中のファイルの代わりにする
+        let busy = dir.join("busy.old");
+        fs::write(&old, b"old").unwrap();
+        fs::create_dir_all(&busy).unwrap();
+        write_pending(&list, &[old.clone(), missing, busy.clone()]).unwrap();
+
+        delete_listed_files(&list);
+        assert!(!old.exists());
+        assert_eq!(read_pending(&list), std::slice::from_ref(&busy));
+
+        fs::remove_dir(&busy).unwrap();
+        delete_listed_files(&list);
+        assert!(!list.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
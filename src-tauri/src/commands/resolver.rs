@@ -0,0 +1,296 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde::Serialize;
+
+use super::installer::{self, CatalogInstallPackage, RelationSet};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedPackage {
+    pub id: String,
+    pub requested: bool,
+    pub required_by: Vec<String>,
+    pub installed_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnavailableDependency {
+    pub id: String,
+    pub required_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageConflict {
+    pub package_id: String,
+    pub conflicts_with: String,
+    pub installed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposedRemoval {
+    pub package_id: String,
+    pub replaced_by: String,
+    pub installed_version: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedInstallPlan {
+    pub ok: bool,
+    pub order: Vec<ResolvedPackage>,
+    pub unknown_requested: Vec<String>,
+    pub unavailable: Vec<UnavailableDependency>,
+    pub conflicts: Vec<PackageConflict>,
+    pub cycles: Vec<Vec<String>>,
+    pub removals: Vec<ProposedRemoval>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Visiting,
+    Done,
+}
+
+struct Resolver<'a> {
+    catalog: &'a HashMap<String, CatalogInstallPackage>,
+    installed: &'a HashMap<String, String>,
+    requested: HashSet<String>,
+    marks: HashMap<String, Mark>,
+    stack: Vec<String>,
+    required_by: HashMap<String, BTreeSet<String>>,
+    unavailable: HashMap<String, BTreeSet<String>>,
+    cycles: Vec<Vec<String>>,
+    order: Vec<String>,
+}
+
+fn relations<'a>(catalog: &'a HashMap<String, CatalogInstallPackage>, id: &str) -> Option<&'a RelationSet> {
+    catalog.get(id).and_then(|p| p.relations.as_ref())
+}
+
+impl Resolver<'_> {
+    // 依存先を先に積む深さ優先探索（帰りがけ順がそのままインストール順になる）
+    fn visit(&mut self, id: &str) {
+        match self.marks.get(id) {
+            Some(Mark::Done) => return,
+            Some(Mark::Visiting) => {
+                let start = self.stack.iter().position(|s| s == id).unwrap_or(0);
+                let mut cycle = self.stack[start..].to_vec();
+                cycle.push(id.to_string());
+                self.cycles.push(cycle);
+                return;
+            }
+            None => {}
+        }
+        self.marks.insert(id.to_string(), Mark::Visiting);
+        self.stack.push(id.to_string());
+        let requires = relations(self.catalog, id).map(|r| r.requires.clone()).unwrap_or_default();
+        for dep in requires {
+            self.required_by.entry(dep.clone()).or_default().insert(id.to_string());
+            if self.requested.contains(&dep) {
+                self.visit(&dep);
+                continue;
+            }
+            if self.installed.contains_key(&dep) {
+                continue;
+            }
+            if !self.catalog.contains_key(&dep) {
+                self.unavailable.entry(dep).or_default().insert(id.to_string());
+                continue;
+            }
+            self.visit(&dep);
+        }
+        self.stack.pop();
+        self.marks.insert(id.to_string(), Mark::Done);
+        self.order.push(id.to_string());
+    }
+}
+
+pub fn resolve(requested: &[String], installed: &HashMap<String, String>, catalog: &HashMap<String, CatalogInstallPackage>) -> ResolvedInstallPlan {
+    let mut plan = ResolvedInstallPlan::default();
+    let mut roots = Vec::new();
+    for id in requested {
+        let id = id.trim();
+        if id.is_empty() || roots.iter().any(|r| r == id) {
+            continue;
+        }
+        if catalog.contains_key(id) {
+            roots.push(id.to_string());
+        } else {
+            plan.unknown_requested.push(id.to_string());
+        }
+    }
+
+    let mut resolver = Resolver {
+        catalog,
+        installed,
+        requested: roots.iter().cloned().collect(),
+        marks: HashMap::new(),
+        stack: Vec::new(),
+        required_by: HashMap::new(),
+        unavailable: HashMap::new(),
+        cycles: Vec::new(),
+        order: Vec::new(),
+    };
+    for id in &roots {
+        resolver.visit(id);
+    }
+
+    let planned: HashSet<&str> = resolver.order.iter().map(String::as_str).collect();
+    let mut replaced: HashSet<(String, String)> = HashSet::new();
+    for id in &resolver.order {
+        let Some(rel) = relations(catalog, id) else {
+            continue;
+        };
+        for old in &rel.replaces {
+            replaced.insert((id.clone(), old.clone()));
+            if let Some(version) = installed.get(old)
+                && !planned.contains(old.as_str())
+            {
+                plan.removals.push(ProposedRemoval {
+                    package_id: old.clone(),
+                    replaced_by: id.clone(),
+                    installed_version: version.clone(),
+                });
+            }
+        }
+    }
+
+    // conflicts はどちらか一方に書かれていれば成立する
+    let mut seen_conflicts: HashSet<(String, String)> = HashSet::new();
+    let others: BTreeSet<&str> = installed.keys().map(String::as_str).chain(planned.iter().copied()).collect();
+    for id in &resolver.order {
+        for other in &others {
+            if *other == id.as_str() {
+                continue;
+            }
+            let declared =
+                relations(catalog, id).is_some_and(|r| r.conflicts.iter().any(|c| c == other)) || relations(catalog, other).is_some_and(|r| r.conflicts.iter().any(|c| c == id));
+            if !declared || replaced.contains(&(id.clone(), other.to_string())) || replaced.contains(&(other.to_string(), id.clone())) {
+                continue;
+            }
+            let key = if id.as_str() < *other { (id.clone(), other.to_string()) } else { (other.to_string(), id.clone()) };
+            if !seen_conflicts.insert(key) {
+                continue;
+            }
+            let in_plan = planned.contains(other);
+            plan.conflicts.push(PackageConflict {
+                package_id: id.clone(),
+                conflicts_with: other.to_string(),
+                installed: !in_plan && installed.contains_key(*other),
+            });
+        }
+    }
+
+    plan.order = resolver
+        .order
+        .iter()
+        .map(|id| ResolvedPackage {
+            id: id.clone(),
+            requested: resolver.requested.contains(id),
+            required_by: resolver.required_by.get(id).map(|s| s.iter().cloned().collect()).unwrap_or_default(),
+            installed_version: installed.get(id).cloned(),
+        })
+        .collect();
+    let mut unavailable: Vec<UnavailableDependency> =
+        resolver.unavailable.into_iter().map(|(id, by)| UnavailableDependency { id, required_by: by.into_iter().collect() }).collect();
+    unavailable.sort_by(|a, b| a.id.cmp(&b.id));
+    plan.unavailable = unavailable;
+    plan.cycles = resolver.cycles;
+    plan.ok = plan.unknown_requested.is_empty() && plan.unavailable.is_empty() && plan.conflicts.is_empty() && plan.cycles.is_empty();
+    plan
+}
+
+#[tauri::command]
+pub fn resolve_install_plan(app: tauri::AppHandle, package_ids: Vec<String>, installed: Option<HashMap<String, String>>) -> Result<ResolvedInstallPlan, String> {
+    let installed = installed.unwrap_or_else(|| super::installed::read_installed_map(&app));
+    let catalog = installer::catalog_packages()?;
+    Ok(resolve(&package_ids, &installed, &catalog))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog(entries: &[(&str, serde_json::Value)]) -> HashMap<String, CatalogInstallPackage> {
+        entries
+            .iter()
+            .map(|(id, relations)| {
+                let package = serde_json::json!({
+                    "relations": relations,
+                    "installation": { "source": { "type": "directUrl", "url": "https://example.com/a.zip" }, "installSteps": [], "uninstallSteps": [] },
+                });
+                (id.to_string(), serde_json::from_value(package).unwrap())
+            })
+            .collect()
+    }
+
+    fn ids(plan: &ResolvedInstallPlan) -> Vec<&str> {
+        plan.order.iter().map(|p| p.id.as_str()).collect()
+    }
+
+    #[test]
+    fn orders_dependencies_before_dependents() {
+        let catalog = catalog(&[
+            ("app", serde_json::json!({ "requires": ["lib", "missing"] })),
+            ("lib", serde_json::json!({ "requires": ["base"] })),
+            ("base", serde_json::json!({})),
+        ]);
+        let installed = HashMap::from([("base".to_string(), "1.0".to_string())]);
+        let plan = resolve(&["app".to_string(), "unknown".to_string()], &installed, &catalog);
+        assert_eq!(ids(&plan), ["lib", "app"]);
+        assert!(!plan.order[0].requested);
+        assert_eq!(plan.order[0].required_by, ["app"]);
+        assert_eq!(plan.unknown_requested, ["unknown"]);
+        assert_eq!(plan.unavailable.len(), 1);
+        assert_eq!(plan.unavailable[0].id, "missing");
+        assert!(!plan.ok);
+    }
+
+    #[test]
+    fn reports_dependency_cycles() {
+        let catalog = catalog(&[
+            ("a", serde_json::json!({ "requires": ["b"] })),
+            ("b", serde_json::json!({ "requires": ["c"] })),
+            ("c", serde_json::json!({ "requires": ["a"] })),
+        ]);
+        let plan = resolve(&["a".to_string()], &HashMap::new(), &catalog);
+        assert_eq!(plan.cycles, [vec!["a", "b", "c", "a"]]);
+        assert_eq!(ids(&plan), ["c", "b", "a"]);
+        assert!(!plan.ok);
+    }
+
+    #[test]
+    fn reports_conflicts_declared_on_either_side() {
+        let catalog = catalog(&[
+            ("new", serde_json::json!({ "conflicts": ["old"] })),
+            ("old", serde_json::json!({})),
+            ("other", serde_json::json!({})),
+            ("peer", serde_json::json!({ "conflicts": ["other"] })),
+        ]);
+        let installed = HashMap::from([("old".to_string(), "1.0".to_string())]);
+        let plan = resolve(&["new".to_string(), "other".to_string(), "peer".to_string()], &installed, &catalog);
+        let mut conflicts: Vec<(&str, &str, bool)> = plan.conflicts.iter().map(|c| (c.package_id.as_str(), c.conflicts_with.as_str(), c.installed)).collect();
+        conflicts.sort();
+        assert_eq!(conflicts, [("new", "old", true), ("other", "peer", false)]);
+        assert!(!plan.ok);
+    }
+
+    #[test]
+    fn replaced_packages_are_proposed_for_removal_instead_of_conflicting() {
+        let catalog = catalog(&[
+            ("next", serde_json::json!({ "replaces": ["legacy"], "conflicts": ["legacy"] })),
+            ("legacy", serde_json::json!({})),
+        ]);
+        let installed = HashMap::from([("legacy".to_string(), "0.9".to_string())]);
+        let plan = resolve(&["next".to_string()], &installed, &catalog);
+        assert!(plan.conflicts.is_empty());
+        assert_eq!(plan.removals.len(), 1);
+        assert_eq!(plan.removals[0].package_id, "legacy");
+        assert_eq!(plan.removals[0].replaced_by, "next");
+        assert_eq!(plan.removals[0].installed_version, "0.9");
+        assert!(plan.ok);
+    }
+}
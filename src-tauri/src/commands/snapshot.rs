@@ -0,0 +1,521 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use zip::write::SimpleFileOptions;
+
+use super::pathutil::{is_under, path_key};
+use crate::paths::AppDirs;
+
+const SNAPSHOT_DIR: &str = "snapshots";
+const SNAPSHOT_EXT: &str = "zip";
+const META_ENTRY: &str = "snapshot.json";
+const DATA_PREFIX: &str = "data/";
+const ROOT_PREFIX: &str = "root/";
+const STATE_PREFIX: &str = "state/";
+// 設定フォルダから一緒に保存・復元するインストール状態（installed.json は復元時に作り直される）
+const STATE_FILE: &str = "installed-state.json";
+// 固定バージョンなどの設定（AviUtl2 のフォルダは復元しない）
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "version", content = "data")]
+enum SnapshotMetaRoot {
+    #[serde(rename = "1")]
+    V1(SnapshotMeta),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotMeta {
+    created_at: String,
+    label: Option<String>,
+    include_root: bool,
+    aviutl2_root: String,
+    aviutl2_data: String,
+    file_count: usize,
+    total_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub path: String,
+    pub created_at: String,
+    pub label: Option<String>,
+    pub include_root: bool,
+    pub file_count: usize,
+    // 展開後の合計サイズ
+    pub total_bytes: u64,
+    // アーカイブ自体のサイズ
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotRestoreReport {
+    pub id: String,
+    pub restored: usize,
+    pub removed: usize,
+    pub installed_packages: usize,
+    // 復元前の状態を保存した自動スナップショット
+    pub safety_snapshot: Option<String>,
+    pub errors: Vec<String>,
+}
+
+fn snapshot_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app.path().app_config_dir().map_err(|e| e.to_string())?.join(SNAPSHOT_DIR))
+}
+
+fn snapshot_path(dir: &Path, id: &str) -> Result<PathBuf, String> {
+    // id はファイル名としてのみ受け付ける
+    if id.is_empty() || id.contains(['/', '\\', ':']) || id.starts_with('.') {
+        return Err(format!("invalid snapshot id: {id}"));
+    }
+    Ok(dir.join(format!("{id}.{SNAPSHOT_EXT}")))
+}
+
+fn entry_name(prefix: &str, relative: &Path) -> String {
+    format!("{prefix}{}", relative.to_string_lossy().replace('\\', "/"))
+}
+
+// base 配下のファイルを列挙する（exclude 配下は含めない）。読めないフォルダがあれば失敗にする
+fn collect_files(base: &Path, exclude: Option<&Path>) -> Result<Vec<PathBuf>, String> {
+    if !base.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(base).into_iter().filter_entry(|e| exclude.is_none_or(|ex| !is_under(e.path(), ex))) {
+        let entry = entry.map_err(|e| format!("read {} error: {}", base.display(), e))?;
+        if entry.file_type().is_file() {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+fn add_tree<W: Write + io::Seek>(zip: &mut zip::ZipWriter<W>, base: &Path, prefix: &str, exclude: Option<&Path>, meta: &mut SnapshotMeta) -> Result<(), String> {
+    for path in collect_files(base, exclude)? {
+        let Ok(relative) = path.strip_prefix(base) else {
+            continue;
+        };
+        // 読めないファイルを抜かしたスナップショットは、復元時にそのファイルを消してしまうので作らない
+        let mut file = fs::File::open(&path).map_err(|e| format!("read {} error: {}", path.display(), e))?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Zstd).large_file(size >= u32::MAX as u64);
+        zip.start_file(entry_name(prefix, relative), options).map_err(|e| format!("zip write error: {}", e))?;
+        io::copy(&mut file, zip).map_err(|e| format!("zip write {} error: {}", path.display(), e))?;
+        meta.file_count += 1;
+        meta.total_bytes += size;
+    }
+    Ok(())
+}
+
+fn read_meta<R: Read + io::Seek>(archive: &mut zip::ZipArchive<R>) -> Result<SnapshotMeta, String> {
+    let mut text = String::new();
+    archive.by_name(META_ENTRY).map_err(|e| format!("{META_ENTRY} not found: {e}"))?.read_to_string(&mut text).map_err(|e| format!("read {META_ENTRY} error: {e}"))?;
+    let SnapshotMetaRoot::V1(meta) = serde_json::from_str(&text).map_err(|e| format!("parse {META_ENTRY} error: {e}"))?;
+    Ok(meta)
+}
+
+fn snapshot_info(path: &Path) -> Result<SnapshotInfo, String> {
+    let file = fs::File::open(path).map_err(|e| format!("open {} error: {}", path.display(), e))?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut archive = zip::ZipArchive::new(BufReader::new(file)).map_err(|e| format!("open {} error: {}", path.display(), e))?;
+    let meta = read_meta(&mut archive)?;
+    Ok(SnapshotInfo {
+        id: path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default(),
+        path: path.to_string_lossy().into_owned(),
+        created_at: meta.created_at,
+        label: meta.label,
+        include_root: meta.include_root,
+        file_count: meta.file_count,
+        total_bytes: meta.total_bytes,
+        size,
+    })
+}
+
+fn list_snapshot_infos(dir: &Path) -> Vec<SnapshotInfo> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut infos: Vec<SnapshotInfo> = read_dir
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == SNAPSHOT_EXT))
+        .filter_map(|p| match snapshot_info(&p) {
+            Ok(info) => Some(info),
+            Err(e) => {
+                tracing::warn!("Ignoring broken snapshot {}: {}", p.display(), e);
+                None
+            }
+        })
+        .collect();
+    // 新しい順
+    infos.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    infos
+}
+
+fn write_snapshot(config_dir: &Path, dirs: &AppDirs, include_root: bool, label: Option<String>) -> Result<SnapshotInfo, String> {
+    if dirs.aviutl2_data.as_os_str().is_empty() {
+        return Err(crate::paths::common_message_current("backend.errors.aviutlRootNotConfigured"));
+    }
+    let dir = config_dir.join(SNAPSHOT_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("create dir error: {}", e))?;
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let mut id = format!("snapshot-{stamp}");
+    let mut seq = 1;
+    while dir.join(format!("{id}.{SNAPSHOT_EXT}")).exists() {
+        seq += 1;
+        id = format!("snapshot-{stamp}-{seq}");
+    }
+    let final_path = dir.join(format!("{id}.{SNAPSHOT_EXT}"));
+    let tmp_path = dir.join(format!("{id}.{SNAPSHOT_EXT}.tmp"));
+
+    let mut meta = SnapshotMeta {
+        created_at: chrono::Local::now().to_rfc3339(),
+        label: label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty()),
+        include_root,
+        aviutl2_root: dirs.aviutl2_root.to_string_lossy().into_owned(),
+        aviutl2_data: dirs.aviutl2_data.to_string_lossy().into_owned(),
+        file_count: 0,
+        total_bytes: 0,
+    };
+    let result = (|| {
+        let file = fs::File::create(&tmp_path).map_err(|e| format!("create {} error: {}", tmp_path.display(), e))?;
+        let mut zip = zip::ZipWriter::new(BufWriter::new(file));
+        add_tree(&mut zip, &dirs.aviutl2_data, DATA_PREFIX, None, &mut meta)?;
+        if include_root {
+            // ポータブル版では data がルート配下にあるので二重に保存しない
+            add_tree(&mut zip, &dirs.aviutl2_root, ROOT_PREFIX, Some(&dirs.aviutl2_data), &mut meta)?;
+        }
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Zstd);
+        for name in [STATE_FILE, SETTINGS_FILE] {
+            if let Ok(bytes) = fs::read(config_dir.join(name)) {
+                zip.start_file(format!("{STATE_PREFIX}{name}"), options).map_err(|e| format!("zip write error: {}", e))?;
+                zip.write_all(&bytes).map_err(|e| format!("zip write error: {}", e))?;
+            }
+        }
+        zip.start_file(META_ENTRY, options).map_err(|e| format!("zip write error: {}", e))?;
+        let json = serde_json::to_vec_pretty(&SnapshotMetaRoot::V1(meta.clone())).map_err(|e| format!("serialize snapshot meta error: {}", e))?;
+        zip.write_all(&json).map_err(|e| format!("zip write error: {}", e))?;
+        let mut writer = zip.finish().map_err(|e| format!("zip finish error: {}", e))?;
+        writer.flush().map_err(|e| format!("zip flush error: {}", e))?;
+        drop(writer);
+        fs::rename(&tmp_path, &final_path).map_err(|e| format!("rename {} error: {}", final_path.display(), e))
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    tracing::info!("Created snapshot {} ({} files, {} bytes)", id, meta.file_count, meta.total_bytes);
+    snapshot_info(&final_path)
+}
+
+// スナップショット時点に無かったファイルを削除する
+fn remove_extra_files(base: &Path, exclude: Option<&Path>, keep: &HashSet<String>, report: &mut SnapshotRestoreReport) {
+    let files = match collect_files(base, exclude) {
+        Ok(files) => files,
+        Err(e) => {
+            report.errors.push(e);
+            return;
+        }
+    };
+    for path in files {
+        if keep.contains(&path_key(&path)) {
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => report.removed += 1,
+            Err(e) => report.errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+}
+
+fn restore_snapshot_impl(config_dir: &Path, dirs: &AppDirs, id: &str) -> Result<SnapshotRestoreReport, String> {
+    let path = snapshot_path(&config_dir.join(SNAPSHOT_DIR), id)?;
+    let file = fs::File::open(&path).map_err(|e| format!("open {} error: {}", path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file)).map_err(|e| format!("open {} error: {}", path.display(), e))?;
+    let meta = read_meta(&mut archive)?;
+    // 別の AviUtl2 のフォルダで作ったスナップショットを今のフォルダに上書きしない
+    let same_dirs = path_key(&meta.aviutl2_data) == path_key(&dirs.aviutl2_data) && (!meta.include_root || path_key(&meta.aviutl2_root) == path_key(&dirs.aviutl2_root));
+    if !same_dirs {
+        return Err(format!(
+            "snapshot {id} was created for a different AviUtl2 folder (data={}, root={}); current data={}, root={}",
+            meta.aviutl2_data,
+            meta.aviutl2_root,
+            dirs.aviutl2_data.display(),
+            dirs.aviutl2_root.display()
+        ));
+    }
+    let mut report = SnapshotRestoreReport { id: id.to_string(), ..Default::default() };
+
+    // 展開先を先に決めておき、スナップショットに含まれないファイルの削除に使う
+    let mut targets = Vec::new();
+    let mut state_json = None;
+    let mut settings_json = None;
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i).map_err(|e| format!("zip entry error: {}", e))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        if name == format!("{STATE_PREFIX}{STATE_FILE}") {
+            state_json = Some(i);
+            continue;
+        }
+        if name == format!("{STATE_PREFIX}{SETTINGS_FILE}") {
+            settings_json = Some(i);
+            continue;
+        }
+        let (base, prefix) = if name.starts_with(DATA_PREFIX) {
+            (&dirs.aviutl2_data, DATA_PREFIX)
+        } else if meta.include_root && name.starts_with(ROOT_PREFIX) {
+            (&dirs.aviutl2_root, ROOT_PREFIX)
+        } else {
+            continue;
+        };
+        // ../ などで展開先の外に出るエントリは無視する
+        let Some(relative) = entry.enclosed_name().and_then(|p| p.strip_prefix(prefix.trim_end_matches('/')).ok().map(Path::to_path_buf)).filter(|p| !p.as_os_str().is_empty())
+        else {
+            report.errors.push(format!("skipped unsafe entry: {name}"));
+            continue;
+        };
+        targets.push((i, base.join(relative)));
+    }
+
+    // 削除・上書きの前に今の状態を保存しておき、失敗したらそこで止める
+    let safety = write_snapshot(config_dir, dirs, meta.include_root, Some(format!("before restoring {id}")))
+        .map_err(|e| format!("failed to create a safety snapshot before restoring: {e}"))?;
+    report.safety_snapshot = Some(safety.id);
+
+    let keep: HashSet<String> = targets.iter().map(|(_, p)| path_key(p)).collect();
+    remove_extra_files(&dirs.aviutl2_data, None, &keep, &mut report);
+    if meta.include_root {
+        remove_extra_files(&dirs.aviutl2_root, Some(&dirs.aviutl2_data), &keep, &mut report);
+    }
+    for (i, target) in targets {
+        let result = (|| -> io::Result<()> {
+            let mut entry = archive.by_index(i).map_err(io::Error::other)?;
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut out = BufWriter::new(fs::File::create(&target)?);
+            io::copy(&mut entry, &mut out)?;
+            out.flush()
+        })();
+        match result {
+            Ok(()) => report.restored += 1,
+            Err(e) => report.errors.push(format!("{}: {}", target.display(), e)),
+        }
+    }
+
+    if let Some(i) = state_json {
+        let mut text = String::new();
+        archive.by_index(i).map_err(|e| format!("zip entry error: {}", e))?.read_to_string(&mut text).map_err(|e| format!("read installed state error: {}", e))?;
+        report.installed_packages = super::installed::restore_installed_state(config_dir, &text)?;
+    }
+    if let Some(i) = settings_json {
+        let mut text = String::new();
+        archive.by_index(i).map_err(|e| format!("zip entry error: {}", e))?.read_to_string(&mut text).map_err(|e| format!("read settings error: {}", e))?;
+        crate::paths::restore_settings(config_dir, &text)?;
+    }
+    tracing::info!("Restored snapshot {} (restored={}, removed={}, errors={})", id, report.restored, report.removed, report.errors.len());
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn create_snapshot(app: tauri::AppHandle, include_root: Option<bool>, label: Option<String>) -> Result<SnapshotInfo, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    // インストール中のファイルを途中の状態で保存しない
+    let _guard = super::jobs::MUTATION_LOCK.lock().await;
+    tauri::async_runtime::spawn_blocking(move || write_snapshot(&config_dir, &crate::paths::dirs(), include_root.unwrap_or(false), label))
+        .await
+        .map_err(|e| format!("task join error: {e}"))?
+}
+
+#[tauri::command]
+pub fn list_snapshots(app: tauri::AppHandle) -> Result<Vec<SnapshotInfo>, String> {
+    Ok(list_snapshot_infos(&snapshot_dir(&app)?))
+}
+
+#[tauri::command]
+pub async fn restore_snapshot(app: tauri::AppHandle, id: String) -> Result<SnapshotRestoreReport, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let _guard = super::jobs::MUTATION_LOCK.lock().await;
+    super::jobs::ensure_aviutl_closed()?;
+    tauri::async_runtime::spawn_blocking(move || restore_snapshot_impl(&config_dir, &crate::paths::dirs(), &id)).await.map_err(|e| format!("task join error: {e}"))?
+}
+
+// 新しいものから keep 件を残し、max_age_days より古いものを削除する
+fn prune_snapshot_files(dir: &Path, keep: Option<usize>, max_age_days: Option<u32>) -> Vec<String> {
+    let cutoff = max_age_days.map(|days| chrono::Local::now() - chrono::Duration::days(days as i64));
+    let mut removed = Vec::new();
+    for (index, info) in list_snapshot_infos(dir).into_iter().enumerate() {
+        let over_count = keep.is_some_and(|keep| index >= keep);
+        let too_old = cutoff.is_some_and(|cutoff| chrono::DateTime::parse_from_rfc3339(&info.created_at).is_ok_and(|created| created < cutoff));
+        if !over_count && !too_old {
+            continue;
+        }
+        match fs::remove_file(&info.path) {
+            Ok(()) => removed.push(info.id),
+            Err(e) => tracing::error!("Failed to remove snapshot {}: {}", info.path, e),
+        }
+    }
+    removed
+}
+
+/// 新しいものから keep 件を残し、max_age_days より古いものを削除する
+#[tauri::command]
+pub fn prune_snapshots(app: tauri::AppHandle, keep: Option<usize>, max_age_days: Option<u32>) -> Result<Vec<String>, String> {
+    if keep.is_none() && max_age_days.is_none() {
+        return Err("either keep or maxAgeDays must be specified".to_string());
+    }
+    let removed = prune_snapshot_files(&snapshot_dir(&app)?, keep, max_age_days);
+    tracing::info!("Pruned {} snapshots", removed.len());
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::Settings;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aviutl2-snapshot-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn app_dirs(base: &Path) -> AppDirs {
+        let data = base.join("data");
+        AppDirs {
+            aviutl2_root: base.join("root"),
+            plugin_dir: data.join("Plugin"),
+            script_dir: data.join("Script"),
+            aviutl2_data: data,
+            catalog_exe_dir: base.join("app"),
+            catalog_config_dir: base.join("config"),
+            log_path: base.join("app.log"),
+        }
+    }
+
+    fn write_file(path: &Path, text: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    fn installed_state(version: &str) -> String {
+        serde_json::json!({ "version": "2", "data": { "packages": { "pkg": { "version": version } } } }).to_string()
+    }
+
+    fn write_settings(config: &Path, root: &str, theme: &str, holds: &[(&str, &str)]) {
+        let settings = Settings {
+            aviutl2_root: PathBuf::from(root),
+            theme: theme.to_string(),
+            package_version_holds: holds.iter().map(|(id, v)| (id.to_string(), v.to_string())).collect(),
+            ..Default::default()
+        };
+        settings.save_to_file(config.join(SETTINGS_FILE)).unwrap();
+    }
+
+    #[test]
+    fn restore_brings_back_files_state_and_settings_but_keeps_the_current_folders() {
+        let base = temp_dir("restore");
+        let dirs = app_dirs(&base);
+        let config = &dirs.catalog_config_dir;
+        write_file(&dirs.plugin_dir.join("a.aux2"), "v1");
+        write_file(&dirs.aviutl2_root.join("aviutl2.exe"), "exe");
+        write_file(&config.join(STATE_FILE), &installed_state("1.0"));
+        write_settings(config, "C:\\old-root", "dark", &[("pkg", "1.0")]);
+        let snapshot = write_snapshot(config, &dirs, true, Some("before update".to_string())).unwrap();
+        assert_eq!(snapshot.file_count, 2);
+        assert_eq!(snapshot.label.as_deref(), Some("before update"));
+
+        write_file(&dirs.plugin_dir.join("a.aux2"), "v2");
+        write_file(&dirs.plugin_dir.join("extra.lua"), "new");
+        write_file(&config.join(STATE_FILE), &installed_state("2.0"));
+        write_settings(config, "C:\\current-root", "light", &[]);
+
+        let report = restore_snapshot_impl(config, &dirs, &snapshot.id).unwrap();
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!((report.restored, report.removed, report.installed_packages), (2, 1, 1));
+        assert_eq!(fs::read_to_string(dirs.plugin_dir.join("a.aux2")).unwrap(), "v1");
+        assert!(!dirs.plugin_dir.join("extra.lua").exists());
+        assert_eq!(super::super::installed::load_installed_state(config).packages["pkg"].version, "1.0");
+        let settings = Settings::load_from_file(config.join(SETTINGS_FILE));
+        assert_eq!(settings.theme, "dark");
+        assert_eq!(settings.package_version_holds.get("pkg").map(String::as_str), Some("1.0"));
+        assert_eq!(settings.aviutl2_root, PathBuf::from("C:\\current-root"));
+        // 復元前の状態も残っている
+        let safety = report.safety_snapshot.unwrap();
+        assert!(list_snapshot_infos(&config.join(SNAPSHOT_DIR)).iter().any(|info| info.id == safety));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn refuses_to_restore_into_a_different_folder() {
+        let base = temp_dir("other-folder");
+        let dirs = app_dirs(&base);
+        write_file(&dirs.plugin_dir.join("a.aux2"), "v1");
+        let snapshot = write_snapshot(&dirs.catalog_config_dir, &dirs, false, None).unwrap();
+        let other = AppDirs { aviutl2_data: base.join("other-data"), ..app_dirs(&base) };
+        let error = restore_snapshot_impl(&dirs.catalog_config_dir, &other, &snapshot.id).unwrap_err();
+        assert!(error.contains("different AviUtl2 folder"), "{error}");
+        assert!(restore_snapshot_impl(&dirs.catalog_config_dir, &dirs, "../escape").is_err());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn skips_entries_that_escape_the_target_folder() {
+        let base = temp_dir("unsafe");
+        let dirs = app_dirs(&base);
+        let snapshots = dirs.catalog_config_dir.join(SNAPSHOT_DIR);
+        fs::create_dir_all(&snapshots).unwrap();
+        let meta = SnapshotMeta {
+            created_at: chrono::Local::now().to_rfc3339(),
+            label: None,
+            include_root: false,
+            aviutl2_root: dirs.aviutl2_root.to_string_lossy().into_owned(),
+            aviutl2_data: dirs.aviutl2_data.to_string_lossy().into_owned(),
+            file_count: 3,
+            total_bytes: 0,
+        };
+        let mut zip = zip::ZipWriter::new(fs::File::create(snapshots.join("crafted.zip")).unwrap());
+        let options = SimpleFileOptions::default();
+        for name in ["data/ok.txt", "data/../evil.txt", "data/../../evil2.txt"] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(name.as_bytes()).unwrap();
+        }
+        zip.start_file(META_ENTRY, options).unwrap();
+        zip.write_all(&serde_json::to_vec(&SnapshotMetaRoot::V1(meta)).unwrap()).unwrap();
+        zip.finish().unwrap();
+
+        let report = restore_snapshot_impl(&dirs.catalog_config_dir, &dirs, "crafted").unwrap();
+        assert_eq!(report.restored, 1);
+        assert_eq!(report.errors.iter().filter(|e| e.starts_with("skipped unsafe entry")).count(), 2);
+        assert!(dirs.aviutl2_data.join("ok.txt").is_file());
+        assert!(!base.join("evil.txt").exists() && !base.join("evil2.txt").exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_snapshots() {
+        let base = temp_dir("prune");
+        let dirs = app_dirs(&base);
+        write_file(&dirs.plugin_dir.join("a.aux2"), "v1");
+        let ids: Vec<String> = (0..3).map(|_| write_snapshot(&dirs.catalog_config_dir, &dirs, false, None).unwrap().id).collect();
+        let snapshots = dirs.catalog_config_dir.join(SNAPSHOT_DIR);
+        let mut removed = prune_snapshot_files(&snapshots, Some(1), None);
+        removed.sort();
+        assert_eq!(removed, ids[..2]);
+        assert_eq!(list_snapshot_infos(&snapshots).iter().map(|info| info.id.as_str()).collect::<Vec<_>>(), [ids[2].as_str()]);
+        assert_eq!(prune_snapshot_files(&snapshots, None, Some(0)), [ids[2].clone()]);
+        let _ = fs::remove_dir_all(&base);
+    }
+}
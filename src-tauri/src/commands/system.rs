@@ -225,7 +225,7 @@ pub async fn run_auo_setup(app: AppHandle, exe_path: String) -> Result<i32, Stri
     let mut args_vec = Vec::new();
     if settings.is_portable_mode {
         tracing::info!("Running in portable mode");
-        let core_installed = crate::read_installed_map(&app).get("Kenkun.AviUtlExEdit2").map(|s| !s.trim().is_empty()).unwrap_or(false);
+        let core_installed = super::installed::read_installed_map(&app).get("Kenkun.AviUtlExEdit2").map(|s| !s.trim().is_empty()).unwrap_or(false);
         if !core_installed {
             let msg = crate::paths::common_message(crate::paths::UiLocale::parse(&settings.locale), "backend.errors.exedit2NotInstalled");
             tracing::error!("{}", msg);
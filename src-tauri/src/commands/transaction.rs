@@ -0,0 +1,661 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use super::ownership::{FileOverlap, OverlapPolicy, OwnershipRegistry};
+use super::pathutil::{is_under_key, path_key};
+use super::replace::DeferredFile;
+
+// インストール中の変更履歴（AviUtl2 配下のファイルのみ記録する）
+static ACTIVE_JOURNAL: Lazy<Mutex<Option<ActiveJournal>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "version", content = "data")]
+enum JournalRoot {
+    // 変更は entries.jsonl に 1 行ずつ追記する（エントリごとに全体を書き直さない）
+    #[serde(rename = "1")]
+    V1(JournalHeader),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalHeader {
+    package_id: String,
+    started_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallJournal {
+    pub package_id: String,
+    pub started_at: String,
+    pub entries: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JournalEntry {
+    CreatedDir { path: PathBuf },
+    CreatedFile { path: PathBuf },
+    ReplacedFile { path: PathBuf, backup: PathBuf },
+    RemovedFile { path: PathBuf, backup: PathBuf },
+    RemovedDir { path: PathBuf },
+}
+
+struct ActiveJournal {
+    journal: InstallJournal,
+    dir: PathBuf,
+    roots: Vec<String>,
+    touched: HashSet<String>,
+    ownership: OwnershipRegistry,
+    overlap_policy: OverlapPolicy,
+    overlaps: Vec<FileOverlap>,
+    deferred: Vec<DeferredFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallTransactionSummary {
+    pub package_id: String,
+    pub written_files: Vec<String>,
+    pub removed_files: Vec<String>,
+    pub overlaps: Vec<FileOverlap>,
+    // 使用中だったため別名に変えたり AviUtl2 の終了を待ったりしたファイル
+    pub deferred_files: Vec<DeferredFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RollbackReport {
+    pub package_id: String,
+    pub restored: usize,
+    pub removed: usize,
+    pub errors: Vec<String>,
+}
+
+fn journal_dir(config_dir: &Path) -> PathBuf {
+    config_dir.join("install-journal")
+}
+
+fn journal_file(dir: &Path) -> PathBuf {
+    dir.join("journal.json")
+}
+
+fn is_under_roots(roots: &[String], path: &Path) -> bool {
+    let key = path_key(path);
+    roots.iter().any(|root| is_under_key(&key, root))
+}
+
+fn entries_file(dir: &Path) -> PathBuf {
+    dir.join("entries.jsonl")
+}
+
+fn write_journal(dir: &Path, journal: &InstallJournal) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let header = JournalHeader { package_id: journal.package_id.clone(), started_at: journal.started_at.clone() };
+    let json = serde_json::to_string_pretty(&JournalRoot::V1(header))?;
+    fs::write(entries_file(dir), "")?;
+    let tmp = dir.join("journal.json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, journal_file(dir))
+}
+
+fn append_entry(dir: &Path, entry: &JournalEntry) -> io::Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    fs::OpenOptions::new().append(true).create(true).open(entries_file(dir))?.write_all(line.as_bytes())
+}
+
+fn read_entries(dir: &Path) -> Vec<JournalEntry> {
+    let Ok(file) = fs::File::open(entries_file(dir)) else {
+        return Vec::new();
+    };
+    let mut entries = Vec::new();
+    for line in io::BufReader::new(file).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => {
+                // 書き込み中に終了した最後の行は読み飛ばす
+                tracing::warn!("Ignoring unreadable install journal entry: {}", e);
+                break;
+            }
+        }
+    }
+    entries
+}
+
+fn read_journal(dir: &Path) -> Option<InstallJournal> {
+    let text = fs::read_to_string(journal_file(dir)).ok()?;
+    match serde_json::from_str::<JournalRoot>(&text) {
+        Ok(JournalRoot::V1(header)) => Some(InstallJournal {
+            package_id: header.package_id,
+            started_at: header.started_at,
+            entries: read_entries(dir),
+        }),
+        Err(e) => {
+            tracing::error!("Failed to parse install journal: {}", e);
+            None
+        }
+    }
+}
+
+fn move_file(src: &Path, dst: &Path) -> io::Result<()> {
+    if let Some(parent) = dst.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // ボリュームを跨ぐ場合は rename できないのでコピーしてから削除する
+    if fs::rename(src, dst).is_err() {
+        fs::copy(src, dst)?;
+        fs::remove_file(src)?;
+    }
+    Ok(())
+}
+
+impl ActiveJournal {
+    fn next_backup_path(&self) -> PathBuf {
+        self.dir.join("backup").join(self.journal.entries.len().to_string())
+    }
+
+    fn push(&mut self, entry: JournalEntry) -> io::Result<()> {
+        append_entry(&self.dir, &entry)?;
+        self.journal.entries.push(entry);
+        Ok(())
+    }
+
+    fn record_missing_dirs(&mut self, dir: &Path) -> io::Result<()> {
+        let mut missing = Vec::new();
+        for ancestor in dir.ancestors() {
+            if ancestor.as_os_str().is_empty() || ancestor.exists() || !is_under_roots(&self.roots, ancestor) {
+                break;
+            }
+            missing.push(ancestor.to_path_buf());
+        }
+        for path in missing.into_iter().rev() {
+            if self.touched.insert(path_key(&path)) {
+                self.push(JournalEntry::CreatedDir { path })?;
+            }
+        }
+        Ok(())
+    }
+
+    // 他のインストール済みパッケージが所有するファイルへの書き込みを検出する
+    fn check_ownership(&mut self, path: &Path) -> io::Result<()> {
+        let owners = self.ownership.foreign_owners(path, &self.journal.package_id);
+        if owners.is_empty() {
+            return Ok(());
+        }
+        let names = owners.iter().map(|o| o.package_id.as_str()).collect::<Vec<_>>().join(", ");
+        if self.overlap_policy == OverlapPolicy::Block {
+            let path = path.display().to_string();
+            return Err(io::Error::other(crate::paths::common_message_with_args(
+                crate::paths::current_ui_locale(),
+                "backend.errors.fileOwnedByOtherPackage",
+                &[("path", &path), ("owners", &names)],
+            )));
+        }
+        tracing::warn!("{} overwrites {} owned by {}", self.journal.package_id, path.display(), names);
+        self.overlaps.push(FileOverlap { path: path.to_string_lossy().into_owned(), owners });
+        Ok(())
+    }
+
+    fn record_write(&mut self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            self.record_missing_dirs(parent)?;
+        }
+        let key = path_key(path);
+        if self.touched.contains(&key) {
+            return Ok(());
+        }
+        self.check_ownership(path)?;
+        self.touched.insert(key);
+        if path.is_file() {
+            let backup = self.next_backup_path();
+            if let Some(parent) = backup.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(path, &backup)?;
+            self.push(JournalEntry::ReplacedFile { path: path.to_path_buf(), backup })
+        } else {
+            self.push(JournalEntry::CreatedFile { path: path.to_path_buf() })
+        }
+    }
+
+    fn record_remove_file(&mut self, path: &Path) -> io::Result<()> {
+        let key = path_key(path);
+        let created_here = self.touched.contains(&key) && self.journal.entries.iter().any(|e| matches!(e, JournalEntry::CreatedFile { path: p } if path_key(p) == key));
+        if created_here {
+            // このトランザクションで作成したファイルは退避不要
+            return fs::remove_file(path);
+        }
+        let backup = self.next_backup_path();
+        move_file(path, &backup)?;
+        self.touched.insert(key);
+        self.push(JournalEntry::RemovedFile { path: path.to_path_buf(), backup })
+    }
+
+    fn record_remove_dir(&mut self, path: &Path) -> io::Result<()> {
+        for entry in walkdir::WalkDir::new(path).contents_first(true) {
+            let entry = entry?;
+            if entry.file_type().is_dir() {
+                fs::remove_dir(entry.path())?;
+                self.push(JournalEntry::RemovedDir { path: entry.path().to_path_buf() })?;
+            } else {
+                self.record_remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn with_active_journal<T>(path: &Path, f: impl FnOnce(&mut ActiveJournal) -> io::Result<T>) -> Option<io::Result<T>> {
+    let mut guard = ACTIVE_JOURNAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let active = guard.as_mut()?;
+    if !is_under_roots(&active.roots, path) {
+        return None;
+    }
+    Some(f(active))
+}
+
+/// ファイルを書き込む直前に呼び出し、既存ファイルの退避と新規作成の記録を行う
+pub fn prepare_write(path: &Path) -> io::Result<()> {
+    with_active_journal(path, |active| active.record_write(path)).unwrap_or(Ok(()))
+}
+
+/// 使用中のファイルの置き換えを後回しにしたことを記録する
+pub fn record_deferred(file: &DeferredFile) {
+    let _ = with_active_journal(Path::new(&file.path), |active| {
+        active.deferred.push(file.clone());
+        Ok(())
+    });
+}
+
+/// 作成したディレクトリを記録しながら create_dir_all を行う
+pub fn create_dir_all(path: &Path) -> io::Result<()> {
+    if let Some(result) = with_active_journal(path, |active| active.record_missing_dirs(path)) {
+        result?;
+    }
+    fs::create_dir_all(path)
+}
+
+/// ファイルまたはディレクトリを削除する（トランザクション中は退避して記録する）
+pub fn remove_path(path: &Path) -> io::Result<bool> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let is_dir = metadata.is_dir();
+    let journaled = with_active_journal(path, |active| if is_dir { active.record_remove_dir(path) } else { active.record_remove_file(path) });
+    match journaled {
+        Some(result) => result?,
+        None if is_dir => fs::remove_dir_all(path)?,
+        None => fs::remove_file(path)?,
+    }
+    Ok(true)
+}
+
+fn rollback_journal(journal: &InstallJournal) -> RollbackReport {
+    let mut report = RollbackReport { package_id: journal.package_id.clone(), ..Default::default() };
+    for entry in journal.entries.iter().rev() {
+        let result = match entry {
+            JournalEntry::CreatedFile { path } => match fs::remove_file(path) {
+                Ok(()) => {
+                    report.removed += 1;
+                    Ok(())
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(e),
+            },
+            JournalEntry::CreatedDir { path } => {
+                // 空でなければ（ユーザーが別途置いたファイルがあれば）残す
+                let _ = fs::remove_dir(path);
+                Ok(())
+            }
+            JournalEntry::ReplacedFile { path, backup } => fs::copy(backup, path).map(|_| report.restored += 1),
+            // 前回のロールバックで戻し済みなら何もしない
+            JournalEntry::RemovedFile { path, backup } if !backup.exists() && path.exists() => Ok(()),
+            JournalEntry::RemovedFile { path, backup } => move_file(backup, path).map(|_| report.restored += 1),
+            JournalEntry::RemovedDir { path } => fs::create_dir_all(path),
+        };
+        if let Err(e) = result {
+            let (JournalEntry::CreatedDir { path }
+            | JournalEntry::CreatedFile { path }
+            | JournalEntry::ReplacedFile { path, .. }
+            | JournalEntry::RemovedFile { path, .. }
+            | JournalEntry::RemovedDir { path }) = entry;
+            tracing::error!("Failed to roll back {}: {}", path.display(), e);
+            report.errors.push(format!("{}: {}", path.display(), e));
+        }
+    }
+    report
+}
+
+fn discard_journal_dir(dir: &Path) {
+    if let Err(e) = fs::remove_dir_all(dir)
+        && e.kind() != io::ErrorKind::NotFound
+    {
+        tracing::error!("Failed to remove install journal {}: {}", dir.display(), e);
+    }
+}
+
+// 残っているジャーナルをロールバックするか破棄する（ロールバックに失敗した変更があればジャーナルは残す）
+fn resolve_journal(dir: &Path, journal: &InstallJournal, rollback: bool) -> RollbackReport {
+    if !rollback {
+        tracing::info!("Discarded interrupted install transaction: {}", journal.package_id);
+        discard_journal_dir(dir);
+        return RollbackReport { package_id: journal.package_id.clone(), ..Default::default() };
+    }
+    let report = rollback_journal(journal);
+    tracing::info!("Interrupted install transaction rolled back: {} (restored={}, removed={}, errors={})", report.package_id, report.restored, report.removed, report.errors.len());
+    if report.errors.is_empty() {
+        discard_journal_dir(dir);
+    }
+    report
+}
+
+/// 中断またはロールバックの失敗で残っているインストールのジャーナル
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingInstallJournal {
+    pub package_id: String,
+    pub started_at: String,
+    pub entries: usize,
+}
+
+#[tauri::command]
+pub fn get_pending_install_journal(app: tauri::AppHandle) -> Result<Option<PendingInstallJournal>, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    if ACTIVE_JOURNAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).is_some() {
+        return Ok(None);
+    }
+    Ok(read_journal(&journal_dir(&config_dir)).map(|journal| PendingInstallJournal {
+        package_id: journal.package_id,
+        started_at: journal.started_at,
+        entries: journal.entries.len(),
+    }))
+}
+
+#[tauri::command]
+pub fn resolve_install_journal(app: tauri::AppHandle, rollback: bool) -> Result<RollbackReport, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let guard = ACTIVE_JOURNAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(active) = guard.as_ref() {
+        return Err(format!("another install transaction is already active: {}", active.journal.package_id));
+    }
+    let dir = journal_dir(&config_dir);
+    let Some(journal) = read_journal(&dir) else {
+        discard_journal_dir(&dir);
+        return Ok(RollbackReport::default());
+    };
+    Ok(resolve_journal(&dir, &journal, rollback))
+}
+
+#[tauri::command]
+pub fn begin_install_transaction(app: tauri::AppHandle, package_id: String, overlap_policy: Option<OverlapPolicy>) -> Result<(), String> {
+    let package_id = package_id.trim().to_string();
+    if package_id.is_empty() {
+        return Err(crate::paths::common_message_current("backend.errors.packageIdEmpty"));
+    }
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let dir = journal_dir(&config_dir);
+    let mut guard = ACTIVE_JOURNAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(active) = guard.as_ref() {
+        return Err(format!("another install transaction is already active: {}", active.journal.package_id));
+    }
+    if let Some(pending) = read_journal(&dir) {
+        return Err(format!("an interrupted install transaction must be resolved first: {}", pending.package_id));
+    }
+    discard_journal_dir(&dir);
+
+    let dirs = crate::paths::dirs();
+    let roots = [&dirs.aviutl2_root, &dirs.aviutl2_data].into_iter().filter(|p| !p.as_os_str().is_empty()).map(path_key).collect();
+    let journal = InstallJournal {
+        package_id: package_id.clone(),
+        started_at: chrono::Local::now().to_rfc3339(),
+        entries: Vec::new(),
+    };
+    write_journal(&dir, &journal).map_err(|e| format!("failed to create install journal: {}", e))?;
+    tracing::info!("Install transaction started: {}", package_id);
+    let ownership = OwnershipRegistry::build(&config_dir);
+    *guard = Some(ActiveJournal {
+        journal,
+        dir,
+        roots,
+        touched: HashSet::new(),
+        ownership,
+        overlap_policy: overlap_policy.unwrap_or_default(),
+        overlaps: Vec::new(),
+        deferred: Vec::new(),
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn commit_install_transaction(app: tauri::AppHandle) -> Result<InstallTransactionSummary, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let active = ACTIVE_JOURNAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take().ok_or_else(|| "no install transaction is active".to_string())?;
+    let mut summary = InstallTransactionSummary {
+        package_id: active.journal.package_id.clone(),
+        written_files: Vec::new(),
+        removed_files: Vec::new(),
+        overlaps: active.overlaps,
+        deferred_files: active.deferred,
+    };
+    for entry in &active.journal.entries {
+        match entry {
+            JournalEntry::CreatedFile { path } | JournalEntry::ReplacedFile { path, .. } if path.is_file() => summary.written_files.push(path.to_string_lossy().into_owned()),
+            JournalEntry::RemovedFile { path, .. } if !path.exists() => summary.removed_files.push(path.to_string_lossy().into_owned()),
+            _ => {}
+        }
+    }
+    discard_journal_dir(&active.dir);
+    if let Err(e) = super::installed::record_package_files(&config_dir, &summary.package_id, &summary.written_files, &summary.removed_files) {
+        tracing::error!("Failed to record installed files for {}: {}", summary.package_id, e);
+    }
+    tracing::info!(
+        "Install transaction committed: {} (written={}, removed={}, overlaps={}, deferred={})",
+        summary.package_id,
+        summary.written_files.len(),
+        summary.removed_files.len(),
+        summary.overlaps.len(),
+        summary.deferred_files.len()
+    );
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn rollback_install_transaction() -> Result<RollbackReport, String> {
+    let active = ACTIVE_JOURNAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take().ok_or_else(|| "no install transaction is active".to_string())?;
+    let report = rollback_journal(&active.journal);
+    tracing::info!("Install transaction rolled back: {} (restored={}, removed={}, errors={})", report.package_id, report.restored, report.removed, report.errors.len());
+    if report.errors.is_empty() {
+        discard_journal_dir(&active.dir);
+    }
+    Ok(report)
+}
+
+// 起動時に中断されたインストールのジャーナルが残っていればロールバックを提案する
+pub fn check_interrupted_install(app: &tauri::AppHandle) {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+    let Ok(config_dir) = app.path().app_config_dir() else {
+        return;
+    };
+    let dir = journal_dir(&config_dir);
+    let Some(journal) = read_journal(&dir) else {
+        if journal_file(&dir).exists() {
+            discard_journal_dir(&dir);
+        }
+        return;
+    };
+    tracing::warn!("Found interrupted install transaction: {} (started {}, entries={})", journal.package_id, journal.started_at, journal.entries.len());
+
+    let locale = crate::paths::current_ui_locale();
+    let message = crate::paths::common_message_with_args(
+        locale,
+        "backend.installJournal.message",
+        &[
+            ("packageId", &journal.package_id),
+            ("startedAt", &journal.started_at),
+            ("count", &journal.entries.len().to_string()),
+        ],
+    );
+    app.dialog()
+        .message(message)
+        .title(crate::paths::common_message(locale, "backend.installJournal.title"))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            crate::paths::common_message(locale, "backend.installJournal.rollback"),
+            crate::paths::common_message(locale, "backend.installJournal.discard"),
+        ))
+        .show(move |rollback| {
+            resolve_journal(&dir, &journal, rollback);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aviutl2-transaction-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn begin(base: &Path, root: &Path) -> ActiveJournal {
+        let journal = InstallJournal {
+            package_id: "test.package".to_string(),
+            started_at: "2026-01-01T00:00:00+09:00".to_string(),
+            entries: Vec::new(),
+        };
+        let dir = journal_dir(base);
+        write_journal(&dir, &journal).unwrap();
+        ActiveJournal {
+            journal,
+            dir,
+            roots: vec![path_key(root)],
+            touched: HashSet::new(),
+            ownership: OwnershipRegistry::default(),
+            overlap_policy: OverlapPolicy::default(),
+            overlaps: Vec::new(),
+            deferred: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn rollback_undoes_writes_removes_and_dirs_in_reverse_order() {
+        let base = temp_dir("rollback");
+        let root = base.join("root");
+        fs::create_dir_all(root.join("keep")).unwrap();
+        fs::write(root.join("a.txt"), "old").unwrap();
+        fs::write(root.join("keep/x.txt"), "kept").unwrap();
+
+        let mut active = begin(&base, &root);
+        let created = root.join("new/sub/b.txt");
+        active.record_write(&created).unwrap();
+        fs::create_dir_all(created.parent().unwrap()).unwrap();
+        fs::write(&created, "new").unwrap();
+        active.record_write(&root.join("a.txt")).unwrap();
+        fs::write(root.join("a.txt"), "new").unwrap();
+        active.record_remove_dir(&root.join("keep")).unwrap();
+        assert!(!root.join("keep").exists());
+
+        // 追記したエントリがそのまま読み戻せる
+        let journal = read_journal(&active.dir).unwrap();
+        let kinds: Vec<&str> = journal
+            .entries
+            .iter()
+            .map(|e| match e {
+                JournalEntry::CreatedDir { .. } => "created_dir",
+                JournalEntry::CreatedFile { .. } => "created_file",
+                JournalEntry::ReplacedFile { .. } => "replaced_file",
+                JournalEntry::RemovedFile { .. } => "removed_file",
+                JournalEntry::RemovedDir { .. } => "removed_dir",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "created_dir",
+                "created_dir",
+                "created_file",
+                "replaced_file",
+                "removed_file",
+                "removed_dir"
+            ]
+        );
+
+        let report = resolve_journal(&active.dir, &journal, true);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!((report.restored, report.removed), (2, 1));
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(root.join("keep/x.txt")).unwrap(), "kept");
+        assert!(!root.join("new").exists());
+        assert!(!active.dir.exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn failed_rollback_keeps_the_journal_and_can_be_retried() {
+        let base = temp_dir("retry");
+        let root = base.join("root");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("removed.txt"), "removed").unwrap();
+        fs::write(root.join("replaced.txt"), "old").unwrap();
+
+        let mut active = begin(&base, &root);
+        active.record_remove_file(&root.join("removed.txt")).unwrap();
+        active.record_write(&root.join("replaced.txt")).unwrap();
+        // 置き換えたファイルの場所をフォルダで塞ぎ、復元を失敗させる
+        fs::remove_file(root.join("replaced.txt")).unwrap();
+        fs::create_dir(root.join("replaced.txt")).unwrap();
+
+        let journal = read_journal(&active.dir).unwrap();
+        let report = resolve_journal(&active.dir, &journal, true);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(fs::read_to_string(root.join("removed.txt")).unwrap(), "removed");
+        assert!(read_journal(&active.dir).is_some());
+
+        // 戻し済みの削除はそのままに、残りだけをやり直せる
+        fs::remove_dir(root.join("replaced.txt")).unwrap();
+        let journal = read_journal(&active.dir).unwrap();
+        let report = resolve_journal(&active.dir, &journal, true);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(fs::read_to_string(root.join("replaced.txt")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(root.join("removed.txt")).unwrap(), "removed");
+        assert!(!active.dir.exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn partial_extraction_file_is_removed_on_rollback() {
+        let base = temp_dir("partial");
+        let root = base.join("root");
+        fs::create_dir_all(&root).unwrap();
+
+        let mut active = begin(&base, &root);
+        let partial = root.join("plugin.aux2.extracting");
+        active.record_write(&partial).unwrap();
+        fs::write(&partial, "half").unwrap();
+
+        let journal = read_journal(&active.dir).unwrap();
+        let report = resolve_journal(&active.dir, &journal, true);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(!partial.exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+}
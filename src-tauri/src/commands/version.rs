@@ -1,11 +1,18 @@
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
+use once_cell::sync::Lazy;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use tauri::Manager;
 use xxhash_rust::xxh3::xxh3_128;
 
+use super::pathutil::is_abs;
+
+// カタログの versions アーティファクト（パッケージ ID → バージョン一覧）
+static CATALOG_VERSIONS: Lazy<RwLock<HashMap<String, Vec<VersionEntryInput>>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum DetectResult {
@@ -14,15 +21,49 @@ pub enum DetectResult {
     Detected { version: String },
 }
 
-fn xxh3_128_hex<P: AsRef<Path>>(path: P) -> Result<String, String> {
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHashMismatch {
+    pub path: String,
+    pub expected: String,
+    // ファイルが存在しない場合は None
+    pub actual: Option<String>,
+}
+
+/// インストール後の検証結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum InstallVerification {
+    Verified { version: String },
+    Mismatch { expected_version: String, detected_version: Option<String>, files: Vec<FileHashMismatch> },
+    Unverifiable { reason: String },
+}
+
+/// 検証結果と、照合のついでに検出した実際のバージョン（検出できなかった場合は None）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallVerificationReport {
+    pub verification: InstallVerification,
+    pub detected: Option<DetectResult>,
+}
+
+pub(crate) fn xxh3_128_hex<P: AsRef<Path>>(path: P) -> Result<String, String> {
     let buf = std::fs::read(path).map_err(|e| format!("open/read error: {}", e))?;
     let h = xxh3_128(&buf);
     Ok(format!("{:032x}", h))
 }
 
-fn is_abs(p: &str) -> bool {
-    let s = p.replace('\\', "/");
-    s.starts_with('/') || (s.len() >= 3 && s.as_bytes()[1] == b':' && (s.as_bytes()[2] == b'/' || s.as_bytes()[2] == b'\\'))
+pub(crate) fn xxh3_128_hex_reader(reader: &mut dyn std::io::Read) -> std::io::Result<String> {
+    let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:032x}", hasher.digest128()))
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -63,6 +104,12 @@ pub struct VersionItemInput {
     versions: Vec<VersionEntryInput>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct CatalogVersionsPackageInput {
+    #[serde(default)]
+    versions: Vec<VersionEntryInput>,
+}
+
 fn read_hash_cache(app: &tauri::AppHandle) -> HashMap<std::path::PathBuf, HashCacheEntry> {
     return match read_hash_cache_impl(app) {
         Ok(map) => map,
@@ -255,3 +302,112 @@ pub fn detect_versions_map(app: tauri::AppHandle, items: Vec<VersionItemInput>)
     let out = determine_versions(&app, &list, &file_hash_cache);
     Ok(out)
 }
+
+#[tauri::command]
+pub fn set_catalog_versions(packages: HashMap<String, CatalogVersionsPackageInput>) -> Result<usize, String> {
+    let count = packages.len();
+    let map = packages.into_iter().map(|(id, p)| (id, p.versions)).collect();
+    *CATALOG_VERSIONS.write().map_err(|_| "catalog versions lock poisoned".to_string())? = map;
+    tracing::info!("Catalog versions updated: {} packages", count);
+    Ok(count)
+}
+
+/// カタログに記載されたバージョンのファイル一覧を展開済みの絶対パスで返す（version が None なら最新版）
+pub(crate) fn catalog_version_files(package_id: &str, version: Option<&str>) -> Vec<PathBuf> {
+    let Ok(guard) = CATALOG_VERSIONS.read() else {
+        return Vec::new();
+    };
+    let Some(versions) = guard.get(package_id) else {
+        return Vec::new();
+    };
+    let entry = match version.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) => versions.iter().find(|e| e.version == v),
+        None => versions.last(),
+    };
+    entry.map(|e| e.files.iter().map(|f| expand_macros(&f.path).replace('/', "\\")).filter(|p| is_abs(p)).map(PathBuf::from).collect()).unwrap_or_default()
+}
+
+/// カタログのバージョン情報（version が None なら最新版）とディスク上のファイルのハッシュを照合する
+/// あわせてパッケージの全バージョンから実際にインストールされているバージョンを検出する
+pub(crate) fn verify_package_files(app: &tauri::AppHandle, package_id: &str, version: Option<&str>) -> (InstallVerification, Option<DetectResult>) {
+    let unverifiable = |reason: String| (InstallVerification::Unverifiable { reason }, None);
+    let versions = match CATALOG_VERSIONS.read() {
+        Ok(guard) => guard.get(package_id).cloned().unwrap_or_default(),
+        Err(_) => return unverifiable("catalog versions lock poisoned".to_string()),
+    };
+    let entry = match version.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) => versions.iter().find(|e| e.version == v),
+        None => versions.last(),
+    }
+    .cloned();
+
+    // 検証できないバージョンでも、カタログに情報があれば実際のバージョンは検出しておく
+    let list = [VersionItemInput { id: package_id.to_string(), versions }];
+    let unique_paths = match collect_unique_paths(app, &list) {
+        Ok(paths) => paths,
+        Err(e) => return unverifiable(e),
+    };
+    let file_hash_cache = build_file_hash_cache(app, &unique_paths);
+    let detected = if list[0].versions.is_empty() { None } else { determine_versions(app, &list, &file_hash_cache).remove(package_id) };
+
+    let Some(entry) = entry else {
+        let reason = match version {
+            Some(v) => format!("version {v} of {package_id} is not listed in the catalog"),
+            None => format!("{package_id} has no version information in the catalog"),
+        };
+        return (InstallVerification::Unverifiable { reason }, detected);
+    };
+    if entry.files.is_empty() || entry.files.iter().any(|f| f.xxh128.is_empty()) {
+        let reason = format!("version {} of {} has no file hashes", entry.version, package_id);
+        return (InstallVerification::Unverifiable { reason }, detected);
+    }
+
+    let files: Vec<FileHashMismatch> = entry
+        .files
+        .iter()
+        .filter_map(|f| {
+            let path = expand_macros(&f.path).replace('/', "\\");
+            let actual = file_hash_cache.get(Path::new(&path)).cloned();
+            (actual.as_deref() != Some(f.xxh128.as_str())).then(|| FileHashMismatch { path, expected: f.xxh128.clone(), actual })
+        })
+        .collect();
+    let verification = if files.is_empty() {
+        InstallVerification::Verified { version: entry.version }
+    } else {
+        let detected_version = match &detected {
+            Some(DetectResult::Detected { version }) => Some(version.clone()),
+            _ => None,
+        };
+        InstallVerification::Mismatch { expected_version: entry.version, detected_version, files }
+    };
+    (verification, detected)
+}
+
+/// カタログに記載された最新バージョン
+pub(crate) fn catalog_latest_version(package_id: &str) -> Option<String> {
+    CATALOG_VERSIONS.read().ok()?.get(package_id)?.last().map(|e| e.version.clone())
+}
+
+/// expand_macros の逆変換（より深いディレクトリのマクロを優先する）
+pub(crate) fn collapse_macros(path: &str) -> String {
+    let dirs = crate::paths::dirs();
+    let mut candidates = [
+        ("{pluginsDir}", &dirs.plugin_dir),
+        ("{scriptsDir}", &dirs.script_dir),
+        ("{dataDir}", &dirs.aviutl2_data),
+        ("{appDir}", &dirs.aviutl2_root),
+    ]
+    .map(|(key, dir)| (key, dir.to_string_lossy().replace('/', "\\").trim_end_matches('\\').to_string()));
+    candidates.sort_by_key(|(_, dir)| std::cmp::Reverse(dir.len()));
+    let normalized = path.replace('/', "\\");
+    for (key, dir) in candidates {
+        if dir.is_empty() || normalized.len() < dir.len() || !normalized.as_bytes()[..dir.len()].eq_ignore_ascii_case(dir.as_bytes()) {
+            continue;
+        }
+        let rest = &normalized[dir.len()..];
+        if rest.is_empty() || rest.starts_with('\\') {
+            return format!("{key}{rest}");
+        }
+    }
+    normalized
+}
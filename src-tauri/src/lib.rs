@@ -33,30 +33,6 @@ fn init_app(app: &tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
-fn installed_file_path(app: &tauri::AppHandle) -> std::path::PathBuf {
-    app_config_dir(app).join("installed.json")
-}
-
-fn read_installed_map(app: &tauri::AppHandle) -> std::collections::HashMap<String, String> {
-    let p = installed_file_path(app);
-    let text = match std::fs::read_to_string(&p) {
-        Ok(v) => v,
-        Err(_) => return std::collections::HashMap::new(),
-    };
-    serde_json::from_str::<std::collections::HashMap<String, String>>(&text).unwrap_or_default()
-}
-
-fn write_installed_map(app: &tauri::AppHandle, map: &std::collections::HashMap<String, String>) -> Result<(), String> {
-    use std::fs::{File, create_dir_all};
-    use std::io::Write;
-    let base = app_config_dir(app);
-    let _ = create_dir_all(&base);
-    let p = installed_file_path(app);
-    let mut f = File::create(&p).map_err(|e| e.to_string())?;
-    f.write_all(serde_json::to_string_pretty(map).unwrap().as_bytes()).map_err(|e| e.to_string())?;
-    Ok(())
-}
-
 fn init_logger(app: &tauri::AppHandle) {
     let log_file = app_config_dir(app).join("logs/app.log");
     if let Some(parent) = log_file.parent() {
@@ -127,6 +103,8 @@ pub fn run() {
 
             paths::init_settings(app.handle())?;
             let _ = init_app(app.handle());
+            commands::transaction::check_interrupted_install(app.handle());
+            commands::replace::delete_pending_files(app.handle());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -134,23 +112,61 @@ pub fn run() {
             commands::catalog::query_catalog_index,
             commands::archive::extract_zip,
             commands::archive::list_zip_entries,
+            commands::archive::list_archive_entries,
             commands::archive::extract_7z_sfx,
+            commands::archive::cancel_extract,
+            commands::archive::extract_archive,
+            commands::archive::test_archive,
+            commands::archive::extract_lzh,
+            commands::archive::list_lzh_entries,
             commands::diagnostics::collect_device_info,
             commands::version::detect_versions_map,
+            commands::version::set_catalog_versions,
             commands::zstd::decompress_zstd_to_utf8,
             commands::logging::log_cmd,
             commands::niconi_commons::write_niconi_commons_ids,
             commands::version::calc_xxh3_hex,
             commands::installed::get_installed_map_cmd,
             commands::installed::add_installed_id_cmd,
+            commands::installed::verify_installed_package,
             commands::installed::remove_installed_id_cmd,
+            commands::installed::sync_installed_snapshot_cmd,
+            commands::installed::get_installed_state,
+            commands::installed::get_installed_files,
+            commands::installed::uninstall_package,
             commands::download::drive_download_to_file,
             commands::download::download_file_to_path,
             commands::download::download_file_to_path_booth,
             commands::download::ensure_booth_auth_window,
             commands::download::close_booth_auth_window,
+            commands::github::resolve_github_release,
+            commands::github::set_github_api_config,
             commands::version::expand_macros,
             commands::archive::copy_item_js,
+            commands::archive::delete_item_js,
+            commands::transaction::begin_install_transaction,
+            commands::transaction::commit_install_transaction,
+            commands::transaction::rollback_install_transaction,
+            commands::transaction::get_pending_install_journal,
+            commands::transaction::resolve_install_journal,
+            commands::installer::set_catalog_install,
+            commands::plan::plan_install,
+            commands::plan::plan_uninstall,
+            commands::resolver::resolve_install_plan,
+            commands::ownership::list_file_overlaps,
+            commands::ownership::check_file_ownership,
+            commands::jobs::enqueue_jobs,
+            commands::jobs::list_jobs,
+            commands::jobs::cancel_job,
+            commands::jobs::retry_job,
+            commands::jobs::clear_finished_jobs,
+            commands::jobs::set_download_concurrency,
+            commands::lockfile::export_environment_lock,
+            commands::lockfile::apply_environment_lock,
+            commands::snapshot::create_snapshot,
+            commands::snapshot::list_snapshots,
+            commands::snapshot::restore_snapshot,
+            commands::snapshot::prune_snapshots,
             commands::system::is_aviutl_running,
             commands::system::launch_aviutl2,
             commands::system::run_installer_executable,
@@ -158,6 +174,7 @@ pub fn run() {
             paths::complete_initial_setup,
             paths::update_settings,
             paths::set_package_update_paused,
+            paths::set_package_version_hold,
             paths::dismiss_deprecated_package_notice,
             paths::default_aviutl2_root,
             paths::resolve_aviutl2_root,
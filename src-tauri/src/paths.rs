@@ -3,6 +3,7 @@ use once_cell::sync::Lazy;
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 use std::{
     fs,
@@ -114,17 +115,18 @@ pub fn common_message_current(key: &str) -> String {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct Settings {
-    pub aviutl2_root: PathBuf,                        // AviUtl2 のルートディレクトリ
-    pub is_portable_mode: bool,                       // ポータブルモードかどうか
-    pub theme: String,                                // テーマ
-    pub locale: String,                               // UI ロケール
-    pub package_state_opt_out: bool,                  // 匿名統計の送信を無効化
-    pub package_updates_paused_ids: Vec<String>,      // 一時停止中のパッケージID一覧(UpdateCheckerの更新で使用予定)
-    pub deprecated_notice_dismissed_ids: Vec<String>, // 非推奨パッケージ通知を非表示にしたパッケージID一覧
-    pub local_mode_enabled: bool,                     // ローカル配信カタログを読み込むメンテナー向けモード
-    pub local_manifest_path: PathBuf,                 // メンテナー向けモードで読み込むローカルの manifest.json
-    pub app_version: String,                          // 本アプリのバージョン(UpdateCheckerの更新で使用)
-    pub catalog_exe_path: PathBuf,                    // 本ソフトの実行ファイルのパス(UpdateCheckerで使用))
+    pub aviutl2_root: PathBuf,                           // AviUtl2 のルートディレクトリ
+    pub is_portable_mode: bool,                          // ポータブルモードかどうか
+    pub theme: String,                                   // テーマ
+    pub locale: String,                                  // UI ロケール
+    pub package_state_opt_out: bool,                     // 匿名統計の送信を無効化
+    pub package_updates_paused_ids: Vec<String>,         // 一時停止中のパッケージID一覧(UpdateCheckerの更新で使用予定)
+    pub package_version_holds: BTreeMap<String, String>, // 指定バージョンに固定したパッケージ(ID→バージョン)
+    pub deprecated_notice_dismissed_ids: Vec<String>,    // 非推奨パッケージ通知を非表示にしたパッケージID一覧
+    pub local_mode_enabled: bool,                        // ローカル配信カタログを読み込むメンテナー向けモード
+    pub local_manifest_path: PathBuf,                    // メンテナー向けモードで読み込むローカルの manifest.json
+    pub app_version: String,                             // 本アプリのバージョン(UpdateCheckerの更新で使用)
+    pub catalog_exe_path: PathBuf,                       // 本ソフトの実行ファイルのパス(UpdateCheckerで使用))
 }
 
 // アプリケーションで使用するディレクトリ一覧
@@ -420,6 +422,58 @@ pub async fn set_package_update_paused(app: AppHandle, package_id: String, pause
     Ok(settings.package_updates_paused_ids)
 }
 
+// 固定バージョンの一覧（設定が読めなければ空）
+pub fn package_version_holds(app: &AppHandle) -> BTreeMap<String, String> {
+    let Ok(catalog_config_dir) = app.path().app_config_dir() else {
+        return BTreeMap::new();
+    };
+    let _settings_guard = SETTINGS_FILE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Settings::load_from_file(catalog_config_dir.join("settings.json")).package_version_holds
+}
+
+/// スナップショットに保存した settings.json で設定を置き換える
+/// AviUtl2 のフォルダと本ソフトの場所はこの端末の今の値を残す（別の構成のデータへ切り替えない）
+pub fn restore_settings(catalog_config_dir: &Path, settings_json: &str) -> Result<(), String> {
+    let mut restored: Settings = serde_json::from_str(settings_json).map_err(|e| format!("parse settings error: {}", e))?;
+    let settings_path = catalog_config_dir.join("settings.json");
+    let _settings_guard = SETTINGS_FILE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let current = Settings::load_from_file(&settings_path);
+    restored.aviutl2_root = current.aviutl2_root;
+    restored.is_portable_mode = current.is_portable_mode;
+    restored.local_manifest_path = current.local_manifest_path;
+    restored.app_version = current.app_version;
+    restored.catalog_exe_path = current.catalog_exe_path;
+    restored.save_to_file(&settings_path).map_err(|e| e.to_string())
+}
+
+// version が None なら固定を解除する
+#[tauri::command]
+pub async fn set_package_version_hold(app: AppHandle, package_id: String, version: Option<String>) -> Result<BTreeMap<String, String>, String> {
+    let package_id = package_id.trim();
+    if package_id.is_empty() {
+        return Err(common_message_current("backend.errors.packageIdEmpty"));
+    }
+
+    let catalog_config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    fs::create_dir_all(&catalog_config_dir).map_err(|e| e.to_string())?;
+    let settings_path = catalog_config_dir.join("settings.json");
+    let _settings_guard = SETTINGS_FILE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut settings = Settings::load_from_file(&settings_path);
+    settings.package_version_holds.retain(|id, v| !id.trim().is_empty() && !v.trim().is_empty());
+
+    match version.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) => {
+            settings.package_version_holds.insert(package_id.to_string(), v.to_string());
+        }
+        None => {
+            settings.package_version_holds.remove(package_id);
+        }
+    }
+
+    settings.save_to_file(&settings_path).map_err(|e| e.to_string())?;
+    Ok(settings.package_version_holds)
+}
+
 #[tauri::command]
 pub async fn dismiss_deprecated_package_notice(app: AppHandle, package_ids: Vec<String>) -> Result<Vec<String>, String> {
     let normalized_ids: Vec<String> = package_ids.into_iter().map(|id| id.trim().to_string()).filter(|id| !id.is_empty()).collect();
@@ -9,6 +9,7 @@ import { detectInstalledVersionsMap, loadInstalledMap, saveInstalledSnapshot } f
 import { ipc } from '@/utils/invokeIpc';
 import { logError } from '@/utils/logging';
 import { flushPackageStateQueue, maybeSendPackageStateSnapshot } from '@/utils/package-state';
+import { loadPackageVersionHolds } from '@/utils/package-version-hold';
 import { getSettings } from '@/utils/settings';
 
 const PACKAGE_STATE_FLUSH_DELAY_MS = 8000;
@@ -65,6 +66,11 @@ export function useCatalogBootstrap(dispatch: CatalogDispatch): void {
       }),
     );
 
+    void runBootstrapStep('version holds load failed', async () => {
+      const holds = await loadPackageVersionHolds();
+      if (!cancelled) dispatch({ type: 'SET_VERSION_HOLDS', payload: holds });
+    });
+
     void (async () => {
       const installedMapResult = await installedMapPromise;
       if (!installedMapResult.ok) {
@@ -1,6 +1,7 @@
 import { useNavigate } from 'react-router-dom';
 import PackageNoticeModal from '@/components/PackageNoticeModal';
 import { buildPackageDetailHref } from '@/features/package/model/helpers';
+import { hasPendingUpdate } from '@/utils/catalogStore';
 import { formatDate } from '@/utils/text';
 import ErrorDialog from '../ErrorDialog';
 import { pickThumbnail } from './helpers';
@@ -28,15 +29,21 @@ export default function PackageCard({
     onDownload,
     onUpdate,
     onRemove,
+    onInstallVersion,
   } = usePackageCardActions(item);
 
   const thumbnail = pickThumbnail(item);
   const isInstalled = Boolean(item.installed);
-  const hasUpdate = isInstalled && !item.isLatest;
+  const hasUpdate = hasPendingUpdate(item);
   const showPausedUpdateState = isPauseStateLoaded && hasUpdate && isUpdatePaused;
   const canInstall = Boolean(item.id);
   const lastUpdated = item.updatedAt ? formatDate(item.updatedAt).replace(/-/g, '/') : '?';
 
+  // 固定したバージョンと実際のバージョンがずれていたら、固定したバージョンを入れ直す
+  const installHeld = async () => {
+    if (item.heldVersion) await onInstallVersion(item.heldVersion);
+  };
+
   const openDetail = () => {
     onBeforeOpenDetail?.();
     navigate(buildPackageDetailHref(item.id, listSearch));
@@ -61,6 +68,7 @@ export default function PackageCard({
         onDownload={onDownload}
         onUpdate={onUpdate}
         onRemove={onRemove}
+        onInstallHeld={installHeld}
       />
       <ErrorDialog open={Boolean(error)} message={error} onClose={() => setError('')} />
       <PackageNoticeModal
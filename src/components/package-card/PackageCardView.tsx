@@ -23,6 +23,7 @@ export default function PackageCardView({
   onDownload,
   onUpdate,
   onRemove,
+  onInstallHeld,
 }: PackageCardViewProps) {
   const { t } = useTranslation('package');
   const installedVersionLabel = getInstalledVersionLabel(
@@ -56,9 +57,12 @@ export default function PackageCardView({
           isBusy={isBusy}
           progress={progress}
           installedVersionLabel={installedVersionLabel}
+          heldVersion={item.heldVersion}
+          holdDrift={Boolean(item.holdDrift)}
           onDownload={onDownload}
           onUpdate={onUpdate}
           onRemove={onRemove}
+          onInstallHeld={onInstallHeld}
         />
       </div>
 
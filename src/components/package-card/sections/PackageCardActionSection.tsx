@@ -1,5 +1,5 @@
 import type { MouseEvent } from 'react';
-import { CheckCircle2, CirclePause, Download, RefreshCw, Trash2, type LucideIcon } from 'lucide-react';
+import { CheckCircle2, CirclePause, Download, Pin, RefreshCw, Trash2, type LucideIcon } from 'lucide-react';
 import { useTranslation } from 'react-i18next';
 import ProgressCircle from '../../ProgressCircle';
 import type { PackageCardActionHandler, PackageCardBusyAction, PackageCardProgressView } from '../types';
@@ -16,9 +16,12 @@ interface PackageCardActionSectionProps {
   isBusy: boolean;
   progress: PackageCardProgressView;
   installedVersionLabel?: string;
+  heldVersion?: string;
+  holdDrift: boolean;
   onDownload: PackageCardActionHandler;
   onUpdate: PackageCardActionHandler;
   onRemove: PackageCardActionHandler;
+  onInstallHeld: PackageCardActionHandler;
 }
 
 const actionButtonBaseClass = 'text-xs font-bold rounded-lg';
@@ -140,9 +143,12 @@ export default function PackageCardActionSection({
   isBusy,
   progress,
   installedVersionLabel,
+  heldVersion,
+  holdDrift,
   onDownload,
   onUpdate,
   onRemove,
+  onInstallHeld,
 }: PackageCardActionSectionProps) {
   const { t } = useTranslation('package');
   const downloading = busyAction === 'download';
@@ -157,6 +163,15 @@ export default function PackageCardActionSection({
           <div className={cn(layout.inlineGap1_5, 'text-xs font-mono text-slate-500 dark:text-slate-400')}>
             <CheckCircle2 size={14} className={hasUpdate ? 'text-amber-500' : 'text-emerald-500'} />
             <span>{installedVersionLabel}</span>
+            {heldVersion ? (
+              <span
+                className={cn(layout.inlineGap1, holdDrift && 'text-amber-600 dark:text-amber-400')}
+                title={holdDrift ? t('actions.holdDrift', { version: heldVersion }) : undefined}
+              >
+                <Pin size={12} />
+                {t('actions.held', { version: heldVersion })}
+              </span>
+            ) : null}
           </div>
         ) : null}
       </div>
@@ -174,6 +189,21 @@ export default function PackageCardActionSection({
             className="h-9 w-full gap-1.5 px-2 bg-blue-600 hover:bg-blue-500 text-white transition-all shadow-lg shadow-blue-600/20 hover:shadow-blue-600/30 active:scale-95 cursor-pointer disabled:cursor-not-allowed"
             onAction={onDownload}
           />
+        ) : holdDrift && heldVersion ? (
+          <PrimaryActionButton
+            busy={updating}
+            disabled={primaryDisabled}
+            progress={progress}
+            label={t('actions.reinstallHeld', { version: heldVersion })}
+            title={t('actions.holdDrift', { version: heldVersion })}
+            icon={Pin}
+            progressClassName="text-amber-600 dark:text-amber-400"
+            className={cn(
+              updating ? 'w-full' : 'flex-1',
+              'h-9 gap-1.5 px-2 bg-amber-50 dark:bg-amber-900/20 text-amber-600 dark:text-amber-400 border border-amber-200 dark:border-amber-800/50 hover:bg-amber-100 dark:hover:bg-amber-900/30 transition-colors cursor-pointer disabled:cursor-not-allowed',
+            )}
+            onAction={onInstallHeld}
+          />
         ) : hasUpdate ? (
           isUpdatePaused ? (
             <PausedUpdateBadge />
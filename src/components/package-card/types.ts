@@ -16,7 +16,12 @@ export type PackageCardItem = Pick<
   | 'installed'
   | 'deprecation'
 > &
-  Partial<Pick<PackageItem, 'isLatest' | 'installedVersion' | 'detectedResult' | 'latestVersion'>> &
+  Partial<
+    Pick<
+      PackageItem,
+      'isLatest' | 'heldVersion' | 'holdDrift' | 'installedVersion' | 'detectedResult' | 'latestVersion'
+    >
+  > &
   InstallerRunnableItem;
 
 export interface PackageCardProps {
@@ -31,6 +36,8 @@ export type PackageCardBusyAction = PackageInstallBusyAction;
 
 export type PackageCardActionHandler = () => Promise<void>;
 
+export type PackageCardInstallVersionHandler = (version: string, hold?: boolean) => Promise<void>;
+
 export interface PackageCardProgressView {
   ratio: number;
   label: string;
@@ -52,6 +59,7 @@ export interface UsePackageCardActionsResult {
   onDownload: PackageCardActionHandler;
   onUpdate: PackageCardActionHandler;
   onRemove: PackageCardActionHandler;
+  onInstallVersion: PackageCardInstallVersionHandler;
 }
 
 export interface PackageCardViewProps {
@@ -71,4 +79,5 @@ export interface PackageCardViewProps {
   onDownload: PackageCardActionHandler;
   onUpdate: PackageCardActionHandler;
   onRemove: PackageCardActionHandler;
+  onInstallHeld: PackageCardActionHandler;
 }
@@ -18,6 +18,7 @@ export default function usePackageCardActions(item: PackageCardItem): UsePackage
     onDownload,
     onUpdate,
     onRemove,
+    onInstallVersion,
   } = usePackageInstallerActions({
     item,
     dispatch,
@@ -40,5 +41,6 @@ export default function usePackageCardActions(item: PackageCardItem): UsePackage
     onDownload,
     onUpdate,
     onRemove,
+    onInstallVersion,
   };
 }
@@ -255,6 +255,8 @@ export default function PackagePage() {
           onDownload={actions.onDownload}
           onUpdate={actions.onUpdate}
           onRemove={actions.onRemove}
+          onInstallVersion={actions.onInstallVersion}
+          onSetHold={actions.onSetHold}
         />
       </div>
 
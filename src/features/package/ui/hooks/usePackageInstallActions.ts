@@ -26,6 +26,8 @@ export default function usePackageInstallActions({
     onDownload,
     onUpdate,
     onRemove,
+    onInstallVersion,
+    onSetHold,
   } = usePackageInstallerActions({
     item,
     dispatch,
@@ -52,5 +54,7 @@ export default function usePackageInstallActions({
     onDownload,
     onUpdate,
     onRemove,
+    onInstallVersion,
+    onSetHold,
   };
 }
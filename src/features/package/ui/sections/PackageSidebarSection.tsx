@@ -23,6 +23,8 @@ export default function PackageSidebarSection({
   onDownload,
   onUpdate,
   onRemove,
+  onInstallVersion,
+  onSetHold,
 }: PackageSidebarSectionProps) {
   return (
     <aside className="flex flex-col gap-4 lg:gap-0 h-full">
@@ -49,6 +51,8 @@ export default function PackageSidebarSection({
           onDownload={onDownload}
           onUpdate={onUpdate}
           onRemove={onRemove}
+          onInstallVersion={onInstallVersion}
+          onSetHold={onSetHold}
         />
       </div>
       <PackageSidebarBackLink listLink={listLink} listLabel={listLabel} listLinkState={listLinkState} />
@@ -1,6 +1,9 @@
+import { useState } from 'react';
 import Badge from '@/components/ui/Badge';
 import Button from '@/components/ui/Button';
-import { CheckCircle2, Download, RefreshCw, Trash2 } from 'lucide-react';
+import Checkbox from '@/components/ui/Checkbox';
+import { inputVariants } from '@/components/ui/Input';
+import { AlertTriangle, CheckCircle2, Download, Pin, PinOff, RefreshCw, Trash2 } from 'lucide-react';
 import { useTranslation } from 'react-i18next';
 import ProgressCircle from '@/components/ProgressCircle';
 import { getInstalledVersionLabel } from '@/utils/detectResult';
@@ -10,9 +13,127 @@ import { layout, surface } from '@/components/ui/_styles';
 
 type PackageSidebarActionsCardProps = Pick<
   PackageSidebarSectionProps,
-  'item' | 'canInstall' | 'busyAction' | 'isBusy' | 'progress' | 'onDownload' | 'onUpdate' | 'onRemove'
+  | 'item'
+  | 'canInstall'
+  | 'busyAction'
+  | 'isBusy'
+  | 'progress'
+  | 'onDownload'
+  | 'onUpdate'
+  | 'onRemove'
+  | 'onInstallVersion'
+  | 'onSetHold'
 >;
 
+type PackageSidebarVersionControlsProps = Pick<
+  PackageSidebarActionsCardProps,
+  'item' | 'canInstall' | 'isBusy' | 'onInstallVersion' | 'onSetHold'
+>;
+
+// 固定状態の表示と、バージョンを指定したインストール・固定の切り替え
+function PackageSidebarVersionControls({
+  item,
+  canInstall,
+  isBusy,
+  onInstallVersion,
+  onSetHold,
+}: PackageSidebarVersionControlsProps) {
+  const { t } = useTranslation('package');
+  const versions = [...item.versions].reverse();
+  const [selectedVersion, setSelectedVersion] = useState(item.heldVersion ?? versions[0]?.version ?? '');
+  const [holdAfterInstall, setHoldAfterInstall] = useState(Boolean(item.heldVersion));
+  const heldVersion = item.heldVersion;
+
+  return (
+    <div className="space-y-3 border-t border-slate-200 dark:border-slate-800 pt-3">
+      {heldVersion ? (
+        <Badge variant="outlineNeutral" shape="pill" size="sm" className={cn(layout.inlineGap2, 'font-bold')}>
+          <Pin size={14} /> {t('actions.held', { version: heldVersion })}
+        </Badge>
+      ) : null}
+      {heldVersion && item.holdDrift ? (
+        <div className="space-y-2">
+          <p className={cn(layout.inlineGap1_5, 'text-xs text-amber-600 dark:text-amber-400')}>
+            <AlertTriangle size={14} className="shrink-0" />
+            {t('actions.holdDrift', { version: heldVersion })}
+          </p>
+          <Button
+            variant="secondary"
+            size="sm"
+            radius="xl"
+            className="w-full cursor-pointer disabled:cursor-not-allowed"
+            onClick={() => void onInstallVersion(heldVersion)}
+            disabled={isBusy || !canInstall}
+            type="button"
+          >
+            <RefreshCw size={16} /> {t('actions.reinstallHeld', { version: heldVersion })}
+          </Button>
+        </div>
+      ) : null}
+      {item.installed ? (
+        <Button
+          variant="secondary"
+          size="sm"
+          radius="xl"
+          className="w-full cursor-pointer disabled:cursor-not-allowed"
+          onClick={() => void onSetHold(heldVersion ? null : (item.installedVersion ?? null))}
+          disabled={isBusy || (!heldVersion && !item.installedVersion)}
+          type="button"
+        >
+          {heldVersion ? (
+            <>
+              <PinOff size={16} /> {t('actions.releaseHold')}
+            </>
+          ) : (
+            <>
+              <Pin size={16} /> {t('actions.hold')}
+            </>
+          )}
+        </Button>
+      ) : null}
+      {versions.length > 0 ? (
+        <div className="space-y-2">
+          <label className="text-xs font-medium text-slate-500 dark:text-slate-400" htmlFor="package-install-version">
+            {t('actions.versionLabel')}
+          </label>
+          <select
+            id="package-install-version"
+            value={selectedVersion}
+            onChange={(event) => setSelectedVersion(event.target.value)}
+            className={cn(inputVariants(), 'cursor-pointer')}
+            disabled={isBusy}
+          >
+            {versions.map((version) => (
+              <option key={version.version} value={version.version}>
+                {version.releaseDate ? `${version.version}（${version.releaseDate}）` : version.version}
+              </option>
+            ))}
+          </select>
+          <div className={cn(layout.inlineGap2, 'text-xs text-slate-600 dark:text-slate-300')}>
+            <Checkbox
+              checked={holdAfterInstall}
+              onChange={() => setHoldAfterInstall((prev) => !prev)}
+              ariaLabel={t('actions.holdAfterInstall')}
+            />
+            <span>{t('actions.holdAfterInstall')}</span>
+          </div>
+          <Button
+            variant="secondary"
+            size="sm"
+            radius="xl"
+            className="w-full cursor-pointer disabled:cursor-not-allowed"
+            onClick={() => void onInstallVersion(selectedVersion, holdAfterInstall)}
+            disabled={isBusy || !canInstall || !selectedVersion}
+            type="button"
+          >
+            <Download size={16} /> {t('actions.installVersion')}
+          </Button>
+        </div>
+      ) : null}
+    </div>
+  );
+}
+
 export default function PackageSidebarActionsCard({
   item,
   canInstall,
@@ -22,6 +143,8 @@ export default function PackageSidebarActionsCard({
   onDownload,
   onUpdate,
   onRemove,
+  onInstallVersion,
+  onSetHold,
 }: PackageSidebarActionsCardProps) {
   const { t } = useTranslation('package');
   const downloading = busyAction === 'download';
@@ -38,7 +161,7 @@ export default function PackageSidebarActionsCard({
     <div className={cn(surface.cardSection, 'space-y-3')}>
       {item.installed ? (
         <>
-          {item.isLatest ? (
+          {item.isLatest || item.heldVersion ? (
             <Badge variant="success" shape="pill" size="sm" className={cn(layout.inlineGap2, 'font-bold')}>
               <CheckCircle2 size={14} /> {t('actions.latest')}
               {installedVersionLabel ? `（${installedVersionLabel}）` : ''}
@@ -118,6 +241,13 @@ export default function PackageSidebarActionsCard({
           )}
         </Button>
       )}
+      <PackageSidebarVersionControls
+        item={item}
+        canInstall={canInstall}
+        isBusy={isBusy}
+        onInstallVersion={onInstallVersion}
+        onSetHold={onSetHold}
+      />
     </div>
   );
 }
@@ -51,6 +51,8 @@ export interface PackageSidebarSectionProps {
   onDownload: () => Promise<void>;
   onUpdate: () => Promise<void>;
   onRemove: () => Promise<void>;
+  onInstallVersion: (version: string, hold?: boolean) => Promise<void>;
+  onSetHold: (version: string | null) => Promise<void>;
 }
 
 export interface LicenseModalProps {
@@ -74,4 +76,6 @@ export interface UsePackageInstallActionsResult {
   onDownload: () => Promise<void>;
   onUpdate: () => Promise<void>;
   onRemove: () => Promise<void>;
+  onInstallVersion: (version: string, hold?: boolean) => Promise<void>;
+  onSetHold: (version: string | null) => Promise<void>;
 }
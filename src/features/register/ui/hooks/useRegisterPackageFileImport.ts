@@ -55,7 +55,8 @@ export default function useRegisterPackageFileImport({
         if (!isAu2pkgFileName(normalizedPath)) {
           throw new Error(t('errors.packageFileExtension'));
         }
-        const entries = await ipc.listZipEntries({ zipPath: normalizedPath });
+        const listing = await ipc.listArchiveEntries({ archivePath: normalizedPath });
+        const entries = listing.entries.filter((entry) => !entry.isDir && entry.path).map((entry) => entry.path ?? '');
         const files = collectAu2pkgEntries(entries);
         if (!files.length) {
           throw new Error(t('errors.packageFileNoCopyTargets'));
@@ -3,7 +3,7 @@ import { useTranslation } from 'react-i18next';
 import ErrorDialog from '@/components/ErrorDialog';
 import useUpdatesPage from './hooks/useUpdatesPage';
 import useUpdatesChangelog from './hooks/useUpdatesChangelog';
-import { BulkProgressSection, UpdatesHeaderSection, UpdatesTableSection } from './sections';
+import { BulkProgressSection, JobQueueSection, UpdatesHeaderSection, UpdatesTableSection } from './sections';
 import { page, text } from '@/components/ui/_styles';
 import { cn } from '@/lib/cn';
 
@@ -23,6 +23,11 @@ export default function UpdatesPage() {
     pauseBusyIdSet,
     error,
     setError,
+    jobs,
+    packageNames,
+    handleCancelJob,
+    handleRetryJob,
+    handleClearFinishedJobs,
     handleBulkUpdate,
     handleUpdate,
     handleTogglePause,
@@ -53,6 +58,16 @@ export default function UpdatesPage() {
           <BulkProgressSection bulkProgress={bulkProgress} bulkPercent={bulkPercent} progressStyle={progressStyle} />
         ) : null}
 
+        {jobs.length > 0 ? (
+          <JobQueueSection
+            jobs={jobs}
+            packageNames={packageNames}
+            onCancel={handleCancelJob}
+            onRetry={handleRetryJob}
+            onClearFinished={handleClearFinishedJobs}
+          />
+        ) : null}
+
         <div className="space-y-3">
           <h3 className={text.headingSmBold}>{t('sections.available')}</h3>
           <UpdatesTableSection
@@ -1,9 +1,18 @@
 import { useCallback, useEffect, useMemo, useState } from 'react';
 import { useTranslation } from 'react-i18next';
-import { useCatalog, useCatalogDispatch } from '@/utils/catalogStore';
-import { resolveInstallableCatalogItem } from '@/utils/catalogInstallItem';
-import { runInstallerForItem, runPackageRemoveAction } from '@/utils/installer';
+import { hasPendingUpdate, useCatalog, useCatalogDispatch } from '@/utils/catalogStore';
+import { resolveInstallableCatalogItem, type InstallableCatalogItem } from '@/utils/catalogInstallItem';
+import {
+  isJobFinished,
+  runInstallerForItem,
+  runInstallJobs,
+  runPackageRemoveAction,
+  toInstallJobRequest,
+  type InstallJobRequest,
+} from '@/utils/installer';
+import { syncDetectedVersionWithDispatch } from '@/utils/installer/actions';
 import { logError } from '@/utils/logging';
+import useInstallJobs from '@/utils/useInstallJobs';
 import usePausedPackageUpdates from '@/utils/usePausedPackageUpdates';
 import { toErrorMessage, toProgressLabel, toProgressRatio } from '../../model/helpers';
 import type {
@@ -118,7 +127,29 @@ export default function useUpdatesPage() {
     patchRuntimeState({ error: nextError });
   }, []);
 
-  const updatableItems = useMemo(() => items.filter((item) => item.installed && !item.isLatest), [items]);
+  const { jobs, cancelJob, retryJob, clearFinishedJobs } = useInstallJobs();
+  const packageNames = useMemo(() => Object.fromEntries(items.map((item) => [item.id, item.name])), [items]);
+  const runJobAction = useCallback(
+    async (action: () => Promise<void>) => {
+      try {
+        await action();
+      } catch (jobError) {
+        patchRuntimeState({ error: t('errors.jobActionFailed', { detail: toErrorMessage(jobError) }) });
+      }
+    },
+    [t],
+  );
+  const handleCancelJob = useCallback(
+    (taskId: string) => runJobAction(() => cancelJob(taskId)),
+    [cancelJob, runJobAction],
+  );
+  const handleRetryJob = useCallback(
+    (taskId: string) => runJobAction(() => retryJob(taskId)),
+    [retryJob, runJobAction],
+  );
+  const handleClearFinishedJobs = useCallback(() => runJobAction(clearFinishedJobs), [clearFinishedJobs, runJobAction]);
+
+  const updatableItems = useMemo(() => items.filter(hasPendingUpdate), [items]);
   const bulkUpdatableItems = useMemo(
     () => (pausedPackageUpdatesLoaded ? updatableItems.filter((item) => !pausedPackageIdSet.has(item.id)) : []),
     [pausedPackageIdSet, pausedPackageUpdatesLoaded, updatableItems],
@@ -143,61 +174,57 @@ export default function useUpdatesPage() {
     const failed: Array<{ item: UpdatesItem; msg: string }> = [];
 
     try {
-      for (let i = 0; i < targets.length; i += 1) {
-        const item = targets[i];
-        patchRuntimeState({
-          bulkProgress: {
-            ratio: 0,
-            itemName: item.name,
-            status: t('common:status.preparing'),
-            current: i + 1,
-            total,
-          },
-        });
-
+      const requests: InstallJobRequest[] = [];
+      const itemsById = new Map<string, UpdatesItem>();
+      const resolvedById = new Map<string, InstallableCatalogItem>();
+      for (const item of targets) {
         try {
           const resolvedItem = await resolveInstallableCatalogItem(item);
           if (!resolvedItem) {
             throw new Error(t('common:errors.unknown'));
           }
-          await runInstallerForItem(resolvedItem, dispatch, (progress: InstallerProgressPayload | null | undefined) => {
-            patchRuntimeState({
-              bulkProgress: {
-                ratio: toProgressRatio(progress),
-                itemName: item.name,
-                status: toProgressLabel(progress),
-                current: i + 1,
-                total,
-              },
-            });
-          });
-          patchRuntimeState({
-            bulkProgress: {
-              ratio: 1,
-              itemName: item.name,
-              status: t('common:status.done'),
-              current: i + 1,
-              total,
-            },
-          });
+          requests.push(toInstallJobRequest(resolvedItem, 'update'));
+          itemsById.set(item.id, item);
+          resolvedById.set(item.id, resolvedItem);
         } catch (itemError) {
           const message = toErrorMessage(itemError);
           failed.push({ item, msg: message });
           try {
             await logError(`[BulkUpdate] ${item.id}: ${message}`);
           } catch {}
-          patchRuntimeState({
-            bulkProgress: {
-              ratio: 1,
-              itemName: item.name,
-              status: t('runtime.error'),
-              current: i + 1,
-              total,
-            },
-          });
         }
       }
 
+      // ダウンロードは並列、インストールは 1 件ずつバックエンドのキューで処理する
+      const jobs = await runInstallJobs(requests, (current) => {
+        const finished = current.filter(isJobFinished).length;
+        const active = current.find((job) => job.state === 'running') ?? current.find((job) => !isJobFinished(job));
+        patchRuntimeState({
+          bulkProgress: {
+            ratio: finished / total,
+            itemName: active ? itemsById.get(active.packageId)?.name : undefined,
+            status: active ? t('common:status.processing') : t('common:status.done'),
+            current: Math.min(finished + 1, total),
+            total,
+          },
+        });
+      });
+
+      for (const job of jobs) {
+        const item = itemsById.get(job.packageId);
+        if (!item) continue;
+        if (job.state !== 'succeeded') {
+          const message = job.error || job.state;
+          failed.push({ item, msg: message });
+          try {
+            await logError(`[BulkUpdate] ${item.id}: ${message}`);
+          } catch {}
+          continue;
+        }
+        const resolvedItem = resolvedById.get(job.packageId);
+        if (resolvedItem) await syncDetectedVersionWithDispatch(resolvedItem, dispatch);
+      }
+
       if (failed.length > 0) {
         const sample = failed[0];
         patchRuntimeState({
@@ -308,6 +335,11 @@ export default function useUpdatesPage() {
     pauseBusyIdSet,
     error,
     setError,
+    jobs,
+    packageNames,
+    handleCancelJob,
+    handleRetryJob,
+    handleClearFinishedJobs,
     handleBulkUpdate,
     handleUpdate,
     handleTogglePause,
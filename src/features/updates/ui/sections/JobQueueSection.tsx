@@ -0,0 +1,67 @@
+import { useTranslation } from 'react-i18next';
+import Button from '@/components/ui/Button';
+import { isJobFinished } from '@/utils/installer';
+import type { InstallJob } from '@/utils/installer/jobs';
+import type { JobQueueSectionProps } from '../types';
+import { layout, surface, text } from '@/components/ui/_styles';
+import { cn } from '@/lib/cn';
+
+function jobPercent(job: InstallJob): number | null {
+  if (job.state === 'downloading' && job.total) return Math.round((job.read / job.total) * 100);
+  if (job.state === 'running' && job.totalSteps > 0) return Math.round(((job.stepIndex ?? 0) / job.totalSteps) * 100);
+  return null;
+}
+
+export default function JobQueueSection({
+  jobs,
+  packageNames,
+  onCancel,
+  onRetry,
+  onClearFinished,
+}: JobQueueSectionProps) {
+  const { t } = useTranslation('updates');
+  const hasFinished = jobs.some(isJobFinished);
+
+  return (
+    <div className={cn(surface.panel, 'mb-6 p-5')}>
+      <div className={cn(layout.rowBetween, 'mb-3')}>
+        <h3 className={text.headingSmBold}>{t('jobs.title')}</h3>
+        <Button variant="ghost" size="sm" onClick={onClearFinished} disabled={!hasFinished}>
+          {t('jobs.clearFinished')}
+        </Button>
+      </div>
+      <ul className="divide-y divide-slate-100 dark:divide-slate-800">
+        {jobs.map((job) => {
+          const percent = jobPercent(job);
+          const retryable = job.state === 'failed' || job.state === 'cancelled';
+          return (
+            <li key={job.taskId} className={cn(layout.rowBetweenGap2, 'py-2')}>
+              <div className="min-w-0">
+                <div className="text-sm font-medium text-slate-800 dark:text-slate-100 truncate">
+                  {packageNames[job.packageId] ?? job.packageId}
+                </div>
+                <div className={text.mutedXsTruncate}>
+                  {t(`jobs.states.${job.state}`)}
+                  {percent !== null ? ` ${percent}%` : ''}
+                  {job.error ? ` - ${job.error}` : ''}
+                </div>
+              </div>
+              <div className="flex shrink-0 gap-2">
+                {!isJobFinished(job) ? (
+                  <Button variant="secondary" size="sm" onClick={() => onCancel(job.taskId)}>
+                    {t('jobs.cancel')}
+                  </Button>
+                ) : null}
+                {retryable ? (
+                  <Button variant="accentBlue" size="sm" onClick={() => onRetry(job.taskId)}>
+                    {t('jobs.retry')}
+                  </Button>
+                ) : null}
+              </div>
+            </li>
+          );
+        })}
+      </ul>
+    </div>
+  );
+}
@@ -1,3 +1,4 @@
 export { default as BulkProgressSection } from './BulkProgressSection';
+export { default as JobQueueSection } from './JobQueueSection';
 export { default as UpdatesHeaderSection } from './UpdatesHeaderSection';
 export { default as UpdatesTableSection } from './UpdatesTableSection';
@@ -1,4 +1,5 @@
 import type { CSSProperties } from 'react';
+import type { InstallJob } from '@/utils/installer/jobs';
 import type { BulkUpdateProgress, ItemUpdateProgressMap, UpdatesItem } from '../model/types';
 import type { UpdatesChangelogEntry } from './hooks/useUpdatesChangelog';
 
@@ -15,6 +16,14 @@ export interface BulkProgressSectionProps {
   progressStyle: CSSProperties;
 }
 
+export interface JobQueueSectionProps {
+  jobs: InstallJob[];
+  packageNames: Record<string, string>;
+  onCancel: (taskId: string) => void;
+  onRetry: (taskId: string) => void;
+  onClearFinished: () => void;
+}
+
 export interface UpdatesTableSectionProps {
   items: UpdatesItem[];
   emptyMessage: string;
@@ -34,22 +34,51 @@ export const downloadInstallStepSchema = z.object({
   action: z.literal('download'),
 });
 
+// アーカイブの一部だけを展開する指定（subpath 以下のみ・先頭フォルダの除去・glob で絞り込み）
+const extractSelectionShape = {
+  subpath: nonEmptyStringSchema.optional(),
+  stripComponents: z.number().int().nonnegative().optional(),
+  include: nonEmptyStringArraySchema.optional(),
+  exclude: nonEmptyStringArraySchema.optional(),
+};
+
+// 展開結果のうち pattern に一致する書庫を同じ場所のフォルダ（拡張子を除いた名前）にさらに展開する
+const nestedExtractSchema = z.object({
+  pattern: nonEmptyStringSchema,
+  maxDepth: z.number().int().min(1).max(8).optional(),
+});
+
 export const extractInstallStepSchema = z.object({
   action: z.literal('extract'),
   from: nonEmptyStringSchema.optional(),
   to: nonEmptyStringSchema.optional(),
+  ...extractSelectionShape,
+  nested: nestedExtractSchema.optional(),
+  // 暗号化された書庫のパスワード（省略時は必要になった時点で尋ねる）
+  password: nonEmptyStringSchema.optional(),
 });
 
 export const extractSfxInstallStepSchema = z.object({
   action: z.literal('extractSfx'),
   from: nonEmptyStringSchema.optional(),
   to: nonEmptyStringSchema.optional(),
+  ...extractSelectionShape,
+  nested: nestedExtractSchema.optional(),
+  // 暗号化された書庫のパスワード（省略時は必要になった時点で尋ねる）
+  password: nonEmptyStringSchema.optional(),
 });
 
 export const copyInstallStepSchema = z.object({
   action: z.literal('copy'),
   from: nonEmptyStringSchema,
   to: nonEmptyStringSchema,
+  // コピー先に同名のファイルがある場合の扱い（省略時は上書き）
+  policy: z.enum(['overwrite', 'skipExisting', 'onlyIfNewer', 'backupThenOverwrite', 'failOnConflict']).optional(),
+  include: nonEmptyStringArraySchema.optional(),
+  exclude: nonEmptyStringArraySchema.optional(),
+  preserveMtime: z.boolean().optional(),
+  // 上書きするファイルが起動中の AviUtl2 に使われている場合の扱い（省略時は別名に変えて置き換える）
+  onLocked: z.enum(['renameAside', 'waitForExit', 'fail']).optional(),
 });
 
 export const deleteInstallStepSchema = z.object({
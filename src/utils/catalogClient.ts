@@ -190,6 +190,12 @@ export async function loadBootstrapCatalog(
     }),
   ]);
 
+  try {
+    await ipc.setCatalogVersions({ packages: versionsResult.data.packages });
+  } catch (error: unknown) {
+    await logError(`[catalogClient] set_catalog_versions failed: ${formatUnknownError(error)}`);
+  }
+
   return {
     manifest: context.manifest,
     locale,
@@ -239,6 +245,11 @@ async function loadInstallCatalogInternal(options: { timeoutMs?: number }): Prom
     timeoutMs,
     cacheLabel: 'catalog-install',
   });
+  try {
+    await ipc.setCatalogInstall({ packages: installResult.data.packages });
+  } catch (error: unknown) {
+    await logError(`[catalogClient] set_catalog_install failed: ${formatUnknownError(error)}`);
+  }
 
   return {
     manifest: context.manifest,
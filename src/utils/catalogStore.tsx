@@ -4,6 +4,7 @@
 // - loading/error: ローディング・エラー状態
 // - allTags/allTypes: UI のフィルター候補（全件から抽出）
 // - installedMap/detectedMap: インストール情報（検出結果）
+// - versionHolds: バージョン固定（id -> 固定バージョン）
 import { createContext, useReducer, useContext, useMemo } from 'react';
 import type { CatalogBootstrapPackage } from './catalogBootstrapModel';
 import {
@@ -25,6 +26,7 @@ type CatalogState = {
   installedIds: string[];
   installedMap: Record<string, string>; // id -> version
   detectedMap: DetectResultMap; // id -> detection result
+  versionHolds: Record<string, string>; // id -> held version
 };
 
 export type CatalogStorePackage = CatalogBootstrapPackage & {
@@ -35,6 +37,9 @@ export type CatalogStorePackage = CatalogBootstrapPackage & {
   installed: boolean;
   installedVersion?: string;
   isLatest?: boolean;
+  heldVersion?: string;
+  // 固定バージョンと検出バージョンが食い違っている
+  holdDrift?: boolean;
   detectedResult: DetectResult;
   catalogIndex: number;
 };
@@ -43,6 +48,7 @@ function applyDetectedResult(
   item: CatalogStorePackage,
   result: DetectResult,
   forceLatest = false,
+  heldVersion: string | undefined = item.heldVersion,
 ): CatalogStorePackage {
   const detectedVersion = getDetectedVersion(result);
   const latest = item.latestVersion;
@@ -53,10 +59,17 @@ function applyDetectedResult(
     installed,
     installedVersion: detectedVersion || undefined,
     isLatest,
+    heldVersion,
+    holdDrift: !!heldVersion && installed && detectedVersion !== heldVersion,
     detectedResult: result,
   };
 }
 
+// 更新対象かどうか（固定中のパッケージは新しいバージョンを無視する）
+export function hasPendingUpdate(item: Pick<CatalogStorePackage, 'installed' | 'isLatest' | 'heldVersion'>): boolean {
+  return item.installed && !item.isLatest && !item.heldVersion;
+}
+
 // 読み取り用/更新用の Context を分離して、再レンダリングを最小化
 const CatalogStateContext = createContext<CatalogState | null>(null);
 const CatalogDispatchContext = createContext<React.Dispatch<CatalogAction> | null>(null);
@@ -94,6 +107,7 @@ export function initCatalog(): CatalogState {
     installedIds: [],
     installedMap: {},
     detectedMap: {},
+    versionHolds: {},
   };
 }
 
@@ -104,7 +118,8 @@ export type CatalogAction =
   | { type: 'SET_INSTALLED_IDS'; payload: string[] }
   | { type: 'SET_INSTALLED_MAP'; payload: Record<string, string> }
   | { type: 'SET_DETECTED_MAP'; payload: DetectResultMap }
-  | { type: 'SET_DETECTED_ONE'; payload: { id: string; result: DetectResult; forceLatest?: boolean } };
+  | { type: 'SET_DETECTED_ONE'; payload: { id: string; result: DetectResult; forceLatest?: boolean } }
+  | { type: 'SET_VERSION_HOLDS'; payload: Record<string, string> };
 
 function catalogReducerInternal(state: CatalogState, action: CatalogAction): CatalogState {
   switch (action.type) {
@@ -123,6 +138,8 @@ function catalogReducerInternal(state: CatalogState, action: CatalogAction): Cat
             detectedResult: MISSING_DETECT_RESULT,
           },
           state.detectedMap?.[item.id] ?? MISSING_DETECT_RESULT,
+          false,
+          state.versionHolds[item.id],
         ),
       );
       // タグ・種類の候補一覧を集計（重複排除）
@@ -166,6 +183,12 @@ function catalogReducerInternal(state: CatalogState, action: CatalogAction): Cat
       items[index] = applyDetectedResult(state.items[index], detectedMap[id], forceLatest);
       return { ...state, detectedMap, items };
     }
+    case 'SET_VERSION_HOLDS': {
+      // バージョン固定の反映（drift の判定をやり直す）
+      const versionHolds = action.payload;
+      const items = state.items.map((it) => applyDetectedResult(it, it.detectedResult, false, versionHolds[it.id]));
+      return { ...state, versionHolds, items };
+    }
     default:
       // 未知のアクションはそのまま返す
       return state;
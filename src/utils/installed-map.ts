@@ -1,6 +1,6 @@
-import * as tauriFs from '@tauri-apps/plugin-fs';
 import * as z from 'zod';
 import {
+  type DetectResult,
   getDetectedVersion,
   isInstalledDetectResult,
   normalizeDetectResultMap,
@@ -11,7 +11,6 @@ import { ipc } from './invokeIpc';
 import { logError } from './logging';
 import { InstallerRunnableItem } from './installer/types';
 
-const INSTALLED_FILE = 'installed.json';
 const stringMapSchema = z.record(z.string(), z.unknown()).transform((value): Record<string, string> => {
   const normalized: Record<string, string> = {};
   Object.entries(value).forEach(([key, raw]) => {
@@ -39,9 +38,8 @@ export async function loadInstalledMap(): Promise<Record<string, string>> {
 
 async function writeInstalledMap(map: Record<string, string>): Promise<Record<string, string>> {
   try {
-    await tauriFs.writeTextFile(INSTALLED_FILE, JSON.stringify(map, null, 2), {
-      baseDir: tauriFs.BaseDirectory.AppConfig,
-    });
+    const saved = stringMapSchema.safeParse(await ipc.syncInstalledSnapshotCmd({ snapshot: map }));
+    if (saved.success) return saved.data;
   } catch (e: unknown) {
     try {
       await logError(`[writeInstalledMap] failed: ${formatUnknownError(e)}`);
@@ -50,9 +48,15 @@ async function writeInstalledMap(map: Record<string, string>): Promise<Record<st
   return map;
 }
 
-export async function addInstalledId(id: string, version: string = ''): Promise<void> {
+export type InstalledDetails = {
+  sourceType?: string;
+  sourceUrl?: string;
+  detected?: DetectResult | null;
+};
+
+export async function addInstalledId(id: string, version: string = '', details?: InstalledDetails): Promise<void> {
   try {
-    await ipc.addInstalledIdCmd({ id, version: String(version || '') });
+    await ipc.addInstalledIdCmd({ id, version: String(version || ''), details: details ?? null });
   } catch (e: unknown) {
     try {
       await logError(`[addInstalledId] invoke failed: ${formatUnknownError(e)}`);
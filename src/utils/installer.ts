@@ -1,5 +1,11 @@
 export { downloadFileFromBoothUrl, downloadFileFromUrl } from './installer/download';
 export { hasInstaller } from './installer/shape';
 export { runInstallerForItem } from './installer/install';
-export { runPackageInstallAction, runPackageRemoveAction } from './installer/package-actions';
+export { isJobFinished, runInstallJobs, toInstallJobRequest } from './installer/jobs';
+export type { InstallJob, InstallJobRequest } from './installer/jobs';
+export {
+  runPackageInstallAction,
+  runPackageInstallVersionAction,
+  runPackageRemoveAction,
+} from './installer/package-actions';
 export { runUninstallerForItem } from './installer/uninstall';
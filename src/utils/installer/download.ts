@@ -52,7 +52,8 @@ export async function downloadFileFromUrl(
   const taskId = createDownloadTaskId(options.taskId);
   return await withDownloadProgressListener(taskId, options.onProgress, async () => {
     try {
-      const finalPath = await ipc.downloadFileToPath({ url, destPath, taskId });
+      const expectedSize = options.expectedSize ?? null;
+      const finalPath = await ipc.downloadFileToPath({ url, destPath, taskId, expectedSize });
       return String(finalPath || '');
     } catch (e: unknown) {
       const detail = formatUnknownError(e) || 'unknown error';
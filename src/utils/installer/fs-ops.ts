@@ -1,8 +1,20 @@
+import * as tauriEvent from '@tauri-apps/api/event';
 import * as tauriPath from '@tauri-apps/api/path';
 import * as tauriFs from '@tauri-apps/plugin-fs';
 import { formatUnknownError } from '../errors';
-import { ipc } from '../invokeIpc';
+import {
+  type ArchiveExtraction,
+  type ArchiveTestReport,
+  type CopyOptions,
+  type CopyReport,
+  type EntrySelection,
+  type ExtractLimits,
+  type ExtractReport,
+  ipc,
+  type NestedExtract,
+} from '../invokeIpc';
 import { bestEffortLogError } from '../logging';
+import type { ExtractEventPayload, ExtractRunOptions } from './types';
 
 function isAbsPath(p: unknown): boolean {
   return /^(?:[a-zA-Z]:[\\/]|\\\\|\/)/.test(String(p || ''));
@@ -55,7 +67,7 @@ export async function deletePath(absPath: string): Promise<boolean> {
   if (!hasPath) {
     return false;
   }
-  await tauriFs.remove(absPath, { recursive: true });
+  await ipc.deleteItemJs({ pathStr: absPath });
   try {
     await removeEmptyDirectParentIfNeeded(absPath);
   } catch (e: unknown) {
@@ -64,27 +76,151 @@ export async function deletePath(absPath: string): Promise<boolean> {
   return true;
 }
 
-export async function extractZip(zipPath: string, destPath: string): Promise<void> {
+const EXTRACT_PROGRESS_EVENT = 'extract:progress';
+
+function createExtractTaskId(taskId?: string): string {
+  if (typeof taskId === 'string' && taskId.trim()) return taskId;
+  if (typeof crypto !== 'undefined' && typeof crypto.randomUUID === 'function') {
+    return crypto.randomUUID();
+  }
+  return `ex-${Date.now()}-${Math.random().toString(16).slice(2)}`;
+}
+
+async function withExtractProgressListener<T>(
+  taskId: string,
+  onProgress: ExtractRunOptions['onProgress'],
+  executor: () => Promise<T>,
+): Promise<T> {
+  if (typeof onProgress !== 'function') {
+    return await executor();
+  }
+  const unlisten = await tauriEvent.listen<ExtractEventPayload>(EXTRACT_PROGRESS_EVENT, (evt) => {
+    const payload = evt?.payload;
+    if (!payload || payload.taskId !== taskId) return;
+    onProgress({
+      entries: payload.entries ?? 0,
+      totalEntries: payload.totalEntries ?? 0,
+      bytes: payload.bytes ?? 0,
+      totalBytes: payload.totalBytes ?? 0,
+    });
+  });
   try {
-    await ipc.extractZip({ zipPath, destPath });
-    return;
-  } catch (e: unknown) {
-    await bestEffortLogError(`[extractZip] failed: ${formatUnknownError(e)}`);
-    throw e;
+    return await executor();
+  } finally {
+    try {
+      unlisten();
+    } catch (e: unknown) {
+      await bestEffortLogError(`[extract] unlisten failed: ${formatUnknownError(e)}`);
+    }
   }
 }
 
-export async function extractSevenZipSfx(sfxPath: string, destPath: string): Promise<void> {
+export async function extractZip(
+  zipPath: string,
+  destPath: string,
+  selection: EntrySelection = {},
+  options: ExtractRunOptions = {},
+): Promise<ExtractReport> {
+  const taskId = createExtractTaskId(options.taskId);
+  return await withExtractProgressListener(taskId, options.onProgress, async () => {
+    try {
+      return await ipc.extractZip({ zipPath, destPath, options: selection, taskId });
+    } catch (e: unknown) {
+      await bestEffortLogError(`[extractZip] failed: ${formatUnknownError(e)}`);
+      throw e;
+    }
+  });
+}
+
+export async function extractSevenZipSfx(
+  sfxPath: string,
+  destPath: string,
+  selection: EntrySelection & { nested?: NestedExtract; password?: string } = {},
+  options: ExtractRunOptions = {},
+): Promise<ExtractReport> {
+  const taskId = createExtractTaskId(options.taskId);
+  return await withExtractProgressListener(taskId, options.onProgress, async () => {
+    try {
+      return await ipc.extract7zSfx({ sfxPath, destPath, options: selection, taskId });
+    } catch (e: unknown) {
+      await bestEffortLogError(`[extractSevenZipSfx] failed: ${formatUnknownError(e)}`);
+      throw e;
+    }
+  });
+}
+
+export async function extractLzh(
+  lzhPath: string,
+  destPath: string,
+  selection: EntrySelection = {},
+  options: ExtractRunOptions = {},
+): Promise<ExtractReport> {
+  const taskId = createExtractTaskId(options.taskId);
+  return await withExtractProgressListener(taskId, options.onProgress, async () => {
+    try {
+      return await ipc.extractLzh({ lzhPath, destPath, options: selection, taskId });
+    } catch (e: unknown) {
+      await bestEffortLogError(`[extractLzh] failed: ${formatUnknownError(e)}`);
+      throw e;
+    }
+  });
+}
+
+// 形式はバックエンドが中身から判定する（zip / 7z / 自己展開形式 / tar / tar.gz / tar.zst / lzh）
+export async function extractArchive(
+  archivePath: string,
+  destPath: string,
+  selection: EntrySelection & { nested?: NestedExtract; password?: string } = {},
+  options: ExtractRunOptions = {},
+): Promise<ArchiveExtraction> {
+  const taskId = createExtractTaskId(options.taskId);
+  return await withExtractProgressListener(taskId, options.onProgress, async () => {
+    try {
+      return await ipc.extractArchive({ archivePath, destPath, options: selection, taskId });
+    } catch (e: unknown) {
+      await bestEffortLogError(`[extractArchive] failed: ${formatUnknownError(e)}`);
+      throw e;
+    }
+  });
+}
+
+// 暗号化された書庫はこれらのエラーで失敗するので、パスワードを尋ねて options.password を付けて再実行する
+export const ARCHIVE_PASSWORD_REQUIRED = 'PASSWORD_REQUIRED';
+export const ARCHIVE_WRONG_PASSWORD = 'WRONG_PASSWORD';
+
+export function isArchivePasswordError(error: unknown): boolean {
+  const message = formatUnknownError(error);
+  return message === ARCHIVE_PASSWORD_REQUIRED || message === ARCHIVE_WRONG_PASSWORD;
+}
+
+// 全エントリを書き込まずに復号して CRC を確かめる（壊れていれば展開前に失敗させる）
+export async function testArchive(
+  archivePath: string,
+  options: { password?: string; limits?: Partial<ExtractLimits>; taskId?: string } = {},
+): Promise<ArchiveTestReport> {
+  const { password, limits, taskId } = options;
   try {
-    await ipc.extract7zSfx({ sfxPath, destPath });
-    return;
+    return await ipc.testArchive({
+      archivePath,
+      password: password ?? null,
+      limits: limits ?? null,
+      taskId: taskId ?? null,
+    });
   } catch (e: unknown) {
-    await bestEffortLogError(`[extractSevenZipSfx] failed: ${formatUnknownError(e)}`);
+    await bestEffortLogError(`[testArchive] failed: ${formatUnknownError(e)}`);
     throw e;
   }
 }
 
-export async function copyPattern(fromPattern: string, toDirRel: string): Promise<number> {
-  const result = await ipc.copyItemJs({ srcStr: fromPattern, dstStr: toDirRel });
-  return typeof result === 'number' ? result : Number(result) || 0;
+// 展開中でなければ false（中断された展開は書きかけのファイルを片付けて CANCELLED で失敗する）
+export async function cancelExtraction(taskId: string): Promise<boolean> {
+  return await ipc.cancelExtract({ taskId });
+}
+
+export async function copyPattern(
+  fromPattern: string,
+  toDirRel: string,
+  options: CopyOptions = {},
+): Promise<CopyReport> {
+  return await ipc.copyItemJs({ srcStr: fromPattern, dstStr: toDirRel, options });
 }
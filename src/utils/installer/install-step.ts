@@ -1,16 +1,20 @@
 import { i18n } from '@/i18n';
-import { assertNever } from '../errors';
+import { assertNever, formatUnknownError } from '../errors';
+import type { EntrySelection, NestedExtract } from '../invokeIpc';
 import { logInfo } from '../logging';
 import { executeDeleteAction, executeRunAction } from './actions';
 import { downloadFileFromBoothUrl, downloadFileFromGoogleDrive, downloadFileFromUrl } from './download';
 import {
+  ARCHIVE_WRONG_PASSWORD,
   copyPattern,
   ensureAbsolutePath,
   expandMacros,
+  extractArchive,
   extractSevenZipSfx,
-  extractZip,
-  fetchGitHubURL,
+  isArchivePasswordError,
+  resolveGitHubAsset,
   runAuoSetup,
+  testArchive,
 } from './runtime';
 import { emitTestOperation } from './shape';
 import type {
@@ -22,6 +26,39 @@ import type {
   TestOperationKind,
 } from './types';
 
+function toEntrySelection(
+  step: Extract<InstallerAction, { action: 'extract' | 'extractSfx' }>,
+): EntrySelection & { nested?: NestedExtract } {
+  const { subpath, stripComponents, include, exclude, nested } = step;
+  return { subpath, stripComponents, include, exclude, nested };
+}
+
+function promptArchivePassword(archivePath: string, wrongPassword: boolean): string | null {
+  const name = archivePath.split(/[\\/]/).pop() || archivePath;
+  const message = wrongPassword
+    ? i18n.t('common:archivePassword.wrong', { name })
+    : i18n.t('common:archivePassword.required', { name });
+  const password = window.prompt(message, '');
+  return password ? password : null;
+}
+
+// 書き込む前に書庫を検査する。暗号化されていればパスワードを尋ねて再試行し、通ったパスワードを返す
+// （尋ねるのをやめた場合は元のエラーで失敗する）
+async function testArchiveWithPassword(archivePath: string, password?: string): Promise<string | undefined> {
+  let current = password;
+  for (;;) {
+    try {
+      await testArchive(archivePath, { password: current });
+      return current;
+    } catch (e: unknown) {
+      if (!isArchivePasswordError(e)) throw e;
+      const next = promptArchivePassword(archivePath, formatUnknownError(e) === ARCHIVE_WRONG_PASSWORD);
+      if (next === null) throw e;
+      current = next;
+    }
+  }
+}
+
 type StepOperation = {
   kind: TestOperationKind;
   summary: string;
@@ -33,6 +70,8 @@ type StepOperation = {
 type ExecuteInstallStepParams = {
   itemId: string;
   installerSource: InstallerSource | undefined;
+  // 最新版以外をインストールするときのバージョン
+  releaseVersion?: string;
   step: InstallerAction;
   tmpDir: string;
   ctx: InstallerMacroContext;
@@ -42,7 +81,17 @@ type ExecuteInstallStepParams = {
 };
 
 export async function executeInstallStep(params: ExecuteInstallStepParams): Promise<void> {
-  const { itemId, installerSource, step, tmpDir, ctx, stepOperation, onOperation, reportDownloadProgress } = params;
+  const {
+    itemId,
+    installerSource,
+    releaseVersion,
+    step,
+    tmpDir,
+    ctx,
+    stepOperation,
+    onOperation,
+    reportDownloadProgress,
+  } = params;
   const stepAction = step.action;
   switch (stepAction) {
     case 'download': {
@@ -63,8 +112,11 @@ export async function executeInstallStep(params: ExecuteInstallStepParams): Prom
         });
       } else {
         let url = '';
+        let expectedSize: number | null = null;
         if (src.type === 'githubRelease') {
-          url = await fetchGitHubURL(src);
+          const asset = await resolveGitHubAsset(src, releaseVersion);
+          url = asset.url;
+          expectedSize = asset.size || null;
         }
         if (src.type === 'directUrl') {
           url = src.url;
@@ -74,6 +126,7 @@ export async function executeInstallStep(params: ExecuteInstallStepParams): Prom
         await logInfo(`[installer ${itemId}] downloading from ${url} to ${tmpDir}`);
         ctx.downloadPath = await downloadFileFromUrl(url, tmpDir, {
           onProgress: reportDownloadProgress,
+          expectedSize,
         });
       }
       stepOperation.fromPath = sourceLabel;
@@ -96,7 +149,12 @@ export async function executeInstallStep(params: ExecuteInstallStepParams): Prom
       stepOperation.fromPath = from;
       stepOperation.toPath = to;
       logInfo(`[installer ${itemId}] extracting from ${from} to ${to}`);
-      await extractZip(from, to);
+      const password = await testArchiveWithPassword(from, step.password);
+      const { format, nested } = await extractArchive(from, to, { ...toEntrySelection(step), password });
+      logInfo(`[installer ${itemId}] extracted ${from} as ${format}`);
+      for (const archive of nested) {
+        logInfo(`[installer ${itemId}] extracted nested ${archive.entry} as ${archive.format} to ${archive.path}`);
+      }
       emitTestOperation(onOperation, {
         kind: stepOperation.kind,
         status: 'done',
@@ -113,7 +171,8 @@ export async function executeInstallStep(params: ExecuteInstallStepParams): Prom
       const from = ensureAbsolutePath(fromRel, `install.extractSfx.from`);
       const to = ensureAbsolutePath(toRel, `install.extractSfx.to`);
       logInfo(`[installer ${itemId}] extracting SFX from ${from} to ${to}`);
-      await extractSevenZipSfx(from, to);
+      const password = await testArchiveWithPassword(from, step.password);
+      await extractSevenZipSfx(from, to, { ...toEntrySelection(step), password });
       emitTestOperation(onOperation, {
         kind: 'extractSfx',
         status: 'done',
@@ -127,8 +186,13 @@ export async function executeInstallStep(params: ExecuteInstallStepParams): Prom
       const to = ensureAbsolutePath(await expandMacros(step.to, ctx), `install.copy.to`);
       stepOperation.fromPath = from;
       stepOperation.toPath = to;
-      const count = await copyPattern(from, to);
-      logInfo(`[installer ${itemId}] copy matched ${count} files (from=${from} to=${to})`);
+      const { policy, include, exclude, preserveMtime, onLocked } = step;
+      const report = await copyPattern(from, to, { policy, include, exclude, preserveMtime, onLocked });
+      const count = report.created.length + report.overwritten.length + report.skipped.length;
+      logInfo(
+        `[installer ${itemId}] copy matched ${count} files (created=${report.created.length} ` +
+          `overwritten=${report.overwritten.length} skipped=${report.skipped.length} from=${from} to=${to})`,
+      );
       if (count === 0) {
         throw new Error(`copy matched 0 files (from=${from} to=${to})`);
       }
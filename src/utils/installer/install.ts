@@ -1,22 +1,118 @@
+import * as tauriDialog from '@tauri-apps/plugin-dialog';
 import { i18n } from '@/i18n';
-import { isUnknownDetectResult } from '../detectResult';
+import { type DetectResult, isUnknownDetectResult } from '../detectResult';
 import { formatUnknownError } from '../errors';
 import { addInstalledId } from '../installed-map';
+import { type FileOverlap, type InstallVerification, ipc, type OverlapPolicy } from '../invokeIpc';
 import { bestEffortLogError, logInfo } from '../logging';
 import { recordPackageStateEvent } from '../package-state';
 import { syncDetectedVersionWithDispatch } from './actions';
 import { createInstallProgressTools } from './install-progress';
 import { executeInstallStep } from './install-step';
-import { deletePath, ensureAviutlClosed, ensureTmpDir } from './runtime';
-import { normalizeInstallerConfig, toTestOperationKind, toTestOperationLabel } from './shape';
+import { resolvePendingInstallJournal, rollbackFailedError } from './journal';
+import {
+  deletePath,
+  ensureAviutlClosed,
+  ensureTmpDir,
+  expandMacros,
+  isAviutlRunning,
+  lockedFilePath,
+  waitForAviutlExit,
+} from './runtime';
+import { describeInstallerSource, normalizeInstallerConfig, toTestOperationKind, toTestOperationLabel } from './shape';
 import type {
   CatalogDispatchFn,
   InstallerAction,
+  InstallerMacroContext,
   InstallProgressPayload,
   InstallerRunnableItem,
   TestOperationKind,
 } from './types';
 
+function formatOverlaps(overlaps: FileOverlap[]): string {
+  return overlaps.map((o) => `${o.path} (${o.owners.map((owner) => owner.packageId).join(', ')})`).join('\n');
+}
+
+const OVERLAP_DIALOG_MAX_FILES = 10;
+
+// 他のパッケージのファイルを上書きするかを確認する（上書きしない場合は block で書き込みを止める）
+async function confirmOverlapPolicy(packageId: string, overlaps: FileOverlap[]): Promise<OverlapPolicy> {
+  const shown = overlaps.slice(0, OVERLAP_DIALOG_MAX_FILES);
+  const files = formatOverlaps(shown) + (overlaps.length > shown.length ? '\n…' : '');
+  const overwrite = await tauriDialog.confirm(
+    i18n.t('common:fileOverlap.message', { packageId, count: overlaps.length, files }),
+    {
+      title: i18n.t('common:fileOverlap.title'),
+      kind: 'warning',
+      okLabel: i18n.t('common:fileOverlap.overwrite'),
+      cancelLabel: i18n.t('common:fileOverlap.keep'),
+    },
+  );
+  return overwrite ? 'warn' : 'block';
+}
+
+const VERIFICATION_DIALOG_MAX_FILES = 10;
+
+// カタログのハッシュと一致しなかったファイルを知らせる
+async function notifyVerificationMismatch(packageId: string, verification: InstallVerification): Promise<void> {
+  if (verification.status !== 'mismatch') return;
+  const shown = verification.files.slice(0, VERIFICATION_DIALOG_MAX_FILES);
+  const files = shown.map((f) => f.path).join('\n') + (verification.files.length > shown.length ? '\n…' : '');
+  await tauriDialog.message(
+    i18n.t('common:installVerification.mismatch', {
+      packageId,
+      expected: verification.expectedVersion,
+      detected: verification.detectedVersion ?? i18n.t('common:installVerification.unknownVersion'),
+      count: verification.files.length,
+      files,
+    }),
+    { title: i18n.t('common:installVerification.title'), kind: 'warning' },
+  );
+}
+
+function describeVerification(verification: InstallVerification): string {
+  switch (verification.status) {
+    case 'verified':
+      return `verified version=${verification.version}`;
+    case 'mismatch':
+      return [
+        `mismatch expected=${verification.expectedVersion} detected=${verification.detectedVersion ?? 'unknown'}`,
+        ...verification.files.map((f) => `${f.path} (expected ${f.expected}, actual ${f.actual ?? 'missing'})`),
+      ].join('\n');
+    case 'unverifiable':
+      return `unverifiable: ${verification.reason}`;
+  }
+}
+
+function isUnderDir(path: string, dir: string): boolean {
+  const key = (p: string) => p.replace(/\//g, '\\').replace(/\\+$/, '').toLowerCase();
+  const target = key(path);
+  const root = key(dir);
+  return target === root || target.startsWith(`${root}\\`);
+}
+
+// 一時フォルダの外に copy 以外の手順（展開・削除・実行）で書き込むか
+// これらの手順は使用中のファイルを置き換えられないので、AviUtl2 を終了してから実行する
+async function writesOutsideCopy(steps: InstallerAction[], ctx: InstallerMacroContext): Promise<boolean> {
+  for (const step of steps) {
+    switch (step.action) {
+      case 'download':
+      case 'copy':
+        break;
+      case 'extract':
+      case 'extractSfx':
+        if (!isUnderDir(String(await expandMacros(step.to || '{tmp}', ctx)), ctx.tmpDir)) return true;
+        break;
+      case 'delete':
+        if (!isUnderDir(String(await expandMacros(step.path, ctx)), ctx.tmpDir)) return true;
+        break;
+      default:
+        return true;
+    }
+  }
+  return false;
+}
+
 type InstallStepOperation = {
   kind: TestOperationKind;
   summary: string;
@@ -25,14 +121,16 @@ type InstallStepOperation = {
   targetPath: string;
 };
 
+// パスワードの入力やテスト用の操作ログが要る単体インストール用の実行系
+// 手順の意味はバックエンドの installer::execute_step（一括更新のジョブが使う）を正とし、こちらはそれに揃える
 export async function runInstallerForItem(
   item: InstallerRunnableItem,
   dispatch: CatalogDispatchFn,
   onProgress?: (progress: InstallProgressPayload) => void,
   onOperation?: (operation: Record<string, unknown>) => void,
 ): Promise<void> {
-  await ensureAviutlClosed();
-  const version = typeof item.latestVersion === 'string' ? item.latestVersion : '';
+  const pinnedVersion = item.installVersion && item.installVersion !== item.latestVersion ? item.installVersion : '';
+  const version = pinnedVersion || (typeof item.latestVersion === 'string' ? item.latestVersion : '');
   const idVersion = `${item.id}-${version || 'latest'}`.replace(/[^A-Za-z0-9._-]/g, '_');
   const tmpDir = await ensureTmpDir(idVersion);
   const installer = normalizeInstallerConfig(item.installer);
@@ -41,12 +139,33 @@ export async function runInstallerForItem(
     downloadPath: '',
   };
   const steps = installer.installSteps;
+  if (await writesOutsideCopy(steps, ctx)) {
+    await ensureAviutlClosed();
+  } else if (await isAviutlRunning()) {
+    // 起動中の AviUtl2 が使っているファイルは copy 手順の onLocked に従って置き換える
+    await logInfo(`[installer ${item.id}] AviUtl2 is running; files in use are replaced according to each copy step`);
+  }
 
   const { emitProgress, createDownloadProgressReporter } = createInstallProgressTools(steps.length, onProgress);
   emitProgress(0, null, -1, 'init');
 
+  let transactionActive = false;
   try {
     await logInfo(`[installer ${item.id}] start version=${version || ''} steps=${steps.length}`);
+    let predicted: FileOverlap[] = [];
+    try {
+      predicted = await ipc.checkFileOwnership({ packageId: item.id, version: version || null });
+    } catch (e: unknown) {
+      await bestEffortLogError(`[installer ${item.id}] ownership check failed: ${formatUnknownError(e)}`);
+    }
+    let overlapPolicy: OverlapPolicy = 'warn';
+    if (predicted.length > 0) {
+      await logInfo(`[installer ${item.id}] files owned by other packages:\n${formatOverlaps(predicted)}`);
+      overlapPolicy = await confirmOverlapPolicy(item.id, predicted);
+    }
+    await resolvePendingInstallJournal();
+    await ipc.beginInstallTransaction({ packageId: item.id, overlapPolicy });
+    transactionActive = true;
 
     for (let idx = 0; idx < steps.length; idx++) {
       const step = steps[idx];
@@ -62,16 +181,31 @@ export async function runInstallerForItem(
       emitProgress(runningUnits, step, idx, 'running');
 
       try {
-        await executeInstallStep({
-          itemId: item.id,
-          installerSource: installer.source,
-          step: step as InstallerAction,
-          tmpDir,
-          ctx,
-          stepOperation,
-          onOperation,
-          reportDownloadProgress,
-        });
+        const runStep = () =>
+          executeInstallStep({
+            itemId: item.id,
+            installerSource: installer.source,
+            releaseVersion: pinnedVersion || undefined,
+            step: step as InstallerAction,
+            tmpDir,
+            ctx,
+            stepOperation,
+            onOperation,
+            reportDownloadProgress,
+          });
+        try {
+          await runStep();
+        } catch (e: unknown) {
+          // onLocked: waitForExit の手順は何も書き込まずに失敗するので、AviUtl2 の終了を待ってから 1 度だけやり直す
+          const lockedPath = lockedFilePath(e);
+          if (!lockedPath || !(await isAviutlRunning())) throw e;
+          await logInfo(
+            `[installer ${item.id}] step ${idx + 1} is waiting for AviUtl2 to exit: ${lockedPath} is in use`,
+          );
+          await waitForAviutlExit();
+          await runStep();
+          await logInfo(`[installer ${item.id}] replaced ${lockedPath} after AviUtl2 exited`);
+        }
         emitProgress(idx + 1, step, idx, 'step-complete');
       } catch (e: unknown) {
         emitProgress(runningUnits, step, idx, 'error');
@@ -96,9 +230,36 @@ export async function runInstallerForItem(
       }
     }
 
-    await addInstalledId(item.id, version);
-    const detectedResult = await syncDetectedVersionWithDispatch(item, dispatch);
-    if (dispatch && isUnknownDetectResult(detectedResult)) {
+    const summary = await ipc.commitInstallTransaction();
+    transactionActive = false;
+    if (summary.overlaps.length > 0) {
+      await logInfo(
+        `[installer ${item.id}] overwrote files owned by other packages:\n${formatOverlaps(summary.overlaps)}`,
+      );
+    }
+    if (summary.deferredFiles.length > 0) {
+      const deferred = summary.deferredFiles.map((f) =>
+        f.renamedTo ? `${f.path} (old file: ${f.renamedTo})` : f.path,
+      );
+      await logInfo(`[installer ${item.id}] replaced files that were in use:\n${deferred.join('\n')}`);
+    }
+    // 検出結果は検証時のハッシュ計算から受け取り、検証できなかった場合だけ改めて検出する
+    await addInstalledId(item.id, version, describeInstallerSource(installer.source));
+    let detectedResult: DetectResult | null = null;
+    try {
+      const report = await ipc.verifyInstalledPackage({ packageId: item.id, version: version || null });
+      await logInfo(`[installer ${item.id}] verification ${describeVerification(report.verification)}`);
+      detectedResult = report.detected;
+      await notifyVerificationMismatch(item.id, report.verification);
+    } catch (e: unknown) {
+      await bestEffortLogError(`[installer ${item.id}] verification failed: ${formatUnknownError(e)}`);
+    }
+    if (detectedResult && dispatch) {
+      dispatch({ type: 'SET_DETECTED_ONE', payload: { id: item.id, result: detectedResult } });
+    } else if (!detectedResult) {
+      detectedResult = await syncDetectedVersionWithDispatch(item, dispatch);
+    }
+    if (dispatch && !pinnedVersion && isUnknownDetectResult(detectedResult)) {
       dispatch({ type: 'SET_DETECTED_ONE', payload: { id: item.id, result: detectedResult, forceLatest: true } });
     }
     try {
@@ -112,6 +273,18 @@ export async function runInstallerForItem(
   } catch (e: unknown) {
     const detail = formatUnknownError(e) || 'unknown error';
     await bestEffortLogError(`[installer ${item.id}] error: ${detail}`);
+    if (transactionActive) {
+      try {
+        const report = await ipc.rollbackInstallTransaction();
+        if (report.errors.length > 0) {
+          // ジャーナルは残るので、次回のインストール時に再試行できる
+          throw rollbackFailedError(report);
+        }
+        await logInfo(`[installer ${item.id}] rolled back`);
+      } catch (rollbackError: unknown) {
+        await bestEffortLogError(`[installer ${item.id}] rollback failed: ${formatUnknownError(rollbackError)}`);
+      }
+    }
     throw e;
   } finally {
     if (!import.meta.env?.DEV) {
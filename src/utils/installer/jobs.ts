@@ -0,0 +1,85 @@
+import * as tauriEvent from '@tauri-apps/api/event';
+import { type DeferredFile, type InstallVerification, ipc, type OverlapPolicy } from '../invokeIpc';
+import type { InstallerRunnableItem } from './types';
+
+export type JobKind = 'install' | 'update' | 'uninstall';
+export type JobState = 'queued' | 'downloading' | 'waiting' | 'running' | 'succeeded' | 'failed' | 'cancelled';
+
+export type InstallJob = {
+  taskId: string;
+  kind: JobKind;
+  packageId: string;
+  version: string | null;
+  downloadUrl: string | null;
+  overlapPolicy: OverlapPolicy | null;
+  state: JobState;
+  stepIndex: number | null;
+  totalSteps: number;
+  read: number;
+  total: number | null;
+  error: string | null;
+  verification: InstallVerification | null;
+  // 使用中だったため置き換えを後回しにしたファイル
+  deferredFiles: DeferredFile[];
+  // 先に終わっている必要がある同じバッチの依存パッケージのジョブ
+  dependsOn: string[];
+  attempts: number;
+  createdAt: string;
+  updatedAt: string;
+};
+
+export type InstallJobRequest = {
+  kind: JobKind;
+  packageId: string;
+  version?: string | null;
+  downloadUrl?: string | null;
+  overlapPolicy?: OverlapPolicy | null;
+};
+
+export const JOB_UPDATE_EVENT = 'job:update';
+
+export function isJobFinished(job: InstallJob): boolean {
+  return job.state === 'succeeded' || job.state === 'failed' || job.state === 'cancelled';
+}
+
+// GitHub のリリースはダウンロード時にバックエンドで解決する
+export function toInstallJobRequest(item: InstallerRunnableItem, kind: JobKind): InstallJobRequest {
+  const version = typeof item.latestVersion === 'string' ? item.latestVersion : '';
+  return { kind, packageId: item.id, version: version || null, downloadUrl: null };
+}
+
+export async function runInstallJobs(
+  requests: InstallJobRequest[],
+  onUpdate?: (jobs: InstallJob[]) => void,
+): Promise<InstallJob[]> {
+  const tracked = new Map<string, InstallJob>();
+  let resolveDone: () => void = () => {};
+  const done = new Promise<void>((resolve) => {
+    resolveDone = resolve;
+  });
+  const notify = () => {
+    const jobs = Array.from(tracked.values());
+    onUpdate?.(jobs);
+    if (jobs.every(isJobFinished)) resolveDone();
+  };
+  const unlisten = await tauriEvent.listen<InstallJob>(JOB_UPDATE_EVENT, (evt) => {
+    const job = evt?.payload;
+    if (!job || !tracked.has(job.taskId)) return;
+    tracked.set(job.taskId, job);
+    notify();
+  });
+  try {
+    const queued = await ipc.enqueueJobs({ jobs: requests });
+    queued.forEach((job) => tracked.set(job.taskId, job));
+    // キューに積む前に終わったジョブの取りこぼしを防ぐ
+    const latest = await ipc.listJobs();
+    latest.forEach((job) => {
+      if (tracked.has(job.taskId)) tracked.set(job.taskId, job);
+    });
+    notify();
+    await done;
+    return Array.from(tracked.values());
+  } finally {
+    unlisten();
+  }
+}
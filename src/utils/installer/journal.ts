@@ -0,0 +1,40 @@
+import * as tauriDialog from '@tauri-apps/plugin-dialog';
+import { i18n } from '@/i18n';
+import { ipc, type RollbackReport } from '../invokeIpc';
+import { logInfo } from '../logging';
+
+export function rollbackFailedError(report: RollbackReport): Error {
+  return new Error(
+    i18n.t('common:backend.installJournal.rollbackFailed', {
+      count: report.errors.length,
+      detail: report.errors.join('\n'),
+    }),
+  );
+}
+
+// 前回のインストールの変更履歴が残っていれば（中断・ロールバック失敗）、元に戻すかそのままにするかを確認する
+export async function resolvePendingInstallJournal(): Promise<void> {
+  const pending = await ipc.getPendingInstallJournal();
+  if (!pending) return;
+  const rollback = await tauriDialog.confirm(
+    i18n.t('common:backend.installJournal.message', {
+      packageId: pending.packageId,
+      startedAt: pending.startedAt,
+      count: pending.entries,
+    }),
+    {
+      title: i18n.t('common:backend.installJournal.title'),
+      kind: 'warning',
+      okLabel: i18n.t('common:backend.installJournal.rollback'),
+      cancelLabel: i18n.t('common:backend.installJournal.discard'),
+    },
+  );
+  const report = await ipc.resolveInstallJournal({ rollback });
+  await logInfo(
+    `[install-journal] ${rollback ? 'rolled back' : 'discarded'} ${pending.packageId} ` +
+      `(restored=${report.restored}, removed=${report.removed}, errors=${report.errors.length})`,
+  );
+  if (report.errors.length > 0) {
+    throw rollbackFailedError(report);
+  }
+}
@@ -2,8 +2,9 @@ import { i18n } from '@/i18n';
 import { MISSING_DETECT_RESULT } from '../detectResult';
 import { detectInstalledVersionsMap, loadInstalledMap, removeInstalledId } from '../installed-map';
 import type { CatalogDispatch } from '../catalogStore';
+import { persistPackageVersionHold } from '../package-version-hold';
 import { runInstallerForItem } from './install';
-import { hasInstaller } from './shape';
+import { hasInstaller, normalizeInstallerConfig } from './shape';
 import type { Installer, InstallProgressPayload, InstallerRunnableItem } from './types';
 import { runUninstallerForItem } from './uninstall';
 
@@ -39,6 +40,25 @@ export async function runPackageInstallAction(
   await runInstallerForItem(item, dispatch, onProgress);
 }
 
+// 過去のバージョンは配布元から取得できる場合のみ（現状は GitHub のリリースタグ）
+export async function runPackageInstallVersionAction(
+  item: InstallerRunnableItem,
+  version: string,
+  dispatch: CatalogDispatch | null | undefined,
+  options: { hold?: boolean; onProgress?: (progress: InstallProgressPayload) => void } = {},
+): Promise<void> {
+  if (!hasInstaller(item)) throw new Error(i18n.t('package:actions.missingInstaller'));
+  const target = version.trim();
+  const isLatest = !target || target === item.latestVersion;
+  if (!isLatest && normalizeInstallerConfig(item.installer).source?.type !== 'githubRelease') {
+    throw new Error(i18n.t('package:actions.versionNotResolvable', { version: target }));
+  }
+  await runInstallerForItem({ ...item, installVersion: isLatest ? undefined : target }, dispatch, options.onProgress);
+  if (options.hold && target) {
+    await persistPackageVersionHold(item.id, target, dispatch);
+  }
+}
+
 export async function runPackageRemoveAction(
   item: InstallerRunnableItem,
   dispatch: CatalogDispatch | null | undefined,
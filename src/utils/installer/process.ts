@@ -21,3 +21,23 @@ export async function ensureAviutlClosed(): Promise<void> {
     throw new Error(i18n.t('common:process.running'));
   }
 }
+
+// copy 手順の onLocked: waitForExit は、使用中のファイルがあると何も書き込まずに "FILE_LOCKED: <パス>" で失敗する
+const FILE_LOCKED_PREFIX = 'FILE_LOCKED: ';
+const AVIUTL_EXIT_POLL_MS = 2000;
+
+export function lockedFilePath(error: unknown): string | null {
+  const message = formatUnknownError(error);
+  const at = message.indexOf(FILE_LOCKED_PREFIX);
+  return at < 0 ? null : message.slice(at + FILE_LOCKED_PREFIX.length);
+}
+
+export async function isAviutlRunning(): Promise<boolean> {
+  return !!(await ipc.isAviutlRunning());
+}
+
+export async function waitForAviutlExit(): Promise<void> {
+  while (await isAviutlRunning()) {
+    await new Promise((resolve) => setTimeout(resolve, AVIUTL_EXIT_POLL_MS));
+  }
+}
@@ -19,7 +19,19 @@ export async function runAuoSetup(exeAbsPath: string): Promise<void> {
   }
 }
 
-export { ensureAbsolutePath, deletePath, extractZip, extractSevenZipSfx, copyPattern } from './fs-ops';
-export { fetchGitHubURL } from './github';
+export {
+  ensureAbsolutePath,
+  deletePath,
+  extractZip,
+  extractSevenZipSfx,
+  extractLzh,
+  extractArchive,
+  testArchive,
+  isArchivePasswordError,
+  ARCHIVE_WRONG_PASSWORD,
+  cancelExtraction,
+  copyPattern,
+} from './fs-ops';
+export { resolveGitHubAsset } from './github';
 export { expandMacros, ensureTmpDir, expandRunArgs } from './macros';
-export { ensureAviutlClosed } from './process';
+export { ensureAviutlClosed, isAviutlRunning, lockedFilePath, waitForAviutlExit } from './process';
@@ -62,6 +62,19 @@ function normalizeInstallerSource(raw: unknown): InstallerSource | undefined {
   return parsed.data;
 }
 
+export function describeInstallerSource(source: InstallerSource | undefined): { sourceType?: string; sourceUrl?: string } {
+  if (!source) return {};
+  switch (source.type) {
+    case 'directUrl':
+    case 'booth':
+      return { sourceType: source.type, sourceUrl: source.url };
+    case 'githubRelease':
+      return { sourceType: source.type, sourceUrl: `https://github.com/${source.owner}/${source.repo}` };
+    case 'googleDrive':
+      return { sourceType: source.type, sourceUrl: `https://drive.google.com/file/d/${source.id}` };
+  }
+}
+
 function normalizeInstallSteps(raw: unknown): InstallerConfigLike['installSteps'] {
   if (!Array.isArray(raw)) return [];
   return raw.map((step, index) => {
@@ -1,5 +1,6 @@
 import type { Installation } from '../catalog-schema/shared/installationSchema';
 import type { DetectResult } from '../detectResult';
+import type { ExtractProgress } from '../invokeIpc';
 
 export type Installer = Installation;
 export type InstallerSource = Installation['source'];
@@ -20,6 +21,8 @@ export type DownloadProgress = {
 export type DownloadOptions = {
   onProgress?: (progress: DownloadProgress) => void;
   taskId?: string;
+  // 一致しない場合はダウンロードしたファイルを削除してエラーにする
+  expectedSize?: number | null;
 };
 
 export type DownloadEventPayload = {
@@ -29,6 +32,16 @@ export type DownloadEventPayload = {
   total?: number;
 };
 
+export type ExtractRunOptions = {
+  onProgress?: (progress: ExtractProgress) => void;
+  // cancelExtraction で中断するときに指定する
+  taskId?: string;
+};
+
+export type ExtractEventPayload = Partial<ExtractProgress> & {
+  taskId?: string;
+};
+
 export type InstallerConfigLike = {
   source?: InstallerSource;
   installSteps: InstallerInstallAction[];
@@ -39,6 +52,8 @@ export type InstallerRunnableItem = {
   id: string;
   installer?: Installer;
   latestVersion?: string;
+  // 指定した場合は最新版ではなくこのバージョンをインストールする
+  installVersion?: string;
 };
 
 export type SetDetectedOneAction = {
@@ -1,6 +1,7 @@
 import { i18n } from '@/i18n';
 import { assertNever, formatUnknownError } from '../errors';
 import { removeInstalledId } from '../installed-map';
+import { ipc } from '../invokeIpc';
 import { bestEffortLogError, logInfo } from '../logging';
 import { recordPackageStateEvent } from '../package-state';
 import { executeDeleteAction, executeRunAction, syncDetectedVersionWithDispatch } from './actions';
@@ -23,6 +24,7 @@ export async function runUninstallerForItem(
     downloadPath: '',
   };
   const uninstallSteps = installer.uninstallSteps;
+  let keptFiles = 0;
   try {
     await logInfo(`[uninstall ${item.id}] start steps=${uninstallSteps.length}`);
     for (let i = 0; i < uninstallSteps.length; i++) {
@@ -76,12 +78,29 @@ export async function runUninstallerForItem(
         throw new Error(msg, { cause: e });
       }
     }
+    // カタログの手順で消しきれなかった、インストール時に記録したファイルを削除する
+    const report = await ipc.uninstallPackage({ packageId: item.id, force: false });
+    await logInfo(
+      `[uninstall ${item.id}] recorded files removed=${report.removed.length} skipped=${report.skippedModified.length} missing=${report.missing.length}`,
+    );
+    for (const path of report.skippedModified) {
+      await logInfo(`[uninstall ${item.id}] kept modified file ${path}`);
+    }
+    for (const error of report.errors) {
+      await bestEffortLogError(`[uninstall ${item.id}] failed to remove ${error}`);
+    }
+    keptFiles = report.kept.length;
   } catch (e: unknown) {
     const detail = formatUnknownError(e);
     await bestEffortLogError(`[uninstall ${item.id}] error: ${detail}`);
     throw e;
   }
-  await removeInstalledId(item.id);
+  // 残したファイルがあれば、後から削除できるようにインストール記録を残す
+  if (keptFiles === 0) {
+    await removeInstalledId(item.id);
+  } else {
+    await logInfo(`[uninstall ${item.id}] keeping install record for ${keptFiles} remaining files`);
+  }
   await syncDetectedVersionWithDispatch(item, dispatch);
   try {
     await recordPackageStateEvent('uninstall', item.id);
@@ -1,6 +1,153 @@
 import * as tauriCore from '@tauri-apps/api/core';
 import type { DeviceInfo } from './diagnostics/types';
-import type { DetectResultMap } from './detectResult';
+import type { DetectResult, DetectResultMap } from './detectResult';
+import type { InstallJob, InstallJobRequest, JobKind } from './installer/jobs';
+
+export type OverlapPolicy = 'warn' | 'block';
+
+export type FileOverlap = {
+  path: string;
+  owners: { packageId: string; recorded: boolean; catalog: boolean }[];
+};
+
+export type SnapshotInfo = {
+  id: string;
+  path: string;
+  createdAt: string;
+  label: string | null;
+  includeRoot: boolean;
+  fileCount: number;
+  totalBytes: number;
+  size: number;
+};
+
+export type InstallVerification =
+  | { status: 'verified'; version: string }
+  | {
+      status: 'mismatch';
+      expectedVersion: string;
+      detectedVersion: string | null;
+      files: { path: string; expected: string; actual: string | null }[];
+    }
+  | { status: 'unverifiable'; reason: string };
+
+export type InstallVerificationReport = {
+  verification: InstallVerification;
+  detected: DetectResult | null;
+};
+
+export type EntrySelection = {
+  subpath?: string;
+  stripComponents?: number;
+  include?: string[];
+  exclude?: string[];
+};
+
+export type ExtractOptions = EntrySelection & {
+  unsafeEntries?: 'reject' | 'skip';
+  // zip のエントリ名の文字コード（'auto' / 'utf-8' / 'cp932' / 'cp437' など。省略時は自動判定）
+  encoding?: string | null;
+  // 暗号化された zip / 7z のパスワード
+  password?: string | null;
+  limits?: Partial<ExtractLimits>;
+  nested?: NestedExtract | null;
+  onLocked?: LockedFilePolicy;
+};
+
+// 展開結果に含まれる書庫を続けて展開する（maxDepth の既定値は 1）
+export type NestedExtract = {
+  pattern: string;
+  maxDepth?: number;
+};
+
+// 展開量の上限（省略した項目は既定値: 8 GiB / 100000 エントリ / 2000 倍 / 64 階層）
+export type ExtractLimits = {
+  maxTotalBytes: number;
+  maxEntries: number;
+  maxRatio: number;
+  maxDepth: number;
+};
+
+export type ExtractReport = {
+  files: { entry: string; path: string; size: number }[];
+  directories: string[];
+  rejected: {
+    entry: string;
+    reason: 'absolutePath' | 'driveLetter' | 'parentTraversal' | 'invalidName' | 'symlink';
+  }[];
+  filteredOut: number;
+  nested: { entry: string; format: ArchiveFormat; path: string; depth: number }[];
+  deferred: DeferredFile[];
+};
+
+export type CopyPolicy = 'overwrite' | 'skipExisting' | 'onlyIfNewer' | 'backupThenOverwrite' | 'failOnConflict';
+
+export type CopyOptions = {
+  policy?: CopyPolicy;
+  include?: string[];
+  exclude?: string[];
+  // コピー元の更新日時をコピー先に引き継ぐ
+  preserveMtime?: boolean;
+  onLocked?: LockedFilePolicy;
+};
+
+// 上書きするファイルが起動中の AviUtl2 などに使われている場合の扱い（省略時は renameAside）
+export type LockedFilePolicy = 'renameAside' | 'waitForExit' | 'fail';
+
+// renamedTo は別名にした古いファイル（次回起動時に削除する）。null は AviUtl2 の終了を待ってから置き換えたもの
+export type DeferredFile = { path: string; renamedTo: string | null };
+
+export type RollbackReport = { packageId: string; restored: number; removed: number; errors: string[] };
+
+export type PendingInstallJournal = { packageId: string; startedAt: string; entries: number };
+
+export type CopyReport = {
+  created: string[];
+  // previousHash は上書き前の内容の xxh3-128、backup は backupThenOverwrite で残したファイル
+  overwritten: { path: string; previousHash: string; backup: string | null }[];
+  skipped: string[];
+  filteredOut: number;
+  deferred: DeferredFile[];
+};
+
+export type ArchiveFormat = 'zip' | 'zipSfx' | 'sevenZip' | 'sevenZipSfx' | 'tar' | 'tarGz' | 'tarZst' | 'lzh';
+
+export type ArchiveExtraction = ExtractReport & { format: ArchiveFormat };
+
+export type ArchiveTestReport = { format: ArchiveFormat; entries: number; bytes: number };
+
+export type ArchiveEntryInfo = {
+  name: string;
+  path: string | null;
+  isDir: boolean;
+  size: number;
+  compressedSize: number;
+  crc32: number | null;
+  modified: string | null;
+  encrypted: boolean;
+};
+
+export type ArchiveListing = {
+  format: ArchiveFormat;
+  entries: ArchiveEntryInfo[];
+  commonRoot: string | null;
+};
+
+export type ExtractProgress = {
+  entries: number;
+  totalEntries: number;
+  bytes: number;
+  totalBytes: number;
+};
+
+export type GithubReleaseAsset = {
+  url: string;
+  name: string;
+  size: number;
+  tag: string;
+  prerelease: boolean;
+  publishedAt: string | null;
+};
 
 type CommandSpec<Args = void, Result = unknown> = {
   args: Args;
@@ -13,12 +160,42 @@ type InvokeIpcMap = {
   setCatalogIndex: CommandSpec<{ items: unknown[] }, void>;
   writeNiconiCommonsIds: CommandSpec<{ payload: unknown }, void>;
   getInstalledMapCmd: CommandSpec<void, unknown>;
-  addInstalledIdCmd: CommandSpec<{ id: string; version: string }, void>;
+  addInstalledIdCmd: CommandSpec<
+    {
+      id: string;
+      version: string;
+      details: { sourceType?: string; sourceUrl?: string; detected?: unknown } | null;
+    },
+    void
+  >;
+  syncInstalledSnapshotCmd: CommandSpec<{ snapshot: Record<string, string> }, Record<string, string>>;
+  getInstalledState: CommandSpec<void, unknown>;
+  verifyInstalledPackage: CommandSpec<{ packageId: string; version?: string | null }, InstallVerificationReport>;
   removeInstalledIdCmd: CommandSpec<{ id: string }, void>;
+  getInstalledFiles: CommandSpec<{ packageId: string }, { path: string; hash: string }[]>;
+  uninstallPackage: CommandSpec<
+    { packageId: string; force?: boolean },
+    {
+      removed: string[];
+      skippedModified: string[];
+      missing: string[];
+      prunedDirs: string[];
+      errors: string[];
+      kept: string[];
+    }
+  >;
   detectVersionsMap: CommandSpec<{ items: unknown[] }, DetectResultMap | null>;
-  downloadFileToPath: CommandSpec<{ url: string; destPath: string; taskId: string }, string>;
+  downloadFileToPath: CommandSpec<
+    { url: string; destPath: string; taskId: string; expectedSize?: number | null },
+    string
+  >;
   driveDownloadToFile: CommandSpec<{ fileId: string; destPath: string }, string>;
   ensureBoothAuthWindow: CommandSpec<void, void>;
+  resolveGithubRelease: CommandSpec<
+    { owner: string; repo: string; pattern: string; version?: string | null },
+    GithubReleaseAsset
+  >;
+  setGithubApiConfig: CommandSpec<{ baseUrl?: string | null; token?: string | null }, void>;
   closeBoothAuthWindow: CommandSpec<void, void>;
   downloadFileToPathBooth: CommandSpec<
     { url: string; destPath: string; taskId: string; sessionWindowLabel: string },
@@ -26,10 +203,96 @@ type InvokeIpcMap = {
   >;
   runInstallerExecutable: CommandSpec<{ exePath: string; args: string[]; elevate: boolean }, void>;
   runAuoSetup: CommandSpec<{ exePath: string }, void>;
-  extractZip: CommandSpec<{ zipPath: string; destPath: string }, void>;
-  listZipEntries: CommandSpec<{ zipPath: string }, string[]>;
-  extract7zSfx: CommandSpec<{ sfxPath: string; destPath: string }, void>;
-  copyItemJs: CommandSpec<{ srcStr: string; dstStr: string }, unknown>;
+  extractZip: CommandSpec<
+    { zipPath: string; destPath: string; options?: ExtractOptions | null; taskId?: string | null },
+    ExtractReport
+  >;
+  listZipEntries: CommandSpec<{ zipPath: string; encoding?: string | null }, string[]>;
+  listArchiveEntries: CommandSpec<{ archivePath: string; encoding?: string | null }, ArchiveListing>;
+  extract7zSfx: CommandSpec<
+    { sfxPath: string; destPath: string; options?: ExtractOptions | null; taskId?: string | null },
+    ExtractReport
+  >;
+  cancelExtract: CommandSpec<{ taskId: string }, boolean>;
+  extractLzh: CommandSpec<
+    { lzhPath: string; destPath: string; options?: ExtractOptions | null; taskId?: string | null },
+    ExtractReport
+  >;
+  listLzhEntries: CommandSpec<{ lzhPath: string }, string[]>;
+  testArchive: CommandSpec<
+    { archivePath: string; password?: string | null; limits?: Partial<ExtractLimits> | null; taskId?: string | null },
+    ArchiveTestReport
+  >;
+  extractArchive: CommandSpec<
+    { archivePath: string; destPath: string; options?: ExtractOptions | null; taskId?: string | null },
+    ArchiveExtraction
+  >;
+  copyItemJs: CommandSpec<{ srcStr: string; dstStr: string; options?: CopyOptions | null }, CopyReport>;
+  deleteItemJs: CommandSpec<{ pathStr: string }, boolean>;
+  beginInstallTransaction: CommandSpec<{ packageId: string; overlapPolicy?: OverlapPolicy | null }, void>;
+  commitInstallTransaction: CommandSpec<
+    void,
+    {
+      packageId: string;
+      writtenFiles: string[];
+      removedFiles: string[];
+      overlaps: FileOverlap[];
+      deferredFiles: DeferredFile[];
+    }
+  >;
+  rollbackInstallTransaction: CommandSpec<void, RollbackReport>;
+  getPendingInstallJournal: CommandSpec<void, PendingInstallJournal | null>;
+  resolveInstallJournal: CommandSpec<{ rollback: boolean }, RollbackReport>;
+  setCatalogInstall: CommandSpec<{ packages: Record<string, unknown> }, number>;
+  setCatalogVersions: CommandSpec<{ packages: Record<string, unknown> }, number>;
+  listFileOverlaps: CommandSpec<void, FileOverlap[]>;
+  checkFileOwnership: CommandSpec<
+    { packageId: string; version?: string | null; paths?: string[] | null },
+    FileOverlap[]
+  >;
+  resolveInstallPlan: CommandSpec<{ packageIds: string[]; installed?: Record<string, string> | null }, unknown>;
+  enqueueJobs: CommandSpec<{ jobs: InstallJobRequest[] }, InstallJob[]>;
+  listJobs: CommandSpec<void, InstallJob[]>;
+  cancelJob: CommandSpec<{ taskId: string }, InstallJob>;
+  retryJob: CommandSpec<{ taskId: string }, InstallJob>;
+  clearFinishedJobs: CommandSpec<void, number>;
+  setDownloadConcurrency: CommandSpec<{ limit: number }, number>;
+  createSnapshot: CommandSpec<{ includeRoot?: boolean | null; label?: string | null }, SnapshotInfo>;
+  listSnapshots: CommandSpec<void, SnapshotInfo[]>;
+  restoreSnapshot: CommandSpec<
+    { id: string },
+    {
+      id: string;
+      restored: number;
+      removed: number;
+      installedPackages: number;
+      safetySnapshot: string | null;
+      errors: string[];
+    }
+  >;
+  pruneSnapshots: CommandSpec<{ keep?: number | null; maxAgeDays?: number | null }, string[]>;
+  exportEnvironmentLock: CommandSpec<
+    { path: string; format?: 'json' | 'toml' | null },
+    { path: string; format: 'json' | 'toml'; packages: number; files: number }
+  >;
+  applyEnvironmentLock: CommandSpec<
+    { path: string; dryRun?: boolean | null },
+    {
+      actions: {
+        packageId: string;
+        kind: JobKind;
+        reason: 'missing' | 'versionMismatch' | 'modified' | 'notLocked' | 'replaced';
+        lockedVersion: string | null;
+        installedVersion: string | null;
+      }[];
+      unavailable: { packageId: string; lockedVersion: string; availableVersion: string | null }[];
+      unchanged: string[];
+      unmanaged: string[];
+      jobs: InstallJob[];
+    }
+  >;
+  planInstall: CommandSpec<{ packageId: string; version?: string | null; archivePath?: string | null }, unknown>;
+  planUninstall: CommandSpec<{ packageId: string; force?: boolean | null }, unknown>;
   getAppDirs: CommandSpec<
     void,
     {
@@ -56,6 +319,7 @@ type InvokeIpcMap = {
     void
   >;
   setPackageUpdatePaused: CommandSpec<{ packageId: string; paused: boolean }, string[]>;
+  setPackageVersionHold: CommandSpec<{ packageId: string; version: string | null }, Record<string, string>>;
   dismissDeprecatedPackageNotice: CommandSpec<{ packageIds: string[] }, string[]>;
   completeInitialSetup: CommandSpec<void, void>;
   calcXxh3Hex: CommandSpec<{ path: string }, string>;
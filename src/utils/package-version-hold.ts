@@ -0,0 +1,30 @@
+import type { CatalogDispatch } from './catalogStore';
+import { ipc } from './invokeIpc';
+import { getSettings } from './settings';
+
+function normalizeVersionHolds(raw: unknown): Record<string, string> {
+  if (!raw || typeof raw !== 'object') return {};
+  const out: Record<string, string> = {};
+  Object.entries(raw as Record<string, unknown>).forEach(([id, version]) => {
+    const key = id.trim();
+    const value = String(version || '').trim();
+    if (key && value) out[key] = value;
+  });
+  return out;
+}
+
+export async function loadPackageVersionHolds(): Promise<Record<string, string>> {
+  const settings = await getSettings();
+  return normalizeVersionHolds(settings.package_version_holds);
+}
+
+// version に null を渡すと固定を解除する
+export async function persistPackageVersionHold(
+  packageId: string,
+  version: string | null,
+  dispatch?: CatalogDispatch | null,
+): Promise<Record<string, string>> {
+  const holds = normalizeVersionHolds(await ipc.setPackageVersionHold({ packageId, version }));
+  dispatch?.({ type: 'SET_VERSION_HOLDS', payload: holds });
+  return holds;
+}
@@ -0,0 +1,90 @@
+import { useSyncExternalStore } from 'react';
+import * as tauriEvent from '@tauri-apps/api/event';
+import { formatUnknownError } from './errors';
+import { type InstallJob, JOB_UPDATE_EVENT } from './installer/jobs';
+import { ipc } from './invokeIpc';
+import { logError } from './logging';
+
+export interface UseInstallJobsResult {
+  jobs: InstallJob[];
+  cancelJob: (taskId: string) => Promise<void>;
+  retryJob: (taskId: string) => Promise<void>;
+  clearFinishedJobs: () => Promise<void>;
+}
+
+// ジョブ一覧はバックエンドが持っているので、画面を開き直しても listJobs から取り直せる
+let storeJobs: InstallJob[] = [];
+let storeInitialized = false;
+const storeListeners = new Set<() => void>();
+
+function emitStoreChange(): void {
+  storeListeners.forEach((listener) => {
+    listener();
+  });
+}
+
+// 古い通知で新しい状態を上書きしないよう、更新日時が新しい方を残す
+function mergeJob(job: InstallJob): void {
+  const index = storeJobs.findIndex((current) => current.taskId === job.taskId);
+  if (index >= 0 && storeJobs[index].updatedAt > job.updatedAt) return;
+  storeJobs = index >= 0 ? storeJobs.map((current, i) => (i === index ? job : current)) : [...storeJobs, job];
+  emitStoreChange();
+}
+
+async function reloadJobs(): Promise<void> {
+  try {
+    const latest = await ipc.listJobs();
+    const known = new Map(storeJobs.map((job) => [job.taskId, job]));
+    storeJobs = latest.map((job) => {
+      const current = known.get(job.taskId);
+      return current && current.updatedAt > job.updatedAt ? current : job;
+    });
+    emitStoreChange();
+  } catch (e: unknown) {
+    await logError(`[useInstallJobs] list jobs failed: ${formatUnknownError(e)}`);
+  }
+}
+
+function ensureStoreInitialized(): void {
+  if (storeInitialized) return;
+  storeInitialized = true;
+  void tauriEvent
+    .listen<InstallJob>(JOB_UPDATE_EVENT, (evt) => {
+      if (evt?.payload) mergeJob(evt.payload);
+    })
+    .then(reloadJobs, async (e: unknown) => {
+      storeInitialized = false;
+      await logError(`[useInstallJobs] listen failed: ${formatUnknownError(e)}`);
+    });
+}
+
+function subscribeStore(listener: () => void): () => void {
+  ensureStoreInitialized();
+  storeListeners.add(listener);
+  return () => {
+    storeListeners.delete(listener);
+  };
+}
+
+function getStoreSnapshot(): InstallJob[] {
+  return storeJobs;
+}
+
+async function cancelJob(taskId: string): Promise<void> {
+  mergeJob(await ipc.cancelJob({ taskId }));
+}
+
+// 受け付けられなかった要求はジョブ一覧に載らないので、ここからは再試行できない（要求し直す）
+async function retryJob(taskId: string): Promise<void> {
+  mergeJob(await ipc.retryJob({ taskId }));
+}
+
+async function clearFinishedJobs(): Promise<void> {
+  await ipc.clearFinishedJobs();
+  await reloadJobs();
+}
+
+export default function useInstallJobs(): UseInstallJobsResult {
+  const jobs = useSyncExternalStore(subscribeStore, getStoreSnapshot, getStoreSnapshot);
+  return { jobs, cancelJob, retryJob, clearFinishedJobs };
+}
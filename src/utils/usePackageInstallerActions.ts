@@ -4,7 +4,8 @@ import { useTranslation } from 'react-i18next';
 import { resolveInstallableCatalogItem } from './catalogInstallItem';
 import type { CatalogDispatch } from './catalogStore';
 import { loadPackageNoticeContent } from './packageNotice';
-import { runPackageInstallAction, runPackageRemoveAction } from './installer';
+import { persistPackageVersionHold } from './package-version-hold';
+import { runPackageInstallAction, runPackageInstallVersionAction, runPackageRemoveAction } from './installer';
 import type { InstallProgressPayload, InstallerRunnableItem } from './installer/types';
 import useExclusiveBusyAction from './useExclusiveBusyAction';
 
@@ -32,6 +33,10 @@ export interface UsePackageInstallerActionsResult {
   onDownload: () => Promise<void>;
   onUpdate: () => Promise<void>;
   onRemove: () => Promise<void>;
+  // 指定したバージョンをインストールする（hold なら続けてそのバージョンに固定する）
+  onInstallVersion: (version: string, hold?: boolean) => Promise<void>;
+  // version に null を渡すと固定を解除する
+  onSetHold: (version: string | null) => Promise<void>;
 }
 
 function toErrorMessage(error: unknown): string {
@@ -77,7 +82,11 @@ export default function usePackageInstallerActions({
   }, [t]);
 
   const runInstall = useCallback(
-    async (action: Extract<PackageInstallBusyAction, 'download' | 'update'>, actionLabel: string) => {
+    async (
+      action: Extract<PackageInstallBusyAction, 'download' | 'update'>,
+      actionLabel: string,
+      target?: { version: string; hold: boolean },
+    ) => {
       if (!item) return;
       if (!beginAction(action)) return;
 
@@ -87,14 +96,17 @@ export default function usePackageInstallerActions({
         if (!resolvedItem) {
           throw new Error(resolvedMissingInstallerMessage);
         }
-        await runPackageInstallAction(
-          resolvedItem,
-          dispatch,
-          (nextProgress) => {
-            setProgress(nextProgress ?? createInitialInstallProgress(t('common:status.preparing')));
-          },
-          resolvedMissingInstallerMessage,
-        );
+        const onProgress = (nextProgress: InstallProgressPayload) => {
+          setProgress(nextProgress ?? createInitialInstallProgress(t('common:status.preparing')));
+        };
+        if (target) {
+          await runPackageInstallVersionAction(resolvedItem, target.version, dispatch, {
+            hold: target.hold,
+            onProgress,
+          });
+        } else {
+          await runPackageInstallAction(resolvedItem, dispatch, onProgress, resolvedMissingInstallerMessage);
+        }
       } catch (installError) {
         setError(t('package:errors.actionFailed', { action: actionLabel, detail: toErrorMessage(installError) }));
       } finally {
@@ -148,6 +160,27 @@ export default function usePackageInstallerActions({
     await runInstall('update', t('package:actions.update'));
   }, [runInstall, t]);
 
+  const onInstallVersion = useCallback(
+    async (version: string, hold = false) => {
+      const installed = Boolean(item && 'installed' in item && item.installed);
+      await runInstall(installed ? 'update' : 'download', t('package:actions.installVersion'), { version, hold });
+    },
+    [item, runInstall, t],
+  );
+
+  const onSetHold = useCallback(
+    async (version: string | null) => {
+      if (!item) return;
+      try {
+        await persistPackageVersionHold(item.id, version, dispatch);
+      } catch (holdError) {
+        const action = version ? t('package:actions.hold') : t('package:actions.releaseHold');
+        setError(t('package:errors.actionFailed', { action, detail: toErrorMessage(holdError) }));
+      }
+    },
+    [dispatch, item, t],
+  );
+
   const onRemove = useCallback(async () => {
     if (!item) return;
     if (!beginAction('remove')) return;
@@ -177,5 +210,7 @@ export default function usePackageInstallerActions({
     onDownload,
     onUpdate,
     onRemove,
+    onInstallVersion,
+    onSetHold,
   };
 }